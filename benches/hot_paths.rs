@@ -0,0 +1,105 @@
+//! Benchmarks of the paths every request goes through: rendering Markdown, scanning
+//! the articles directory, the article cache, and listing and searching the index.
+//!
+//! Run with `cargo bench --features testing`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use henkaiki::{
+    articles::Articles,
+    config::{Config, Extensions},
+    markdown::MarkdownConverter,
+    search::SearchFields,
+    testing::ArticleTree,
+};
+
+/// The sizes of the article trees the index benchmarks run against.
+const TREE_SIZES: [usize; 2] = [100, 1000];
+
+/// The built-in sample article: long, with headings, lists and paragraphs.
+const SAMPLE_MARKDOWN: &str = include_str!("../src/udhr.md");
+
+fn tree_with(count: usize) -> (ArticleTree, Articles) {
+    let tree = ArticleTree::new().expect("create article tree");
+    tree.generate(count).expect("generate articles");
+    let articles = tree.articles().expect("load articles");
+    (tree, articles)
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    let mut none = Config::defaults();
+    none.extensions = Extensions::default();
+    let all = Config::defaults();
+    for (name, config) in [("no_extensions", &none), ("all_extensions", &all)] {
+        group.bench_function(name, |b| b.iter(|| SAMPLE_MARKDOWN.to_html_with_config(config)));
+    }
+    group.finish();
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    for count in TREE_SIZES {
+        let (_tree, articles) = tree_with(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &articles, |b, articles| {
+            b.iter(|| articles.refresh_index().expect("refresh index"))
+        });
+    }
+    group.finish();
+}
+
+fn cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cache");
+    let (_tree, articles) = tree_with(100);
+    articles.get_article(1).expect("warm the cache");
+    group.bench_function("hit", |b| b.iter(|| articles.get_article(1).expect("get article")));
+    group.bench_function("miss", |b| {
+        b.iter_batched(
+            || articles.clear_cache(),
+            |_| articles.get_article(1).expect("get article"),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn listing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("listing");
+    for count in TREE_SIZES {
+        let (_tree, articles) = tree_with(count);
+        let last_page = articles.get_article_summary_page_count(10) - 1;
+        group.bench_with_input(BenchmarkId::new("page", count), &articles, |b, articles| {
+            b.iter(|| articles.list_article_summaries_paginated(10, last_page).expect("list page"))
+        });
+        group.bench_with_input(BenchmarkId::new("tag", count), &articles, |b, articles| {
+            b.iter(|| articles.list_article_summaries_by_tag_paginated("even", 10, 0).expect("list tag"))
+        });
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    for count in TREE_SIZES {
+        let (_tree, articles) = tree_with(count);
+        // Applying the configuration drops the cached results, so each search runs;
+        // it has to happen right before every iteration, not for a batch up front
+        group.bench_with_input(BenchmarkId::new("uncached", count), &articles, |b, articles| {
+            b.iter_batched(
+                || articles.apply_config(),
+                |_| articles.search_articles("article 5", SearchFields::ALL, &[]).expect("search"),
+                BatchSize::PerIteration,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("next_page", count), &articles, |b, articles| {
+            b.iter(|| {
+                articles
+                    .search_articles_paginated("article 5", SearchFields::ALL, &[], 10, 1)
+                    .expect("search page")
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render, scan, cache, listing, search);
+criterion_main!(benches);
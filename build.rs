@@ -0,0 +1,109 @@
+fn main() {
+    emit_build_info();
+    #[cfg(feature = "grpc")]
+    compile_grpc_service();
+}
+
+/// Pass the git commit, build time and enabled features to the crate as
+/// `HENKAIKI_*` environment variables, reported by `henkaiki::build_info`.
+fn emit_build_info() {
+    use std::{
+        env,
+        process::Command,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Honour SOURCE_DATE_EPOCH so reproducible builds get a fixed timestamp
+    let timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0)
+        });
+
+    let mut features = env::vars()
+        .filter_map(|(key, _)| {
+            let feature = key.strip_prefix("CARGO_FEATURE_")?;
+            (feature != "DEFAULT").then(|| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect::<Vec<_>>();
+    features.sort();
+
+    println!("cargo:rustc-env=HENKAIKI_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=HENKAIKI_BUILD_TIMESTAMP={}", format_utc(timestamp));
+    println!("cargo:rustc-env=HENKAIKI_FEATURES={}", features.join(","));
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+fn format_utc(secs: u64) -> String {
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60
+    )
+}
+
+/// Generate the server side of the `henkaiki.v1.Articles` service described in
+/// `proto/henkaiki.proto`. The messages are defined in `src/grpc.rs`, so this
+/// doesn't need `protoc`.
+#[cfg(feature = "grpc")]
+fn compile_grpc_service() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route_name: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("super::{}", input))
+            .output_type(format!("super::{}", output))
+            .codec_path("tonic_prost::ProstCodec")
+            .build()
+    };
+    let service = Service::builder()
+        .name("Articles")
+        .package("henkaiki.v1")
+        .method(method("get_article", "GetArticle", "GetArticleRequest", "Article"))
+        .method(method("list_articles", "ListArticles", "ListArticlesRequest", "ArticleList"))
+        .method(method("search_articles", "SearchArticles", "SearchArticlesRequest", "ArticleList"))
+        .method(method(
+            "list_articles_by_tag",
+            "ListArticlesByTag",
+            "ListArticlesByTagRequest",
+            "ArticleList",
+        ))
+        .build();
+
+    Builder::new().build_client(false).compile(&[service]);
+}
@@ -0,0 +1,517 @@
+use actix_web::{
+    get, post, rt,
+    web::{self, Data, Path, Query},
+    HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use log::*;
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::{DecodePrivateKey, EncodePublicKey, LineEnding},
+    RsaPrivateKey,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    api::error_response,
+    articles::{ArticleId, Articles, ListingFilter, ListingOrder},
+    config,
+    error::Error,
+    events::ContentEvent,
+    outbound,
+};
+
+mod signature;
+
+const ACTIVITY_JSON: &str = "application/activity+json";
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+/// How many of the newest articles the outbox shows.
+const OUTBOX_SIZE: usize = 20;
+/// How long a request to another server may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The blog's ActivityPub actor: its identity, signing key and followers.
+pub struct Federation {
+    base_url: String,
+    /// Where the `/ap` routes are reached: below `base_url` and the `path_prefix`.
+    ap_url: String,
+    username: String,
+    display_name: String,
+    summary: String,
+    key: RsaPrivateKey,
+    public_key_pem: String,
+    followers: Followers,
+    http: ureq::Agent,
+}
+
+impl Federation {
+    pub fn from_config(config: &config::ActivityPub, path_prefix: &str) -> Result<Self> {
+        let pem = fs::read_to_string(&config.private_key_path)
+            .with_context(|| format!("Failed to read {}", config.private_key_path))?;
+        let key = RsaPrivateKey::from_pkcs8_pem(&pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+            .with_context(|| format!("{} is not a PEM RSA private key", config.private_key_path))?;
+        let public_key_pem = key.to_public_key().to_public_key_pem(LineEnding::LF)?;
+        let http = outbound::agent(
+            ureq::Agent::config_builder()
+                .timeout_global(Some(REQUEST_TIMEOUT))
+                .build(),
+        );
+
+        let base_url = config.base_url.trim_end_matches('/').to_string();
+        Ok(Federation {
+            ap_url: format!("{}{}/ap", base_url, path_prefix),
+            base_url,
+            username: config.username.clone(),
+            display_name: config
+                .display_name
+                .clone()
+                .unwrap_or_else(|| config.username.clone()),
+            summary: config.summary.clone(),
+            key,
+            public_key_pem,
+            followers: Followers::load(PathBuf::from(&config.followers_path))?,
+            http,
+        })
+    }
+
+    fn actor_id(&self) -> String {
+        format!("{}/actor", self.ap_url)
+    }
+
+    fn object_id(&self, article_id: ArticleId) -> String {
+        format!("{}/articles/{}", self.ap_url, article_id)
+    }
+
+    /// The host name in the actor's `acct:` URI.
+    fn domain(&self) -> &str {
+        let rest = self
+            .base_url
+            .split_once("://")
+            .map_or(&*self.base_url, |(_, rest)| rest);
+        rest.split('/').next().unwrap_or(rest)
+    }
+
+    fn actor(&self) -> Value {
+        let actor_id = self.actor_id();
+        json!({
+            "@context": ["https://www.w3.org/ns/activitystreams", "https://w3id.org/security/v1"],
+            "id": actor_id,
+            "type": "Person",
+            "preferredUsername": self.username,
+            "name": self.display_name,
+            "summary": self.summary,
+            "url": self.base_url,
+            "inbox": format!("{}/inbox", self.ap_url),
+            "outbox": format!("{}/outbox", self.ap_url),
+            "followers": format!("{}/followers", self.ap_url),
+            "publicKey": {
+                "id": format!("{}#main-key", actor_id),
+                "owner": actor_id,
+                "publicKeyPem": self.public_key_pem,
+            },
+        })
+    }
+
+    /// The article as an ActivityStreams `Article` object. Private articles aren't federated.
+    fn article_object(&self, articles: &Articles, article_id: ArticleId) -> Result<Value> {
+        if articles.is_private(article_id) {
+            bail!(Error::NotFound(article_id));
+        }
+        let (article, _) = articles.get_article(article_id)?;
+        let date = article.date;
+        Ok(json!({
+            "id": self.object_id(article.id),
+            "type": "Article",
+            "attributedTo": self.actor_id(),
+            "name": &*article.title,
+            "summary": &*article.description,
+            "content": &*article.content,
+            "published": format!("{:04}-{:02}-{:02}T00:00:00Z", date / 10000, date / 100 % 100, date % 100),
+            "url": article.canonical_url.as_deref().map_or_else(|| self.object_id(article.id), str::to_string),
+            "tag": article.tags.iter().map(|tag| json!({"type": "Hashtag", "name": format!("#{}", tag)})).collect::<Vec<_>>(),
+            "to": [PUBLIC],
+            "cc": [format!("{}/followers", self.ap_url)],
+        }))
+    }
+
+    /// The `Create` activity announcing an article.
+    fn create_activity(&self, object: Value) -> Value {
+        json!({
+            "id": format!("{}#create", object["id"].as_str().unwrap_or_default()),
+            "type": "Create",
+            "actor": self.actor_id(),
+            "published": object["published"].clone(),
+            "to": object["to"].clone(),
+            "cc": object["cc"].clone(),
+            "object": object,
+        })
+    }
+
+    /// Send a new article to every follower.
+    fn publish_article(&self, articles: &Articles, article_id: ArticleId) -> Result<()> {
+        let mut activity = self.create_activity(self.article_object(articles, article_id)?);
+        activity["@context"] = json!("https://www.w3.org/ns/activitystreams");
+        let inboxes = self.followers.inboxes();
+        info!(
+            "Publishing article {} to {} inbox(es)",
+            article_id,
+            inboxes.len()
+        );
+        for inbox in inboxes {
+            if let Err(e) = self.deliver(&inbox, &activity) {
+                warn!(
+                    "Failed to deliver article {} to {}: {:#}",
+                    article_id, inbox, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle an activity posted to the inbox: follows and unfollows are acted
+    /// upon, anything else is ignored.
+    fn receive(
+        &self,
+        path: &str,
+        headers: &actix_web::http::header::HeaderMap,
+        body: &[u8],
+    ) -> Result<()> {
+        let signer = signature::verify(path, headers, body, |key_id| self.fetch_key(key_id))?;
+        let activity: Value = serde_json::from_slice(body)
+            .map_err(|e| Error::InvalidRequest(format!("invalid activity: {}", e)))?;
+        if id_of(&activity["actor"]) != Some(signer.as_str()) {
+            bail!(Error::Unauthorized(
+                "the activity is not signed by its actor".into()
+            ));
+        }
+
+        let follows_us = |follow: &Value| {
+            follow["type"] == "Follow" && id_of(&follow["object"]) == Some(self.actor_id().as_str())
+        };
+        if follows_us(&activity) {
+            let follower = self.fetch(&signer)?;
+            let inbox = follower["endpoints"]["sharedInbox"]
+                .as_str()
+                .or_else(|| follower["inbox"].as_str())
+                .ok_or_else(|| anyhow!("{} has no inbox", signer))?
+                .to_string();
+            self.followers.add(&signer, &inbox)?;
+            info!("{} followed the blog", signer);
+
+            let accept = json!({
+                "@context": "https://www.w3.org/ns/activitystreams",
+                "id": format!("{}#accept-{}", self.actor_id(), signature_nonce(body)),
+                "type": "Accept",
+                "actor": self.actor_id(),
+                "object": activity,
+            });
+            self.deliver(&inbox, &accept)?;
+        } else if activity["type"] == "Undo" && follows_us(&activity["object"]) {
+            self.followers.remove(&signer)?;
+            info!("{} unfollowed the blog", signer);
+        } else {
+            debug!("Ignoring {} activity from {}", activity["type"], signer);
+        }
+        Ok(())
+    }
+
+    /// The PEM public key with ID `key_id`, and the actor it belongs to. The
+    /// actor's document has to name the key as its own: the key's `owner` alone
+    /// is whatever the server hosting the key says.
+    fn fetch_key(&self, key_id: &str) -> Result<(String, String)> {
+        let url = key_id.split('#').next().unwrap_or(key_id);
+        let document = self.fetch(url)?;
+        // The key is usually embedded in its owner's actor document
+        let key = if document["publicKeyPem"].is_string() {
+            &document
+        } else {
+            &document["publicKey"]
+        };
+        let pem = key["publicKeyPem"]
+            .as_str()
+            .ok_or_else(|| anyhow!("{} has no public key", key_id))?;
+        let owner = key["owner"]
+            .as_str()
+            .or_else(|| document["id"].as_str())
+            .ok_or_else(|| anyhow!("{} has no owner", key_id))?;
+        let fetched;
+        let actor = if owner == url {
+            &document
+        } else {
+            fetched = self.fetch(owner)?;
+            &fetched
+        };
+        if id_of(&actor["id"]) != Some(owner) || id_of(&actor["publicKey"]) != Some(key_id) {
+            bail!(Error::Unauthorized(format!(
+                "{} is not a key of {}",
+                key_id, owner
+            )));
+        }
+        Ok((pem.to_string(), owner.to_string()))
+    }
+
+    /// Fetch an ActivityStreams document, signing the request for servers that
+    /// require it.
+    fn fetch(&self, url: &str) -> Result<Value> {
+        let mut request = self.http.get(url).header("accept", ACTIVITY_JSON);
+        for (name, value) in signature::sign(&self.key, &self.key_id(), "GET", url, None)? {
+            request = request.header(name, value);
+        }
+        let mut response = request
+            .call()
+            .with_context(|| format!("Failed to fetch {}", url))?;
+        let body = response.body_mut().read_to_string()?;
+        serde_json::from_str(&body).with_context(|| format!("{} is not JSON", url))
+    }
+
+    /// Post a signed activity to an inbox.
+    fn deliver(&self, inbox: &str, activity: &Value) -> Result<()> {
+        let body = serde_json::to_vec(activity)?;
+        let mut request = self.http.post(inbox).header("content-type", ACTIVITY_JSON);
+        for (name, value) in signature::sign(&self.key, &self.key_id(), "POST", inbox, Some(&body))?
+        {
+            request = request.header(name, value);
+        }
+        request
+            .send(&body[..])
+            .with_context(|| format!("Failed to post to {}", inbox))?;
+        Ok(())
+    }
+
+    fn key_id(&self) -> String {
+        format!("{}#main-key", self.actor_id())
+    }
+}
+
+/// The ID of an object given either inline or by reference.
+fn id_of(value: &Value) -> Option<&str> {
+    value.as_str().or_else(|| value["id"].as_str())
+}
+
+/// A short, stable token identifying an incoming activity.
+fn signature_nonce(body: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(body)
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// ===== FOLLOWERS =====
+
+/// The actors following the blog, each with the inbox to deliver to, persisted
+/// as a JSON object.
+struct Followers {
+    path: PathBuf,
+    inboxes: Mutex<BTreeMap<String, String>>,
+}
+
+impl Followers {
+    fn load(path: PathBuf) -> Result<Self> {
+        let inboxes = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Invalid followers file {:?}", path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        };
+        Ok(Followers {
+            path,
+            inboxes: Mutex::new(inboxes),
+        })
+    }
+
+    fn add(&self, actor: &str, inbox: &str) -> Result<()> {
+        let mut inboxes = self.inboxes.lock().unwrap();
+        inboxes.insert(actor.to_string(), inbox.to_string());
+        self.save(&inboxes)
+    }
+
+    fn remove(&self, actor: &str) -> Result<()> {
+        let mut inboxes = self.inboxes.lock().unwrap();
+        inboxes.remove(actor);
+        self.save(&inboxes)
+    }
+
+    fn count(&self) -> usize {
+        self.inboxes.lock().unwrap().len()
+    }
+
+    /// Every distinct inbox; followers on the same server often share one.
+    fn inboxes(&self) -> Vec<String> {
+        let mut inboxes: Vec<_> = self.inboxes.lock().unwrap().values().cloned().collect();
+        inboxes.sort_unstable();
+        inboxes.dedup();
+        inboxes
+    }
+
+    fn save(&self, inboxes: &BTreeMap<String, String>) -> Result<()> {
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(inboxes)?)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+// ===== ROUTES =====
+
+#[derive(Deserialize)]
+struct WebfingerParams {
+    resource: String,
+}
+
+/// Resolves `acct:{username}@{domain}` to the actor
+#[get("/.well-known/webfinger")]
+async fn webfinger(federation: Data<Federation>, query: Query<WebfingerParams>) -> impl Responder {
+    let account = format!("acct:{}@{}", federation.username, federation.domain());
+    if !query.resource.eq_ignore_ascii_case(&account) && query.resource != federation.actor_id() {
+        return Error::InvalidRequest(format!("unknown resource {}", query.resource))
+            .error_response();
+    }
+    HttpResponse::Ok()
+        .content_type("application/jrd+json")
+        .json(json!({
+            "subject": account,
+            "aliases": [federation.actor_id()],
+            "links": [{
+                "rel": "self",
+                "type": ACTIVITY_JSON,
+                "href": federation.actor_id(),
+            }],
+        }))
+}
+
+#[get("/ap/actor")]
+async fn get_actor(federation: Data<Federation>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ACTIVITY_JSON)
+        .json(federation.actor())
+}
+
+/// Lists the `Create` activities of the newest articles
+#[get("/ap/outbox")]
+async fn get_outbox(federation: Data<Federation>, articles_data: Data<Articles>) -> impl Responder {
+    let (federation, articles) = (federation.into_inner(), articles_data.into_inner());
+    let result = web::block(move || -> Result<Value> {
+        let page = articles.list_article_summaries_page(
+            &ListingFilter::default(),
+            ListingOrder::Newest,
+            None,
+            OUTBOX_SIZE,
+        );
+        let mut items = Vec::new();
+        for summary in page.items {
+            match federation.article_object(&articles, summary.id) {
+                Ok(object) => items.push(federation.create_activity(object)),
+                Err(e) => warn!("Leaving article {} out of the outbox: {:#}", summary.id, e),
+            }
+        }
+        Ok(json!({
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "id": format!("{}/outbox", federation.ap_url),
+            "type": "OrderedCollection",
+            "totalItems": page.total,
+            "orderedItems": items,
+        }))
+    })
+    .await;
+    match result {
+        Ok(Ok(outbox)) => HttpResponse::Ok().content_type(ACTIVITY_JSON).json(outbox),
+        Ok(Err(e)) => error_response(e, "Failed to build the outbox"),
+        Err(e) => {
+            error!("Outbox task failed: {:?}", e);
+            Error::from(e).response("Failed to build the outbox")
+        }
+    }
+}
+
+#[get("/ap/articles/{id}")]
+async fn get_article(
+    federation: Data<Federation>,
+    articles_data: Data<Articles>,
+    path: Path<ArticleId>,
+) -> impl Responder {
+    let article_id = path.into_inner();
+    match federation.article_object(&articles_data, article_id) {
+        Ok(mut object) => {
+            object["@context"] = json!("https://www.w3.org/ns/activitystreams");
+            HttpResponse::Ok().content_type(ACTIVITY_JSON).json(object)
+        }
+        Err(e) => error_response(e, &format!("Failed to load article {}", article_id)),
+    }
+}
+
+/// Shows how many followers there are, but not who they are
+#[get("/ap/followers")]
+async fn get_followers(federation: Data<Federation>) -> impl Responder {
+    HttpResponse::Ok().content_type(ACTIVITY_JSON).json(json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}/followers", federation.ap_url),
+        "type": "OrderedCollection",
+        "totalItems": federation.followers.count(),
+    }))
+}
+
+/// Accepts signed activities from other servers
+#[post("/ap/inbox")]
+async fn receive_activity(
+    req: HttpRequest,
+    federation: Data<Federation>,
+    body: web::Bytes,
+) -> impl Responder {
+    let federation = federation.into_inner();
+    let path = req.uri().path().to_string();
+    let headers = req.headers().clone();
+    match web::block(move || federation.receive(&path, &headers, &body)).await {
+        Ok(Ok(())) => HttpResponse::Accepted().finish(),
+        Ok(Err(e)) => error_response(e, "Failed to process activity"),
+        Err(e) => {
+            error!("Inbox task failed: {:?}", e);
+            Error::from(e).response("Failed to process activity")
+        }
+    }
+}
+
+/// Configures the ActivityPub routes
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(webfinger)
+        .service(get_actor)
+        .service(get_outbox)
+        .service(get_article)
+        .service(get_followers)
+        .service(receive_activity);
+}
+
+/// Spawn a background task sending each new article to the followers.
+pub fn spawn_publisher(federation: Arc<Federation>, articles: Articles) {
+    let mut events = articles.subscribe();
+    rt::spawn(async move {
+        loop {
+            let article_id = match events.recv().await {
+                Ok(ContentEvent::ArticleAdded { id }) => id,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("ActivityPub publisher missed {} content events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let (federation, articles) = (Arc::clone(&federation), articles.clone());
+            match web::block(move || federation.publish_article(&articles, article_id)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!("Failed to publish article {}: {:?}", article_id, e),
+                Err(e) => error!("Publishing task failed: {:?}", e),
+            }
+        }
+    });
+}
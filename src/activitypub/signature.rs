@@ -0,0 +1,152 @@
+use actix_web::http::header::HeaderMap;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rsa::{
+    pkcs1v15::{Signature, SigningKey, VerifyingKey},
+    pkcs8::DecodePublicKey,
+    signature::{SignatureEncoding, Signer, Verifier},
+    RsaPrivateKey, RsaPublicKey,
+};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime};
+
+use crate::error::Error;
+
+/// How far a signed request's `Date` may be from now.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// The headers to send with a request to `url` for it to carry an HTTP signature
+/// (draft-cavage-http-signatures, as used across the Fediverse) by `key`.
+pub fn sign(
+    key: &RsaPrivateKey,
+    key_id: &str,
+    method: &str,
+    url: &str,
+    body: Option<&[u8]>,
+) -> Result<Vec<(&'static str, String)>> {
+    let (host, path) = split_url(url).ok_or_else(|| anyhow!("invalid URL {:?}", url))?;
+    let date = httpdate::fmt_http_date(SystemTime::now());
+
+    let mut headers = vec![("host", host.to_string()), ("date", date)];
+    if let Some(body) = body {
+        headers.push(("digest", digest(body)));
+    }
+    let mut signing_string = format!("(request-target): {} {}", method.to_lowercase(), path);
+    for (name, value) in &headers {
+        signing_string.push_str(&format!("\n{}: {}", name, value));
+    }
+    let signature = SigningKey::<Sha256>::new(key.clone()).sign(signing_string.as_bytes());
+
+    let names = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(" ");
+    headers.push((
+        "signature",
+        format!(
+            "keyId=\"{}\",algorithm=\"rsa-sha256\",headers=\"(request-target) {}\",signature=\"{}\"",
+            key_id,
+            names,
+            STANDARD.encode(signature.to_bytes())
+        ),
+    ));
+    Ok(headers)
+}
+
+/// Check the HTTP signature of an incoming `POST` to `path`, and that the body
+/// matches its signed digest. `fetch_key` looks up the PEM public key and owner
+/// of a key ID; the owner's actor ID is returned.
+pub fn verify(
+    path: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    fetch_key: impl FnOnce(&str) -> Result<(String, String)>,
+) -> Result<String> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let unauthorized =
+        |reason: &str| Error::Unauthorized(format!("Invalid HTTP signature: {}", reason));
+
+    let params = header("signature")
+        .map(parse_params)
+        .ok_or_else(|| unauthorized("the request is not signed"))?;
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let key_id = param("keyId").ok_or_else(|| unauthorized("no keyId"))?;
+    let signed_headers = param("headers").unwrap_or("date");
+    let signature = param("signature")
+        .and_then(|signature| STANDARD.decode(signature).ok())
+        .ok_or_else(|| unauthorized("malformed signature"))?;
+
+    // The signature has to cover the target, the body and a recent date
+    for required in ["(request-target)", "digest", "date"] {
+        if !signed_headers.split(' ').any(|name| name == required) {
+            return Err(unauthorized(&format!("'{}' is not signed", required)).into());
+        }
+    }
+    if header("digest") != Some(digest(body).as_str()) {
+        return Err(unauthorized("the digest doesn't match the body").into());
+    }
+    let date = header("date")
+        .and_then(|date| httpdate::parse_http_date(date).ok())
+        .ok_or_else(|| unauthorized("malformed date"))?;
+    let skew = match SystemTime::now().duration_since(date) {
+        Ok(age) => age,
+        Err(e) => e.duration(),
+    };
+    if skew > MAX_CLOCK_SKEW {
+        return Err(unauthorized("the date is too far from now").into());
+    }
+
+    let mut lines = Vec::new();
+    for name in signed_headers.split(' ') {
+        let value = match name {
+            "(request-target)" => format!("post {}", path),
+            name => header(name)
+                .ok_or_else(|| unauthorized(&format!("missing signed header '{}'", name)))?
+                .to_string(),
+        };
+        lines.push(format!("{}: {}", name, value));
+    }
+
+    let (pem, owner) = fetch_key(key_id).context("Failed to fetch the signing key")?;
+    let key = RsaPublicKey::from_public_key_pem(&pem).context("Invalid public key")?;
+    let signature = Signature::try_from(signature.as_slice())
+        .map_err(|_| unauthorized("malformed signature"))?;
+    if VerifyingKey::<Sha256>::new(key)
+        .verify(lines.join("\n").as_bytes(), &signature)
+        .is_err()
+    {
+        bail!(unauthorized("the signature doesn't match"));
+    }
+    Ok(owner)
+}
+
+/// The `Digest` header value for `body`.
+fn digest(body: &[u8]) -> String {
+    format!("SHA-256={}", STANDARD.encode(Sha256::digest(body)))
+}
+
+/// Parse `key="value",key="value"` pairs.
+fn parse_params(header: &str) -> Vec<(String, String)> {
+    header
+        .split(',')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .map(|(key, value)| (key.to_string(), value.trim_matches('"').to_string()))
+        .collect()
+}
+
+/// Split an `http(s)` URL into its host and its path with the query.
+fn split_url(url: &str) -> Option<(&str, &str)> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    match rest.find('/') {
+        Some(slash) => Some((&rest[..slash], &rest[slash..])),
+        None => Some((rest, "/")),
+    }
+}
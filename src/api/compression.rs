@@ -0,0 +1,148 @@
+//! Compressed article responses. Bodies are compressed with brotli or gzip, as the
+//! client's `Accept-Encoding` prefers, and kept in a cache keyed by a hash of the
+//! uncompressed body, so an article requested again is served without compressing
+//! it again.
+
+use std::{
+    collections::VecDeque,
+    io::Write,
+    sync::Mutex,
+};
+
+use actix_web::{http::header, web::Bytes, HttpRequest};
+use flate2::{write::GzEncoder, Compression};
+use log::*;
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+/// Bodies smaller than this are sent as they are.
+pub const MIN_COMPRESS_BYTES: usize = 1024;
+
+/// Brotli's quality (0 to 11): a little denser than gzip's default, without the
+/// much slower top settings.
+const BROTLI_QUALITY: i32 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// The value of the `Content-Encoding` header.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    fn compress(self, chunks: &VecDeque<Bytes>) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Brotli => {
+                let params = brotli::enc::BrotliEncoderParams {
+                    quality: BROTLI_QUALITY,
+                    ..Default::default()
+                };
+                let mut encoder = brotli::CompressorWriter::with_params(Vec::new(), 4096, &params);
+                for chunk in chunks {
+                    encoder.write_all(chunk)?;
+                }
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                for chunk in chunks {
+                    encoder.write_all(chunk)?;
+                }
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// The encoding `req` accepts with the highest weight, brotli winning ties, or
+/// `None` if it accepts neither.
+pub fn preferred(req: &HttpRequest) -> Option<Encoding> {
+    let accepted = req.headers().get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+    let mut best: Option<(Encoding, f32)> = None;
+    for coding in accepted.split(',') {
+        let mut params = coding.split(';').map(str::trim);
+        let encoding = match params.next().unwrap_or_default().to_ascii_lowercase().as_str() {
+            "br" => Encoding::Brotli,
+            "gzip" | "x-gzip" => Encoding::Gzip,
+            _ => continue,
+        };
+        let weight = params
+            .find_map(|param| param.strip_prefix("q="))
+            .map_or(Some(1.0), |q| q.parse::<f32>().ok())
+            .unwrap_or(0.0);
+        let better = best.is_none_or(|(best, best_weight)| {
+            weight > best_weight || (weight == best_weight && best != Encoding::Brotli)
+        });
+        if weight > 0.0 && better {
+            best = Some((encoding, weight));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+type CacheKey = ([u8; 32], Encoding);
+
+/// Compressed response bodies, the least recently used dropped first once they
+/// hold more than `max_bytes` (`compressed_cache_bytes`).
+pub struct CompressedCache {
+    max_bytes: usize,
+    entries: Mutex<CompressedEntries>,
+}
+
+struct CompressedEntries {
+    bodies: LruCache<CacheKey, Bytes>,
+    bytes: usize,
+}
+
+impl CompressedCache {
+    pub fn new(max_bytes: usize) -> Self {
+        CompressedCache {
+            max_bytes,
+            entries: Mutex::new(CompressedEntries {
+                bodies: LruCache::unbounded(),
+                bytes: 0,
+            }),
+        }
+    }
+
+    /// The body made of `chunks`, compressed with `encoding`: from the cache if it
+    /// holds it, otherwise compressed now and cached.
+    pub fn compressed(&self, chunks: &VecDeque<Bytes>, encoding: Encoding) -> Result<Bytes, Error> {
+        let mut hasher = Sha256::new();
+        for chunk in chunks {
+            hasher.update(chunk);
+        }
+        let key = (hasher.finalize().into(), encoding);
+        if let Some(body) = self.entries.lock().unwrap().bodies.get(&key) {
+            return Ok(body.clone());
+        }
+
+        let body = Bytes::from(encoding.compress(chunks).map_err(anyhow::Error::from)?);
+        if body.len() <= self.max_bytes {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(replaced) = entries.bodies.put(key, body.clone()) {
+                entries.bytes -= replaced.len();
+            }
+            entries.bytes += body.len();
+            while entries.bytes > self.max_bytes {
+                let Some((_, evicted)) = entries.bodies.pop_lru() else {
+                    break;
+                };
+                entries.bytes -= evicted.len();
+            }
+        } else {
+            debug!("A {}-compressed body of {} bytes is too large to cache", encoding.name(), body.len());
+        }
+        Ok(body)
+    }
+}
@@ -0,0 +1,27 @@
+//! The embedded admin dashboard: a single page for operators without the command
+//! line, showing the index status, scan problems and cache statistics, with buttons
+//! to refresh the index, clear the cache, validate the content and reload the
+//! configuration.
+
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Only the page's own inline script and style may run, and only against this server.
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; script-src 'unsafe-inline'; \
+    style-src 'unsafe-inline'; connect-src 'self'; frame-ancestors 'none'";
+
+/// Serves the dashboard page. It holds no data itself: it asks for an API key and
+/// sends it with every API request it makes.
+#[get("/admin")]
+async fn dashboard() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header((header::CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY))
+        .body(PAGE)
+}
+
+/// Configures the dashboard route
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(dashboard);
+}
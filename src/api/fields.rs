@@ -0,0 +1,121 @@
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+use std::collections::HashSet;
+
+use crate::{
+    api::ContentSlot,
+    articles::{Article, ArticleSummary},
+    error::Error,
+};
+
+/// A type that can be serialized with only some of its fields, as selected by a
+/// `?fields=` query parameter.
+pub trait SelectFields {
+    /// The names of all fields, in serialization order.
+    const FIELDS: &'static [&'static str];
+
+    /// Serialize the field called `name` into `map`.
+    fn serialize_field<M: SerializeMap>(&self, name: &'static str, map: &mut M) -> Result<(), M::Error>;
+}
+
+/// The fields a client asked for with `?fields=a,b,c`; all of them when absent.
+pub struct FieldSelection(Option<HashSet<&'static str>>);
+
+impl FieldSelection {
+    /// Parse a comma-separated list of fields of `T`, rejecting unknown names.
+    pub fn parse<T: SelectFields>(fields: Option<&str>) -> Result<Self, Error> {
+        let Some(fields) = fields else {
+            return Ok(FieldSelection(None));
+        };
+        let mut selected = HashSet::new();
+        for name in fields.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let Some(field) = T::FIELDS.iter().find(|field| **field == name) else {
+                return Err(Error::InvalidRequest(format!(
+                    "unknown field '{}' (expected one of: {})",
+                    name,
+                    T::FIELDS.join(", ")
+                )));
+            };
+            selected.insert(*field);
+        }
+        // `?fields=` with nothing selected means no selection rather than no fields
+        Ok(FieldSelection((!selected.is_empty()).then_some(selected)))
+    }
+
+    fn includes(&self, name: &str) -> bool {
+        self.0.as_ref().is_none_or(|selected| selected.contains(name))
+    }
+}
+
+/// Serializes a value, or each value of a slice, with only the selected fields.
+pub struct Sparse<'a, T: ?Sized>(pub &'a T, pub &'a FieldSelection);
+
+impl<T: SelectFields> Serialize for Sparse<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Sparse(value, selection) = self;
+        let mut map = serializer.serialize_map(None)?;
+        for name in T::FIELDS.iter().filter(|name| selection.includes(name)) {
+            value.serialize_field(name, &mut map)?;
+        }
+        map.end()
+    }
+}
+
+impl<T: SelectFields> Serialize for Sparse<'_, [T]> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Sparse(values, selection) = self;
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for value in values.iter() {
+            seq.serialize_element(&Sparse(value, selection))?;
+        }
+        seq.end()
+    }
+}
+
+impl SelectFields for Article {
+    const FIELDS: &'static [&'static str] = &[
+        "id", "title", "description", "content", "date", "tags", "keywords", "canonical_url", "license",
+        "source", "attribution", "extra", "reactions",
+    ];
+
+    fn serialize_field<M: SerializeMap>(&self, name: &'static str, map: &mut M) -> Result<(), M::Error> {
+        match name {
+            "id" => map.serialize_entry(name, &self.id),
+            "title" => map.serialize_entry(name, self.title.as_ref()),
+            "description" => map.serialize_entry(name, self.description.as_ref()),
+            "content" => map.serialize_entry(name, &ContentSlot(&self.content)),
+            "date" => map.serialize_entry(name, &self.date),
+            "tags" => map.serialize_entry(name, self.tags.as_ref()),
+            "keywords" => map.serialize_entry(name, self.keywords.as_ref()),
+            "canonical_url" => map.serialize_entry(name, &self.canonical_url.as_deref()),
+            "license" => map.serialize_entry(name, &self.license.as_deref()),
+            "source" => map.serialize_entry(name, &self.source.as_deref()),
+            "attribution" => map.serialize_entry(name, &self.attribution.as_deref()),
+            "extra" => map.serialize_entry(name, self.extra.as_ref()),
+            "reactions" => map.serialize_entry(name, &self.reactions),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SelectFields for ArticleSummary {
+    const FIELDS: &'static [&'static str] =
+        &["id", "title", "description", "date", "tags", "keywords", "cover", "extra", "comments"];
+
+    fn serialize_field<M: SerializeMap>(&self, name: &'static str, map: &mut M) -> Result<(), M::Error> {
+        match name {
+            "id" => map.serialize_entry(name, &self.id),
+            "title" => map.serialize_entry(name, self.title.as_ref()),
+            "description" => map.serialize_entry(name, self.description.as_ref()),
+            "date" => map.serialize_entry(name, &self.date),
+            "tags" => map.serialize_entry(name, self.tags.as_ref()),
+            "keywords" => map.serialize_entry(name, self.keywords.as_ref()),
+            "cover" => map.serialize_entry(name, &self.cover.as_deref()),
+            "extra" => map.serialize_entry(name, self.extra.as_ref()),
+            "comments" => map.serialize_entry(name, &self.comments),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use actix_web::{
+    web::{self, Data, Json},
+    HttpResponse, Resource,
+};
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, Enum, ErrorExtensions, InputObject,
+    Object, Schema, SimpleObject,
+};
+
+use crate::{
+    api::v2::{decode_cursor, encode_cursor, MAX_PAGE_SIZE},
+    articles::{
+        ArticleId, ArticleSummary, Articles, ListingFilter, ListingOrder, ListingPosition, tag_slug,
+    },
+    error::Error,
+    search::SearchFields,
+};
+
+/// The schema served at `/api/graphql`. Resolvers find the `Articles` of the
+/// resource the request came in through in the request data.
+pub type ArticleSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub fn schema() -> ArticleSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription).finish()
+}
+
+/// Report an error with its `error_code` as the `code` extension.
+fn graphql_error(e: impl Into<Error>) -> async_graphql::Error {
+    let e = e.into();
+    async_graphql::Error::new(e.to_string()).extend_with(|_, ext| ext.set("code", e.error_code()))
+}
+
+/// An article. Its `content` is only rendered when asked for.
+#[derive(SimpleObject)]
+#[graphql(complex, name = "Article")]
+struct ArticleNode {
+    id: ArticleId,
+    title: String,
+    description: String,
+    date: u32,
+    tags: Vec<String>,
+    keywords: Vec<String>,
+    /// The URL of the article's cover image.
+    cover: Option<String>,
+    /// Site-specific metadata from `[article.extra]`, as a JSON object.
+    extra: async_graphql::Json<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl From<ArticleSummary> for ArticleNode {
+    fn from(summary: ArticleSummary) -> Self {
+        ArticleNode {
+            id: summary.id,
+            title: summary.title.to_string(),
+            description: summary.description.to_string(),
+            date: summary.date,
+            tags: summary.tags.to_vec(),
+            keywords: summary.keywords.to_vec(),
+            cover: summary.cover.as_deref().map(str::to_string),
+            extra: async_graphql::Json(summary.extra.as_ref().clone()),
+        }
+    }
+}
+
+#[ComplexObject]
+impl ArticleNode {
+    /// The rendered HTML of the article.
+    async fn content(&self, ctx: &Context<'_>) -> async_graphql::Result<String> {
+        let articles = ctx.data::<Articles>()?.clone();
+        let id = self.id;
+        let (article, _) = web::block(move || articles.get_article(id))
+            .await
+            .map_err(graphql_error)?
+            .map_err(graphql_error)?;
+        Ok(article.content.to_string())
+    }
+
+    /// Articles sharing the most tags with this one, newest first among equals.
+    async fn related(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 5)] limit: usize,
+    ) -> async_graphql::Result<Vec<ArticleNode>> {
+        let articles = ctx.data::<Articles>()?;
+        let ids = articles.related_article_ids(self.id, limit.min(MAX_PAGE_SIZE));
+        Ok(summaries(articles, &ids))
+    }
+}
+
+/// A tag and the number of articles carrying it.
+#[derive(SimpleObject)]
+#[graphql(complex)]
+struct Tag {
+    name: String,
+    count: usize,
+}
+
+#[ComplexObject]
+impl Tag {
+    /// The tag's canonical URL form, e.g. `human-rights` for `Human Rights`.
+    async fn slug(&self) -> String {
+        tag_slug(&self.name)
+    }
+
+    /// The articles carrying the tag, newest first.
+    async fn articles(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ArticleNode>> {
+        let articles = ctx.data::<Articles>()?;
+        let filter = ListingFilter {
+            tags: vec![self.name.clone()],
+            ..Default::default()
+        };
+        let page = articles.list_article_summaries_page(&filter, ListingOrder::Newest, None, self.count);
+        Ok(page.items.into_iter().map(ArticleNode::from).collect())
+    }
+}
+
+/// One page of an article listing, as in `GET /api/v2/articles`.
+#[derive(SimpleObject)]
+struct ArticlePage {
+    items: Vec<ArticleNode>,
+    total: usize,
+    next_cursor: Option<String>,
+    prev_cursor: Option<String>,
+}
+
+/// Which articles to list. Every given criterion must match.
+#[derive(Default, InputObject)]
+struct ArticleFilter {
+    /// Only articles with all of these tags.
+    #[graphql(default)]
+    tags: Vec<String>,
+    /// Only articles with this keyword.
+    keyword: Option<String>,
+    /// Only articles matching this search query.
+    query: Option<String>,
+    /// Only articles dated on or after this day (YYYYMMDD).
+    from: Option<u32>,
+    /// Only articles dated on or before this day (YYYYMMDD).
+    to: Option<u32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Enum)]
+enum Sort {
+    Newest,
+    Oldest,
+}
+
+impl From<Sort> for ListingOrder {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Newest => ListingOrder::Newest,
+            Sort::Oldest => ListingOrder::Oldest,
+        }
+    }
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The article with the given ID, if there is one.
+    async fn article(&self, ctx: &Context<'_>, id: ArticleId) -> async_graphql::Result<Option<ArticleNode>> {
+        let articles = ctx.data::<Articles>()?;
+        Ok(summaries(articles, &[id]).pop())
+    }
+
+    /// Articles matching every given criterion, a page at a time.
+    async fn articles(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 10)] first: usize,
+        #[graphql(desc = "The `nextCursor` or `prevCursor` of another page.")] cursor: Option<String>,
+        #[graphql(default)] filter: ArticleFilter,
+        #[graphql(default_with = "Sort::Newest")] sort: Sort,
+    ) -> async_graphql::Result<ArticlePage> {
+        let articles = ctx.data::<Articles>()?;
+        if first == 0 || first > MAX_PAGE_SIZE {
+            let message = format!("'first' must be between 1 and {}", MAX_PAGE_SIZE);
+            return Err(graphql_error(Error::InvalidRequest(message)));
+        }
+        let position = match cursor.as_deref() {
+            Some(cursor) => Some(decode_cursor(cursor).map_err(graphql_error)?),
+            None => None,
+        };
+        let filter = ListingFilter {
+            tags: filter.tags,
+            keyword: filter.keyword,
+            query: filter.query,
+            search_fields: SearchFields::ALL,
+            from: filter.from,
+            to: filter.to,
+        };
+        let page = articles.list_article_summaries_page(&filter, sort.into(), position, first);
+        Ok(ArticlePage {
+            items: page.items.into_iter().map(ArticleNode::from).collect(),
+            total: page.total,
+            next_cursor: page.next.map(ListingPosition::After).map(encode_cursor),
+            prev_cursor: page.prev.map(ListingPosition::Before).map(encode_cursor),
+        })
+    }
+
+    /// Every tag, by name.
+    async fn tags(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Tag>> {
+        let articles = ctx.data::<Articles>()?;
+        Ok(articles
+            .list_tags()
+            .into_iter()
+            .map(|(name, count)| Tag { name, count })
+            .collect())
+    }
+
+    /// Articles whose title, description, tags or keywords match `query`, most
+    /// relevant first.
+    async fn search(&self, ctx: &Context<'_>, query: String) -> async_graphql::Result<Vec<ArticleNode>> {
+        let articles = ctx.data::<Articles>()?;
+        let results = articles
+            .search_articles(&query, SearchFields::ALL, &[])
+            .map_err(graphql_error)?;
+        Ok(results.into_iter().map(ArticleNode::from).collect())
+    }
+}
+
+/// The summaries of the given articles that exist, in order.
+fn summaries(articles: &Articles, ids: &[ArticleId]) -> Vec<ArticleNode> {
+    articles
+        .summaries_of(ids)
+        .into_iter()
+        .map(ArticleNode::from)
+        .collect()
+}
+
+/// Executes a GraphQL query against the articles of the resource
+async fn graphql(
+    schema: Data<ArticleSchema>,
+    articles_data: Data<Articles>,
+    request: Json<async_graphql::Request>,
+) -> HttpResponse {
+    let request = request.into_inner().data(articles_data.get_ref().clone());
+    HttpResponse::Ok().json(schema.execute(request).await)
+}
+
+/// Configures the GraphQL endpoint
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/api/graphql").route(web::post().to(graphql)));
+}
+
+/// Builds the resource serving the GraphQL endpoint for a named content section at
+/// `/api/graphql/{section}`, backed by the section's own `Articles` instance.
+pub fn section_resource(name: &str, articles: Articles) -> Resource {
+    web::resource(format!("/api/graphql/{}", name))
+        .app_data(Data::new(articles))
+        .route(web::post().to(graphql))
+}
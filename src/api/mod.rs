@@ -1,5 +1,39 @@
+use actix_web::{
+    body::{BodySize, MessageBody},
+    http::header,
+    web::{self, Bytes, Data},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use log::*;
 use serde::Serialize;
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::VecDeque,
+    convert::Infallible,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
 
+use crate::{
+    api::compression::{CompressedCache, MIN_COMPRESS_BYTES},
+    articles::{Article, ArticleId, ArticleRef, Articles, Content},
+    auth::check_access,
+    config::{self, Config},
+    error::Error,
+    proxy,
+    reactions::Reactions,
+};
+
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod compression;
+pub mod fields;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod shortlink;
 pub mod v1;
 pub mod v2;
 
@@ -9,4 +43,355 @@ pub struct ApiResponse<T> {
     pub success: bool,
     pub data: T,
     pub message: Option<String>,
+    /// A stable, machine-readable name for the error of a failed request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<&'static str>,
+}
+
+/// The tags named by a `tag` parameter and a comma-separated `tags` parameter.
+fn requested_tags(tag: Option<&str>, tags: Option<&str>) -> Vec<String> {
+    tag.into_iter()
+        .chain(tags.into_iter().flat_map(|tags| tags.split(',')))
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The URL of the request with the query parameter `name` set to `value`, or left
+/// out if `None`, keeping every other parameter as sent.
+fn link_with(req: &HttpRequest, name: &str, value: Option<&str>) -> String {
+    let mut pairs = req
+        .query_string()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some(name))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if let Some(value) = value {
+        pairs.push(format!("{}={}", name, value));
+    }
+    if pairs.is_empty() {
+        req.path().to_string()
+    } else {
+        format!("{}?{}", req.path(), pairs.join("&"))
+    }
+}
+
+/// Set the `X-Total-Count` header of a listing to its `total`, and its `Link`
+/// header (RFC 8288) to the `links` to other pages, by relation.
+fn set_pagination_headers(headers: &mut header::HeaderMap, total: usize, links: &[(&str, String)]) {
+    headers.insert(header::HeaderName::from_static("x-total-count"), total.into());
+    let link = links
+        .iter()
+        .map(|(rel, url)| format!("<{}>; rel=\"{}\"", url, rel))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if let Ok(link) = header::HeaderValue::from_str(&link)
+        && !links.is_empty()
+    {
+        headers.insert(header::LINK, link);
+    }
+}
+
+/// The settings URLs are built from in answer to `req`. Without a `base_url`, the
+/// scheme and host forwarded by a trusted proxy stand in for it.
+fn site_config<'a>(config: &'a Config, req: &HttpRequest) -> Cow<'a, config::Main> {
+    if config.mainconfig.base_url.is_some() {
+        return Cow::Borrowed(&config.mainconfig);
+    }
+    match proxy::forwarded_origin(req.head(), &config.proxy) {
+        Some(origin) => Cow::Owned(config::Main {
+            base_url: Some(origin),
+            ..config.mainconfig.clone()
+        }),
+        None => Cow::Borrowed(&config.mainconfig),
+    }
+}
+
+/// `article` without its content, to be sent with [`article_json`], its canonical
+/// URL on the site a trusted proxy forwarded `req` for, and its reaction counts
+/// when reactions are enabled.
+fn public_article(articles: &Articles, req: &HttpRequest, article: &Article) -> Article {
+    let mut public = article.without_content();
+    if let Some(reactions) = req.app_data::<Data<Reactions>>() {
+        public.reactions = reactions.counts(article.id);
+    }
+    let config = articles.config();
+    if let Cow::Owned(site) = site_config(&config, req)
+        && let Ok(metainfo) = articles.get_metainfo(article.id)
+    {
+        public.canonical_url = metainfo.canonical_url(&site).map(Arc::from);
+    }
+    public
+}
+
+/// Where an article requested by one of its aliases lives now.
+#[derive(Serialize)]
+struct Moved {
+    id: ArticleId,
+    location: String,
+}
+
+/// A deleted article that left a tombstone, and the article succeeding it.
+#[derive(Serialize)]
+struct Gone {
+    id: ArticleId,
+    successor: Option<ArticleId>,
+    location: Option<String>,
+}
+
+/// The path of the article `id`, in place of the article requested by `req`.
+fn article_location(req: &HttpRequest, id: ArticleId) -> String {
+    let path = req.path().rsplit_once('/').map_or("", |(parent, _)| parent);
+    match req.query_string() {
+        "" => format!("{}/{}", path, id),
+        query => format!("{}/{}?{}", path, id, query),
+    }
+}
+
+/// Resolve the `{id}` segment of an article path, which may also be an alias. A
+/// request for an alias gets a permanent redirect to the article's current path,
+/// and one for a deleted article with a tombstone `410 Gone` with a link to its
+/// successor, both returned as the error; an ID that doesn't exist is passed on
+/// to be reported as not found. A private article the request may not read (see
+/// [`check_access`]) is reported as not found right away, an alias of one as an
+/// unknown alias, and a private successor is left out of the `410 Gone`.
+pub fn resolve_article(
+    req: &HttpRequest,
+    articles: &Articles,
+    requested: &str,
+) -> Result<ArticleId, HttpResponse> {
+    match articles.resolve(requested) {
+        Some(ArticleRef::Id(id)) => match check_access(req, articles, id) {
+            Ok(()) => Ok(id),
+            Err(e) => Err(e.error_response()),
+        },
+        Some(ArticleRef::Gone { id, successor }) => {
+            let successor = successor.filter(|&next| check_access(req, articles, next).is_ok());
+            let error = Error::Gone { id, successor };
+            let location = successor.map(|successor| article_location(req, successor));
+            debug!("Article {} is gone", id);
+            let mut response = HttpResponse::Gone();
+            if let Some(location) = &location {
+                response.insert_header((
+                    header::LINK,
+                    format!("<{}>; rel=\"successor-version\"", location),
+                ));
+            }
+            Err(response.json(ApiResponse {
+                success: false,
+                data: Gone { id, successor, location },
+                message: Some(error.to_string()),
+                error_code: Some(error.error_code()),
+            }))
+        }
+        Some(ArticleRef::Moved(id)) if check_access(req, articles, id).is_ok() => {
+            let location = article_location(req, id);
+            debug!("Redirecting '{}' to {}", requested, location);
+            Err(HttpResponse::PermanentRedirect()
+                .insert_header((header::LOCATION, location.clone()))
+                .json(ApiResponse {
+                    success: true,
+                    data: Moved { id, location },
+                    message: Some(format!("Article '{}' has moved to {}", requested, id)),
+                    error_code: None,
+                }))
+        }
+        Some(ArticleRef::Moved(_)) | None => requested
+            .parse()
+            .map_err(|_| unknown_article(requested).error_response()),
+    }
+}
+
+/// The error for a requested article that is neither an ID nor a known alias,
+/// which an alias of a private article the request may not read also gets.
+fn unknown_article(requested: &str) -> Error {
+    Error::InvalidRequest(format!("'{}' is not an article ID or alias", requested))
+}
+
+/// How many articles a page holds when the request doesn't say, as configured by
+/// `default_page_size`.
+pub fn default_page_size(articles: &Articles) -> usize {
+    articles.config().mainconfig.default_page_size
+}
+
+/// Maps a failed request to a response, exposing the reason for rejected input.
+/// Only server-side failures are logged as errors; requests for missing articles
+/// or pages past the end are routine.
+pub fn error_response(e: anyhow::Error, message: &str) -> HttpResponse {
+    let e = Error::from(e);
+    if e.status_code().is_server_error() {
+        error!("{}: {:?}", message, e);
+    } else {
+        debug!("{}: {}", message, e);
+    }
+    e.response(message)
+}
+
+thread_local! {
+    /// While [`article_json`] encodes on this thread: how many bytes it wrote so far,
+    /// and where the article's content was written, if it was.
+    static SPLICE: Cell<(usize, Option<usize>)> = const { Cell::new((0, None)) };
+}
+
+/// The buffer [`article_json`] encodes into, keeping [`SPLICE`] up to date with its
+/// length; the JSON serializer writes to it directly, without buffering.
+struct SpliceWriter(Vec<u8>);
+
+impl io::Write for SpliceWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        SPLICE.with(|splice| splice.set((self.0.len(), splice.get().1)));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes the content of an article as the content itself, noting where it
+/// goes in the output of [`article_json`], which puts the shared encoding there.
+pub struct ContentSlot<'a>(pub &'a Content);
+
+impl Serialize for ContentSlot<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SPLICE.with(|splice| {
+            let (written, _) = splice.get();
+            splice.set((written, Some(written)));
+        });
+        self.0.serialize(serializer)
+    }
+}
+
+/// Respond with `value` as JSON, where `value` holds an article made with
+/// [`Article::without_content`](crate::articles::Article::without_content): its
+/// empty content, written through a [`ContentSlot`], is replaced by the shared JSON
+/// encoding of `content`, which is sent as it is rather than encoded and copied
+/// again. With a [`CompressedCache`] in the app data, the body is compressed as
+/// `req` prefers.
+pub async fn article_json<T: Serialize>(req: &HttpRequest, value: &T, content: &Content) -> HttpResponse {
+    /// The length of the empty string `""` the content was encoded as.
+    const EMPTY_CONTENT: usize = 2;
+
+    SPLICE.with(|splice| splice.set((0, None)));
+    let mut writer = SpliceWriter(Vec::new());
+    if let Err(e) = serde_json::to_writer(&mut writer, value) {
+        return error_response(e.into(), "Failed to encode the article");
+    }
+    let (_, content_at) = SPLICE.with(|splice| splice.replace((0, None)));
+    // A `?fields=` selection may have left the content out
+    let chunks = match content_at {
+        Some(at) => {
+            let mut head = Bytes::from(writer.0);
+            let tail = head.split_off(at + EMPTY_CONTENT);
+            head.truncate(at);
+            VecDeque::from([head, content.json(), tail])
+        }
+        None => VecDeque::from([Bytes::from(writer.0)]),
+    };
+    let mut response = HttpResponse::Ok();
+    response.content_type("application/json");
+
+    if let Some(cache) = req.app_data::<Data<CompressedCache>>() {
+        response.insert_header((header::VARY, "Accept-Encoding"));
+        let size = chunks.iter().map(Bytes::len).sum::<usize>();
+        if let Some(encoding) = compression::preferred(req).filter(|_| size >= MIN_COMPRESS_BYTES) {
+            // Compressing a large article takes a while, so it's kept off the async workers
+            let cache = cache.clone().into_inner();
+            match web::block(move || cache.compressed(&chunks, encoding)).await {
+                Ok(Ok(body)) => {
+                    return response
+                        .insert_header((header::CONTENT_ENCODING, encoding.name()))
+                        .body(body);
+                }
+                Ok(Err(e)) => return e.response("Failed to compress the article"),
+                Err(e) => {
+                    error!("Compression task failed: {:?}", e);
+                    return Error::from(e).response("Failed to compress the article");
+                }
+            }
+        }
+    }
+    response.body(Chunks(chunks))
+}
+
+/// A response body streamed from a channel, as a producer on another thread sends
+/// its chunks. An error ends the response early.
+struct Streamed(tokio::sync::mpsc::Receiver<Result<Bytes, Error>>);
+
+impl MessageBody for Streamed {
+    type Error = Error;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        self.get_mut().0.poll_recv(cx)
+    }
+}
+
+/// The size of the chunks a [`ChunkWriter`] sends.
+#[cfg(feature = "backup")]
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Feeds a [`Streamed`] body from a blocking task through `std::io::Write`. Writes
+/// fail with `BrokenPipe` once the client has gone away.
+#[cfg(feature = "backup")]
+struct ChunkWriter {
+    sender: tokio::sync::mpsc::Sender<Result<Bytes, Error>>,
+    chunk: Vec<u8>,
+}
+
+#[cfg(feature = "backup")]
+impl ChunkWriter {
+    fn new(sender: tokio::sync::mpsc::Sender<Result<Bytes, Error>>) -> Self {
+        ChunkWriter {
+            sender,
+            chunk: Vec::with_capacity(CHUNK_BYTES),
+        }
+    }
+}
+
+#[cfg(feature = "backup")]
+impl std::io::Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.chunk.extend_from_slice(buf);
+        if self.chunk.len() >= CHUNK_BYTES {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_BYTES));
+        self.sender
+            .blocking_send(Ok(chunk.into()))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the client went away"))
+    }
+}
+
+/// A response body sent as a series of buffers, without joining them.
+struct Chunks(VecDeque<Bytes>);
+
+impl MessageBody for Chunks {
+    type Error = Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Sized(self.0.iter().map(|chunk| chunk.len() as u64).sum())
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        Poll::Ready(self.get_mut().0.pop_front().map(Ok))
+    }
 }
@@ -0,0 +1,72 @@
+//! Short permalinks: `/s/{id}` redirects to an article's page on the site, so
+//! authors can share a compact link that keeps working when the article moves.
+
+use actix_web::{
+    get,
+    http::header,
+    web::{self, Data, Path},
+    HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use log::*;
+
+use crate::{
+    api::{site_config, unknown_article, ApiResponse, Moved},
+    articles::{ArticleId, ArticleRef, Articles},
+    auth::check_access,
+    error::Error,
+};
+
+/// Redirects to the page of an article, given by its ID or one of its aliases: the
+/// configured `shortlink_target`, or else its canonical URL. Without either, it
+/// redirects to the article in the API. A deleted article redirects to its
+/// successor, if it has one.
+#[get("/s/{id}")]
+async fn shortlink(
+    req: HttpRequest,
+    articles_data: Data<Articles>,
+    path: Path<String>,
+) -> impl Responder {
+    let requested = path.into_inner();
+    // Neither a private article the request may not read nor its aliases and
+    // successor ID are disclosed
+    let readable = |id| check_access(&req, &articles_data, id).is_ok();
+    let id = match articles_data.resolve(&requested) {
+        Some(ArticleRef::Id(id)) if !readable(id) => return Error::NotFound(id).error_response(),
+        Some(ArticleRef::Id(id)) => id,
+        Some(ArticleRef::Moved(id)) if readable(id) => id,
+        Some(ArticleRef::Gone { successor: Some(id), .. }) if readable(id) => id,
+        Some(ArticleRef::Gone { id, .. }) => {
+            return Error::Gone { id, successor: None }.error_response();
+        }
+        Some(ArticleRef::Moved(_)) | None => {
+            return match requested.parse::<ArticleId>() {
+                Ok(id) => Error::NotFound(id).error_response(),
+                Err(_) => unknown_article(&requested).error_response(),
+            };
+        }
+    };
+
+    let config = articles_data.config();
+    let site = site_config(&config, &req);
+    let location = match site.shortlink_url(id) {
+        Some(location) => location,
+        None => match articles_data.get_metainfo(id).ok().and_then(|m| m.canonical_url(&site)) {
+            Some(location) => location,
+            None => format!("{}/api/v1/articles/{}", config.mainconfig.path_prefix, id),
+        },
+    };
+    debug!("Redirecting shortlink '{}' to {}", requested, location);
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, location.clone()))
+        .json(ApiResponse {
+            success: true,
+            data: Moved { id, location },
+            message: None,
+            error_code: None,
+        })
+}
+
+/// Configures the shortlink route
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(shortlink);
+}
@@ -1,344 +1,1615 @@
+#[cfg(feature = "uploads")]
+use actix_multipart::Multipart;
 use actix_web::{
-    delete, get, post,
-    web::{self, Data, Path, Query},
-    HttpResponse, Responder,
+    delete, get,
+    http::StatusCode,
+    post, put,
+    web::{self, Data, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder, ResponseError, Scope,
 };
+use actix_web::rt;
+#[cfg(feature = "websocket")]
+use actix_ws::{Message, MessageStream, Session};
+#[cfg(feature = "uploads")]
+use futures_util::StreamExt;
 use log::*;
-use serde::Deserialize;
-use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 use crate::{
-    api::ApiResponse,
-    articles::{ArticleId, Articles, CachedStatus},
-    cache_recorder::{CacheHit, CacheStats},
+    api::{
+        article_json, default_page_size, error_response, link_with, requested_tags, resolve_article,
+        fields::{FieldSelection, Sparse},
+        public_article, set_pagination_headers, site_config, ApiResponse, Streamed,
+    },
+    articles::{
+        Article, ArticleDraft, ArticleId, ArticleSummary, Articles, ContentFormat, ImportItem, OnConflict,
+    },
+    auth::{check_access, Admin, ApiKey, Author, Operator, Read},
+    cache_recorder::RequestStats,
+    comments::{CommentId, CommentStatus, Comments},
+    config::{Config, Overrides},
+    error::Error,
+    proxy,
+    reactions::Reactions,
+    reload::ConfigReloader,
+    search::SearchFields,
+    social::{self, MetaTag},
+    status::{StartTime, Status},
+    version,
 };
-
-const DEFAULT_PAGE_SIZE: usize = 10;
+#[cfg(feature = "git")]
+use actix_web::FromRequest;
+#[cfg(feature = "backup")]
+use crate::{api::ChunkWriter, backup};
+#[cfg(feature = "uploads")]
+use crate::bundle::{self, BundleFile};
+#[cfg(feature = "websocket")]
+use crate::events::ContentEvent;
+#[cfg(feature = "websocket")]
+use tokio::sync::broadcast::{self, error::RecvError};
 
 #[derive(Deserialize)]
 struct PaginationParams {
     limit: Option<usize>,
     page: Option<usize>,
+    /// Comma-separated fields to include in each item (all if absent).
+    fields: Option<String>,
+    /// `content` to list complete articles rather than summaries.
+    include: Option<String>,
+}
+
+/// The most articles a listing with `include=content` returns at once.
+const MAX_CONTENT_PAGE_SIZE: usize = 20;
+
+impl PaginationParams {
+    /// The page to list complete articles of, with `include=content`: its size, at
+    /// most [`MAX_CONTENT_PAGE_SIZE`], and number, the first page if not given. `None`
+    /// lists summaries.
+    fn content_page(&self, articles: &Articles) -> Result<Option<(usize, usize)>, Error> {
+        match self.include.as_deref() {
+            None | Some("") => Ok(None),
+            Some("content") => {
+                let limit = self
+                    .limit
+                    .unwrap_or_else(|| default_page_size(articles).min(MAX_CONTENT_PAGE_SIZE));
+                if limit == 0 || limit > MAX_CONTENT_PAGE_SIZE {
+                    return Err(Error::InvalidRequest(format!(
+                        "'limit' must be between 1 and {} with include=content",
+                        MAX_CONTENT_PAGE_SIZE
+                    )));
+                }
+                Ok(Some((limit, self.page.unwrap_or_else(|| articles.first_page()))))
+            }
+            Some(other) => Err(Error::InvalidRequest(format!(
+                "unknown include '{}' (expected: content)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Adds the `X-Total-Count` header to a successful listing of `total` articles and,
+/// when it is page `page` of `limit` articles, a `Link` header to the first, last
+/// and neighbouring pages, numbered from the configured first page.
+fn with_page_headers(
+    req: &HttpRequest,
+    articles: &Articles,
+    mut response: HttpResponse,
+    total: usize,
+    page: Option<(usize, usize)>,
+) -> HttpResponse {
+    if !response.status().is_success() {
+        return response;
+    }
+    let mut links = Vec::new();
+    if let Some((limit, page)) = page {
+        let first = articles.first_page();
+        let last = first + total.div_ceil(limit.max(1)).saturating_sub(1);
+        let link = |page: usize| link_with(req, "page", Some(&page.to_string()));
+        links.push(("first", link(first)));
+        if page > first {
+            links.push(("prev", link((page - 1).min(last))));
+        }
+        if page < last {
+            links.push(("next", link(page + 1)));
+        }
+        links.push(("last", link(last)));
+    }
+    set_pagination_headers(response.headers_mut(), total, &links);
+    response
+}
+
+/// Responds with the complete articles of a page of `summaries`, loaded off the
+/// async workers, with the `fields` of [`Article`] selected.
+async fn complete_articles(
+    articles_data: &Data<Articles>,
+    summaries: Result<Vec<ArticleSummary>, anyhow::Error>,
+    fields: Option<&str>,
+    context: &str,
+) -> HttpResponse {
+    let fields = match FieldSelection::parse::<Article>(fields) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+    let ids = match summaries {
+        Ok(summaries) => summaries.iter().map(|summary| summary.id).collect::<Vec<_>>(),
+        Err(e) => return error_response(e, context),
+    };
+    let articles = articles_data.clone().into_inner();
+    let result = web::block(move || {
+        ids.into_iter()
+            .map(|id| articles.get_article(id).map(|(article, _)| article))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await;
+    match result {
+        Ok(Ok(articles)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: Sparse(articles.as_slice(), &fields),
+            message: None,
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, context),
+        Err(e) => {
+            error!("Article load task failed: {:?}", e);
+            Error::from(e).response(context)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ArticleParams {
+    /// Comma-separated fields to include (all if absent).
+    fields: Option<String>,
+    /// The format of the content (as `markdown_to_html` says if absent).
+    format: Option<ContentFormat>,
+}
+
+#[derive(Deserialize)]
+struct SummaryParams {
+    /// Comma-separated fields to include (all if absent).
+    fields: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeleteParams {
+    /// Leave a tombstone, so the article's ID reports it as gone.
+    #[serde(default)]
+    tombstone: bool,
+    /// The article replacing the deleted one; implies `tombstone`.
+    successor: Option<ArticleId>,
 }
 
 /// Retrieves a list of articles with optional pagination
-#[get("/api/v1/articles")]
+#[get("/articles")]
 async fn list_articles(
+    req: HttpRequest,
     articles_data: Data<Articles>,
     query: Query<PaginationParams>,
 ) -> impl Responder {
+    let total = articles_data.get_article_summary_count();
+    match query.content_page(&articles_data) {
+        Ok(Some((limit, page))) => {
+            let summaries = articles_data.list_article_summaries_paginated(limit, page);
+            let context = "Failed to retrieve articles";
+            let response = complete_articles(&articles_data, summaries, query.fields.as_deref(), context).await;
+            return with_page_headers(&req, &articles_data, response, total, Some((limit, page)));
+        }
+        Ok(None) => {}
+        Err(e) => return e.error_response(),
+    }
+    let fields = match FieldSelection::parse::<ArticleSummary>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+
     // If both limit and page are provided, use pagination
-    if let (Some(limit), Some(page)) = (query.limit, query.page) {
+    let response = if let (Some(limit), Some(page)) = (query.limit, query.page) {
         match articles_data.list_article_summaries_paginated(limit, page) {
             Ok(articles) => HttpResponse::Ok().json(ApiResponse {
                 success: true,
-                data: articles,
+                data: Sparse(articles.as_slice(), &fields),
                 message: None,
+                error_code: None,
             }),
-            Err(e) => {
-                error!("Error retrieving paginated articles: {:?}", e);
-                HttpResponse::BadRequest().json(ApiResponse::<()> {
-                    success: false,
-                    data: (),
-                    message: Some("Invalid pagination parameters".into()),
-                })
-            }
+            Err(e) => error_response(e, "Failed to retrieve articles"),
         }
     } else {
         // If no pagination parameters, return all articles
         match articles_data.list_article_summaries() {
             Ok(articles) => HttpResponse::Ok().json(ApiResponse {
                 success: true,
-                data: articles,
+                data: Sparse(articles.as_slice(), &fields),
                 message: None,
+                error_code: None,
             }),
             Err(e) => {
                 error!("Error retrieving articles: {:?}", e);
-                HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                    success: false,
-                    data: (),
-                    message: Some("Failed to retrieve articles".into()),
-                })
+                Error::from(e).response("Failed to retrieve articles")
             }
         }
-    }
+    };
+    with_page_headers(&req, &articles_data, response, total, query.limit.zip(query.page))
 }
 
 /// Get total number of pages for articles
-#[get("/api/v1/articles/pages")]
+#[get("/articles/pages")]
 async fn get_article_pages(
     articles_data: Data<Articles>,
     query: Query<PaginationParams>,
 ) -> impl Responder {
-    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let limit = query.limit.unwrap_or_else(|| default_page_size(&articles_data));
     let pages = articles_data.get_article_summary_page_count(limit);
     HttpResponse::Ok().json(ApiResponse {
         success: true,
         data: pages,
         message: None,
+        error_code: None,
+    })
+}
+
+#[derive(Deserialize)]
+struct ChangesParams {
+    /// A generation from an earlier response, or a Unix timestamp.
+    #[serde(default)]
+    since: u64,
+}
+
+/// Lists the IDs of articles added, updated and removed since a generation or time
+#[get("/articles/changes")]
+async fn get_article_changes(articles_data: Data<Articles>, query: Query<ChangesParams>) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: articles_data.changes_since(query.since),
+        message: None,
+        error_code: None,
     })
 }
 
-/// Retrieves a specific article by ID
-#[get("/api/v1/articles/{id}")]
+/// Retrieves a specific article by ID, redirecting requests for its aliases
+#[get("/articles/{id}")]
 async fn get_article(
     articles_data: Data<Articles>,
-    cache_recorder: Data<Mutex<CacheHit>>,
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<ArticleParams>,
+) -> impl Responder {
+    let article_id = match resolve_article(&req, &articles_data, &path) {
+        Ok(article_id) => article_id,
+        Err(response) => return response,
+    };
+    let fields = match FieldSelection::parse::<Article>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+    // Reading and rendering the article blocks, so it's kept off the async workers
+    let format = query.format;
+    let articles = articles_data.clone().into_inner();
+    let result = web::block(move || match format {
+        Some(format) => articles.get_article_as(article_id, format),
+        None => articles.get_article(article_id),
+    })
+    .await;
+    match result {
+        Ok(Ok((article, _))) => {
+            let response = ApiResponse {
+                success: true,
+                data: Sparse(&public_article(&articles_data, &req, &article), &fields),
+                message: None,
+                error_code: None,
+            };
+            article_json(&req, &response, &article.content).await
+        }
+        Ok(Err(e)) => error_response(e, &format!("Failed to load article {}", article_id)),
+        Err(e) => {
+            error!("Article load task failed: {:?}", e);
+            Error::from(e).response(&format!("Failed to load article {}", article_id))
+        }
+    }
+}
+
+/// Retrieves the summary of an article from the index, without reading or rendering
+/// its content
+#[get("/articles/{id}/summary")]
+async fn get_article_summary(
+    articles_data: Data<Articles>,
+    req: HttpRequest,
     path: Path<ArticleId>,
+    query: Query<SummaryParams>,
 ) -> impl Responder {
     let article_id = path.into_inner();
-    match articles_data.get_article(article_id) {
-        Ok((article, cache_status)) => {
-            // Record cache hit or miss
-            {
-                let mut recorder = cache_recorder.lock().unwrap();
-                match cache_status {
-                    CachedStatus::Cached => recorder.hit(),
-                    CachedStatus::NotCached => recorder.miss(),
-                }
-            }
+    let fields = match FieldSelection::parse::<ArticleSummary>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+    if let Err(e) = check_access(&req, &articles_data, article_id) {
+        return e.error_response();
+    }
+    match articles_data.get_summary(article_id) {
+        Ok(summary) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: Sparse(&summary, &fields),
+            message: None,
+            error_code: None,
+        }),
+        Err(e) => error_response(e, &format!("Failed to load article {}", article_id)),
+    }
+}
+
+/// The social metadata of an article, as tags and as the HTML rendering them along
+/// with the article's structured data.
+#[derive(Serialize)]
+struct SocialMetadata {
+    tags: Vec<MetaTag>,
+    html: String,
+}
+
+/// Retrieves the OpenGraph and Twitter Card metadata of an article
+#[get("/articles/{id}/social")]
+async fn get_social_metadata(
+    articles_data: Data<Articles>,
+    req: HttpRequest,
+    path: Path<ArticleId>,
+) -> impl Responder {
+    let article_id = path.into_inner();
+    if let Err(e) = check_access(&req, &articles_data, article_id) {
+        return e.error_response();
+    }
+    match articles_data.get_metainfo(article_id) {
+        Ok(metainfo) => {
+            let config = articles_data.config();
+            let site = site_config(&config, &req);
+            let tags = social::social_tags(&metainfo, &site);
+            let structured_data = social::structured_data(&metainfo, &site);
+            let html = social::to_html(&tags, &structured_data);
             HttpResponse::Ok().json(ApiResponse {
                 success: true,
-                data: article,
+                data: SocialMetadata { tags, html },
                 message: None,
+                error_code: None,
             })
         }
+        Err(e) => error_response(e, &format!("Failed to load article {}", article_id)),
+    }
+}
+
+/// Retrieves the schema.org `BlogPosting` of an article as a JSON-LD document
+#[get("/articles/{id}/jsonld")]
+async fn get_structured_data(
+    articles_data: Data<Articles>,
+    req: HttpRequest,
+    path: Path<ArticleId>,
+) -> impl Responder {
+    let article_id = path.into_inner();
+    if let Err(e) = check_access(&req, &articles_data, article_id) {
+        return e.error_response();
+    }
+    match articles_data.get_metainfo(article_id) {
+        Ok(metainfo) => HttpResponse::Ok()
+            .content_type("application/ld+json")
+            .json(social::structured_data(&metainfo, &site_config(&articles_data.config(), &req))),
+        Err(e) => error_response(e, &format!("Failed to load article {}", article_id)),
+    }
+}
+
+/// Adds a reader's reaction of a kind to an article, returning its reaction counts
+#[post("/articles/{id}/reactions/{kind}")]
+async fn add_reaction(
+    articles_data: Data<Articles>,
+    reactions: Option<Data<Reactions>>,
+    req: HttpRequest,
+    path: Path<(ArticleId, String)>,
+) -> impl Responder {
+    let (article_id, kind) = path.into_inner();
+    let Some(reactions) = reactions else {
+        return Error::InvalidRequest("reactions are disabled".into()).error_response();
+    };
+    if let Err(e) = check_access(&req, &articles_data, article_id) {
+        return e.error_response();
+    }
+    if let Err(e) = articles_data.get_metainfo(article_id) {
+        return error_response(e, &format!("Failed to load article {}", article_id));
+    }
+    let client = proxy::client_ip(req.head(), &articles_data.config().proxy);
+    let reactions = reactions.into_inner();
+    let context = format!("Failed to add a reaction to article {}", article_id);
+    match web::block(move || reactions.add(article_id, &kind, client)).await {
+        Ok(Ok(counts)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: counts,
+            message: None,
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, &context),
         Err(e) => {
-            warn!("Article ID {} not found: {:?}", article_id, e);
-            HttpResponse::NotFound().json(ApiResponse::<()> {
-                success: false,
-                data: (),
-                message: Some("Article not found".into()),
+            error!("Reaction task failed: {:?}", e);
+            Error::from(e).response(&context)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NewComment {
+    author: String,
+    body: String,
+}
+
+/// The comments of the served articles, if enabled, with `article_id` readable
+/// and existing.
+fn article_comments(
+    req: &HttpRequest,
+    articles: &Articles,
+    article_id: ArticleId,
+) -> Result<Arc<Comments>, HttpResponse> {
+    let Some(comments) = articles.comments() else {
+        return Err(Error::InvalidRequest("comments are disabled".into()).error_response());
+    };
+    check_access(req, articles, article_id).map_err(|e| e.error_response())?;
+    articles
+        .get_metainfo(article_id)
+        .map_err(|e| error_response(e, &format!("Failed to load article {}", article_id)))?;
+    Ok(Arc::clone(comments))
+}
+
+/// Runs `task` on `comments` off the async workers, responding with its result
+/// and `status`, or with its error.
+async fn comment_task<T, F>(
+    comments: Arc<Comments>,
+    status: StatusCode,
+    context: &str,
+    task: F,
+) -> HttpResponse
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(&Comments) -> anyhow::Result<(T, Option<String>)> + Send + 'static,
+{
+    match web::block(move || task(&comments)).await {
+        Ok(Ok((data, message))) => HttpResponse::build(status).json(ApiResponse {
+            success: true,
+            data,
+            message,
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, context),
+        Err(e) => {
+            error!("Comment task failed: {:?}", e);
+            Error::from(e).response(context)
+        }
+    }
+}
+
+/// Lists the approved comments on an article, oldest first
+#[get("/articles/{id}/comments")]
+async fn list_comments(
+    articles_data: Data<Articles>,
+    req: HttpRequest,
+    path: Path<ArticleId>,
+) -> impl Responder {
+    let article_id = path.into_inner();
+    let comments = match article_comments(&req, &articles_data, article_id) {
+        Ok(comments) => comments,
+        Err(response) => return response,
+    };
+    let context = format!("Failed to load the comments on article {}", article_id);
+    comment_task(comments, StatusCode::OK, &context, move |comments| {
+        Ok((comments.approved(article_id)?, None))
+    })
+    .await
+}
+
+/// Posts a reader's comment on an article, held for moderation unless disabled
+#[post("/articles/{id}/comments")]
+async fn post_comment(
+    articles_data: Data<Articles>,
+    req: HttpRequest,
+    path: Path<ArticleId>,
+    body: Json<NewComment>,
+) -> impl Responder {
+    let article_id = path.into_inner();
+    let comments = match article_comments(&req, &articles_data, article_id) {
+        Ok(comments) => comments,
+        Err(response) => return response,
+    };
+    let client = proxy::client_ip(req.head(), &articles_data.config().proxy);
+    let NewComment { author, body } = body.into_inner();
+    let context = format!("Failed to post a comment on article {}", article_id);
+    comment_task(comments, StatusCode::CREATED, &context, move |comments| {
+        let comment = comments.post(article_id, &author, &body, client)?;
+        let message = match comment.status {
+            CommentStatus::Approved => "Comment published",
+            CommentStatus::Pending | CommentStatus::Spam => "Comment held for moderation",
+        };
+        Ok((comment, Some(message.to_string())))
+    })
+    .await
+}
+
+/// Lists the comments waiting for moderation, pending or spam (requires an `admin` key)
+#[get("/comments/queue")]
+async fn list_held_comments(_key: ApiKey<Admin>, articles_data: Data<Articles>) -> impl Responder {
+    let Some(comments) = articles_data.comments() else {
+        return Error::InvalidRequest("comments are disabled".into()).error_response();
+    };
+    comment_task(Arc::clone(comments), StatusCode::OK, "Failed to load the comments", |comments| {
+        Ok((comments.held()?, None))
+    })
+    .await
+}
+
+/// Approves a held comment, showing it with its article (requires an `admin` key)
+#[post("/comments/{comment_id}/approve")]
+async fn approve_comment(
+    _key: ApiKey<Admin>,
+    articles_data: Data<Articles>,
+    path: Path<CommentId>,
+) -> impl Responder {
+    let Some(comments) = articles_data.comments() else {
+        return Error::InvalidRequest("comments are disabled".into()).error_response();
+    };
+    let id = path.into_inner();
+    let context = format!("Failed to approve comment {}", id);
+    comment_task(Arc::clone(comments), StatusCode::OK, &context, move |comments| {
+        Ok((comments.approve(id)?, Some("Comment approved".to_string())))
+    })
+    .await
+}
+
+/// Deletes a comment, held or approved (requires an `admin` key)
+#[delete("/comments/{comment_id}")]
+async fn delete_comment(
+    _key: ApiKey<Admin>,
+    articles_data: Data<Articles>,
+    path: Path<CommentId>,
+) -> impl Responder {
+    let Some(comments) = articles_data.comments() else {
+        return Error::InvalidRequest("comments are disabled".into()).error_response();
+    };
+    let id = path.into_inner();
+    let context = format!("Failed to delete comment {}", id);
+    comment_task(Arc::clone(comments), StatusCode::OK, &context, move |comments| {
+        Ok((comments.delete(id)?, Some("Comment deleted".to_string())))
+    })
+    .await
+}
+
+/// Creates a new article from its metadata and Markdown source (requires an `author` key)
+#[post("/articles")]
+async fn create_article(
+    _key: ApiKey<Author>,
+    articles_data: Data<Articles>,
+    draft: web::Json<ArticleDraft>,
+) -> impl Responder {
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.create_article(&draft)).await {
+        Ok(Ok(article)) => HttpResponse::Created().json(ApiResponse {
+            success: true,
+            data: article,
+            message: None,
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, "Failed to write article"),
+        Err(e) => {
+            error!("Article creation task failed: {:?}", e);
+            Error::from(e).response("Failed to create article")
+        }
+    }
+}
+
+/// Publishes an article bundle (`metainfo.toml`, Markdown and assets) uploaded
+/// either as a zip archive or as the files of a multipart form (requires an `author` key)
+#[cfg(feature = "uploads")]
+#[post("/articles/upload")]
+async fn upload_article(
+    _key: ApiKey<Author>,
+    articles_data: Data<Articles>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> impl Responder {
+    let max_bytes = articles_data.config().mainconfig.max_upload_bytes;
+    let files = match read_bundle(&req, payload, max_bytes).await {
+        Ok(files) => files,
+        Err(e) => return e.response("Failed to read the upload"),
+    };
+
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.create_from_bundle(files)).await {
+        Ok(Ok(article)) => HttpResponse::Created().json(ApiResponse {
+            success: true,
+            data: article,
+            message: None,
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, "Failed to write article"),
+        Err(e) => {
+            error!("Article upload task failed: {:?}", e);
+            Error::from(e).response("Failed to create article")
+        }
+    }
+}
+
+/// Collect the files of an uploaded bundle, enforcing `max_bytes` (the configured
+/// `max_upload_bytes`) on the request and, for zip archives, on the unpacked contents.
+#[cfg(feature = "uploads")]
+async fn read_bundle(
+    req: &HttpRequest,
+    payload: web::Payload,
+    max_bytes: usize,
+) -> Result<Vec<BundleFile>, Error> {
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    if content_type.starts_with("application/zip") {
+        let body = payload
+            .to_bytes_limited(max_bytes)
+            .await
+            .map_err(|_| Error::too_large(max_bytes))?
+            .map_err(|e| Error::InvalidRequest(e.to_string()))?;
+        return bundle::from_zip(&body, max_bytes);
+    }
+    if !content_type.starts_with("multipart/form-data") {
+        return Err(Error::InvalidRequest(
+            "expected a zip archive or a multipart/form-data upload".into(),
+        ));
+    }
+
+    // Every file part becomes a file of the bundle; a single zip part is unpacked
+    let mut multipart = Multipart::new(req.headers(), payload);
+    let mut files = Vec::new();
+    let mut total_bytes = 0usize;
+    while let Some(field) = multipart.next().await {
+        let mut field = field.map_err(|e| Error::InvalidRequest(e.to_string()))?;
+        let Some(file_name) = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .map(str::to_string)
+        else {
+            continue; // not a file
+        };
+
+        let mut content = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| Error::InvalidRequest(e.to_string()))?;
+            total_bytes += chunk.len();
+            if total_bytes > max_bytes {
+                return Err(Error::too_large(max_bytes));
+            }
+            content.extend_from_slice(&chunk);
+        }
+
+        if file_name.ends_with(".zip") {
+            files.extend(bundle::from_zip(&content, max_bytes)?);
+        } else {
+            files.push(BundleFile {
+                path: file_name.into(),
+                content,
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// Replaces the metadata and Markdown source of an article (requires an `author` key)
+#[put("/articles/{id}")]
+async fn update_article(
+    _key: ApiKey<Author>,
+    articles_data: Data<Articles>,
+    path: Path<ArticleId>,
+    draft: web::Json<ArticleDraft>,
+) -> impl Responder {
+    let article_id = path.into_inner();
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.update_article(article_id, &draft)).await {
+        Ok(Ok(article)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: article,
+            message: None,
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, "Failed to write article"),
+        Err(e) => {
+            error!("Article update task failed: {:?}", e);
+            Error::from(e).response("Failed to update article")
+        }
+    }
+}
+
+/// Deletes an article, optionally leaving a tombstone (requires an `author` key)
+#[delete("/articles/{id}")]
+async fn delete_article(
+    _key: ApiKey<Author>,
+    articles_data: Data<Articles>,
+    path: Path<ArticleId>,
+    query: Query<DeleteParams>,
+) -> impl Responder {
+    let article_id = path.into_inner();
+    let DeleteParams { tombstone, successor } = query.into_inner();
+    let articles = articles_data.into_inner();
+    let result = web::block(move || {
+        if tombstone || successor.is_some() {
+            articles.bury_article(article_id, successor)
+        } else {
+            articles.delete_article(article_id)
+        }
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            data: (),
+            message: Some("Article deleted".into()),
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, "Failed to write article"),
+        Err(e) => {
+            error!("Article deletion task failed: {:?}", e);
+            Error::from(e).response("Failed to delete article")
+        }
+    }
+}
+
+/// The outcome of a configuration reload.
+#[derive(Serialize)]
+struct ConfigReload {
+    /// Likely mistakes in the configuration, such as unknown keys.
+    warnings: Vec<String>,
+    /// Changed settings that only take effect after a restart.
+    restart_required: Vec<&'static str>,
+}
+
+/// Reports uptime, article and tag counts, cache occupancy and the last index load
+#[get("/status")]
+async fn get_status(articles_data: Data<Articles>, start_time: Data<StartTime>) -> impl Responder {
+    let articles = articles_data.into_inner();
+    match web::block(move || Status::collect(&articles, &start_time)).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: status,
+            message: None,
+            error_code: None,
+        }),
+        Err(e) => {
+            error!("Status task failed: {:?}", e);
+            Error::from(e).response("Failed to collect status")
+        }
+    }
+}
+
+/// How many exported articles are buffered ahead of a slow client.
+const EXPORT_BUFFER: usize = 16;
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    /// One JSON array.
+    #[default]
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// Streams every article with its metadata and Markdown source, as JSON or NDJSON,
+/// for backups and migrations (requires a `read` key)
+#[get("/export")]
+async fn export_articles(
+    _key: ApiKey<Read>,
+    articles_data: Data<Articles>,
+    query: Query<ExportParams>,
+) -> impl Responder {
+    let articles = articles_data.get_ref().clone();
+    let format = query.format;
+    let (sender, receiver) = tokio::sync::mpsc::channel(EXPORT_BUFFER);
+    // Articles are read off the async workers, one chunk each, as the client keeps up
+    rt::task::spawn_blocking(move || {
+        let mut chunk = Vec::new();
+        if format == ExportFormat::Json {
+            chunk.push(b'[');
+        }
+        for (i, draft) in articles.export().enumerate() {
+            let written = draft.and_then(|draft| {
+                if format == ExportFormat::Json && i > 0 {
+                    chunk.push(b',');
+                }
+                serde_json::to_writer(&mut chunk, &draft)?;
+                Ok(())
+            });
+            if let Err(e) = written {
+                error!("Export failed: {:?}", e);
+                let _ = sender.blocking_send(Err(e.into()));
+                return;
+            }
+            if format == ExportFormat::Ndjson {
+                chunk.push(b'\n');
+            }
+            if sender.blocking_send(Ok(std::mem::take(&mut chunk).into())).is_err() {
+                return; // the client went away
+            }
+        }
+        if format == ExportFormat::Json {
+            chunk.push(b']');
+        }
+        let _ = sender.blocking_send(Ok(chunk.into()));
+    });
+    let content_type = match format {
+        ExportFormat::Json => "application/json",
+        ExportFormat::Ndjson => "application/x-ndjson",
+    };
+    HttpResponse::Ok().content_type(content_type).body(Streamed(receiver))
+}
+
+#[derive(Deserialize)]
+struct ImportParams {
+    #[serde(default)]
+    on_conflict: OnConflict,
+}
+
+/// Writes the articles of an export (JSON or NDJSON) or of a zip archive of article
+/// directories to disk, then reloads the index (requires an `author` key)
+#[post("/import")]
+async fn import_articles(
+    _key: ApiKey<Author>,
+    articles_data: Data<Articles>,
+    req: HttpRequest,
+    query: Query<ImportParams>,
+    payload: web::Payload,
+) -> impl Responder {
+    let max_bytes = articles_data.config().mainconfig.max_upload_bytes;
+    let items = match read_import(&req, payload, max_bytes).await {
+        Ok(items) => items,
+        Err(e) => return e.response("Failed to read the import"),
+    };
+
+    let articles = articles_data.into_inner();
+    let on_conflict = query.on_conflict;
+    match web::block(move || articles.import(items, on_conflict)).await {
+        Ok(Ok(report)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: report,
+            message: None,
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, "Failed to import articles"),
+        Err(e) => {
+            error!("Article import task failed: {:?}", e);
+            Error::from(e).response("Failed to import articles")
+        }
+    }
+}
+
+/// Parse an import body by its content type, enforcing `max_bytes` (the configured
+/// `max_upload_bytes`) on the request.
+async fn read_import(
+    req: &HttpRequest,
+    payload: web::Payload,
+    max_bytes: usize,
+) -> Result<Vec<ImportItem>, Error> {
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    let body = payload
+        .to_bytes_limited(max_bytes)
+        .await
+        .map_err(|_| Error::too_large(max_bytes))?
+        .map_err(|e| Error::InvalidRequest(e.to_string()))?;
+
+    let draft = |item: Box<ArticleDraft>| ImportItem::Draft(item);
+    if content_type.starts_with("application/json") {
+        let drafts: Vec<Box<ArticleDraft>> =
+            serde_json::from_slice(&body).map_err(|e| Error::InvalidRequest(e.to_string()))?;
+        return Ok(drafts.into_iter().map(draft).collect());
+    }
+    if content_type.starts_with("application/x-ndjson") {
+        let text = std::str::from_utf8(&body).map_err(|e| Error::InvalidRequest(e.to_string()))?;
+        return text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map(draft)
+                    .map_err(|e| Error::InvalidRequest(format!("line {}: {}", i + 1, e)))
             })
+            .collect();
+    }
+    #[cfg(feature = "uploads")]
+    if content_type.starts_with("application/zip") {
+        let files = bundle::from_zip(&body, max_bytes)?;
+        return Ok(bundle::split_directories(files).into_iter().map(ImportItem::Bundle).collect());
+    }
+    Err(Error::InvalidRequest(if cfg!(feature = "uploads") {
+        "expected application/json, application/x-ndjson or application/zip".into()
+    } else {
+        "expected application/json or application/x-ndjson".into()
+    }))
+}
+
+/// Reports article, word, tag and year totals of the content
+#[get("/stats/content")]
+async fn get_content_stats(articles_data: Data<Articles>) -> impl Responder {
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.content_stats()).await {
+        Ok(stats) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: &*stats,
+            message: None,
+            error_code: None,
+        }),
+        Err(e) => {
+            error!("Content statistics task failed: {:?}", e);
+            Error::from(e).response("Failed to compute content statistics")
         }
     }
 }
 
-/// Refreshes the articles index
-#[post("/api/v1/articles/index/refresh")]
-async fn refresh_index(articles_data: Data<Articles>) -> impl Responder {
-    match articles_data.refresh_index() {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::<()> {
+/// Reports the latency percentiles and response statuses of each route, recorded
+/// while `record_request_stats` is enabled
+#[get("/stats/requests")]
+async fn get_request_stats(stats: Data<RequestStats>) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: stats.stats(),
+        message: None,
+        error_code: None,
+    })
+}
+
+/// Resets request statistics (requires an `admin` key once keys are configured)
+#[post("/stats/requests/reset")]
+async fn reset_request_stats(_operator: Operator, stats: Data<RequestStats>) -> impl Responder {
+    stats.reset();
+    HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        data: (),
+        message: Some("Request statistics have been reset".into()),
+        error_code: None,
+    })
+}
+
+/// Reports the version, git commit, build time and cargo features of the server
+#[get("/version")]
+async fn get_version() -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: version::build_info(),
+        message: None,
+        error_code: None,
+    })
+}
+
+/// The configuration the server is running with.
+#[derive(Serialize)]
+struct EffectiveConfig<'a> {
+    /// The file it was loaded from.
+    path: String,
+    /// The settings given on the command line, which take precedence over the file.
+    overrides: &'a Overrides,
+    /// Every setting, including the defaults of those the file leaves out, with
+    /// secrets masked.
+    config: &'a Config,
+}
+
+/// Returns the effective configuration with secrets masked (requires an `admin` key)
+#[get("/admin/config")]
+async fn get_config(_key: ApiKey<Admin>, reloader: Data<ConfigReloader>) -> impl Responder {
+    let source = reloader.source();
+    let config = reloader.config();
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: EffectiveConfig {
+            path: source.path.display().to_string(),
+            overrides: &source.overrides,
+            config: &config,
+        },
+        message: None,
+        error_code: None,
+    })
+}
+
+/// Reloads `config.toml` without restarting the server (requires an `admin` key)
+#[post("/admin/config/reload")]
+async fn reload_config(_key: ApiKey<Admin>, reloader: Data<ConfigReloader>) -> impl Responder {
+    let reloader = reloader.into_inner();
+    match web::block(move || reloader.reload()).await {
+        Ok(Ok(reload)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: ConfigReload {
+                warnings: reload.warnings,
+                restart_required: reload.restart_required,
+            },
+            message: Some("Configuration reloaded".into()),
+            error_code: None,
+        }),
+        Ok(Err(e)) => error_response(e, "Failed to reload configuration"),
+        Err(e) => {
+            error!("Configuration reload task failed: {:?}", e);
+            Error::from(e).response("Failed to reload configuration")
+        }
+    }
+}
+
+#[cfg(feature = "backup")]
+#[derive(Deserialize)]
+struct BackupParams {
+    #[serde(default)]
+    render_cache: bool,
+}
+
+/// Streams a tar.gz of the articles directories, and optionally of the render cache,
+/// for off-site backups (requires an `admin` key)
+#[cfg(feature = "backup")]
+#[get("/admin/backup")]
+async fn get_backup(
+    _key: ApiKey<Admin>,
+    reloader: Data<ConfigReloader>,
+    query: Query<BackupParams>,
+) -> impl Responder {
+    let sources = match backup::sources(&reloader.config(), query.render_cache) {
+        Ok(sources) => sources,
+        Err(e) => return e.response("Failed to back up content"),
+    };
+    let (sender, receiver) = tokio::sync::mpsc::channel(EXPORT_BUFFER);
+    // The archive is compressed off the async workers, one chunk at a time
+    rt::task::spawn_blocking(move || {
+        let written = backup::write_archive(&sources, ChunkWriter::new(sender.clone()))
+            .and_then(|mut writer| std::io::Write::flush(&mut writer));
+        match written {
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {} // the client went away
+            Err(e) => {
+                error!("Backup failed: {:?}", e);
+                let _ = sender.blocking_send(Err(anyhow::Error::from(e).into()));
+            }
+            Ok(()) => {}
+        }
+    });
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header((
+            actix_web::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"henkaiki-backup-{}.tar.gz\"", now),
+        ))
+        .body(Streamed(receiver))
+}
+
+/// Refreshes the articles index (requires an `admin` key once keys are configured)
+#[post("/articles/index/refresh")]
+async fn refresh_index(_operator: Operator, articles_data: Data<Articles>) -> impl Responder {
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.refresh_index()).await {
+        Ok(Ok(())) => HttpResponse::Ok().json(ApiResponse::<()> {
             success: true,
             data: (),
             message: Some("Index refreshed".into()),
+            error_code: None,
         }),
-        Err(e) => {
+        Ok(Err(e)) => {
             error!("Error refreshing index: {:?}", e);
-            HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                success: false,
-                data: (),
-                message: Some("Failed to refresh index".into()),
-            })
+            Error::from(e).response("Failed to refresh index")
+        }
+        Err(e) => {
+            error!("Index refresh task failed: {:?}", e);
+            Error::from(e).response("Failed to refresh index")
         }
     }
 }
 
-/// Clears the articles cache
-#[delete("/api/v1/articles/cache")]
-async fn clear_cache(articles_data: Data<Articles>) -> impl Responder {
+/// Reports the outcome of every entry examined by the last index load, private
+/// articles included (requires an `admin` key once keys are configured)
+#[get("/articles/index/report")]
+async fn get_index_report(_operator: Operator, articles_data: Data<Articles>) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: &*articles_data.last_scan_report(),
+        message: None,
+        error_code: None,
+    })
+}
+
+/// Re-scans the articles and reports every problem found, without changing the live index
+/// (requires an `admin` key once keys are configured)
+#[post("/maintenance/validate")]
+async fn validate_articles(_operator: Operator, articles_data: Data<Articles>) -> impl Responder {
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.validate()).await {
+        Ok(Ok(report)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: report,
+            message: None,
+            error_code: None,
+        }),
+        Ok(Err(e)) => {
+            error!("Error validating articles: {:?}", e);
+            Error::from(e).response("Failed to validate articles")
+        }
+        Err(e) => {
+            error!("Validation task failed: {:?}", e);
+            Error::from(e).response("Failed to validate articles")
+        }
+    }
+}
+
+/// Clears the articles cache (requires an `admin` key once keys are configured)
+#[delete("/articles/cache")]
+async fn clear_cache(_operator: Operator, articles_data: Data<Articles>) -> impl Responder {
     articles_data.clear_cache();
     HttpResponse::Ok().json(ApiResponse::<()> {
         success: true,
         data: (),
         message: Some("Cache cleared".into()),
+        error_code: None,
     })
 }
 
-/// Refreshes a specific article in the cache
-#[post("/api/v1/articles/{id}/refresh")]
+/// Refreshes a specific article in the cache (requires an `admin` key once keys are
+/// configured)
+#[post("/articles/{id}/refresh")]
 async fn refresh_article(
+    _operator: Operator,
     articles_data: Data<Articles>,
     path: Path<ArticleId>,
 ) -> impl Responder {
     let article_id = path.into_inner();
-    match articles_data.refresh_article(article_id) {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::<()> {
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.refresh_article(article_id)).await {
+        Ok(Ok(_)) => HttpResponse::Ok().json(ApiResponse::<()> {
             success: true,
             data: (),
             message: Some("Article refreshed".into()),
+            error_code: None,
         }),
+        Ok(Err(e)) => error_response(e, &format!("Failed to refresh article {}", article_id)),
         Err(e) => {
-            error!("Error refreshing article {}: {:?}", article_id, e);
-            HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                success: false,
-                data: (),
-                message: Some("Failed to refresh article".into()),
-            })
+            error!("Article refresh task failed: {:?}", e);
+            Error::from(e).response(&format!("Failed to refresh article {}", article_id))
         }
     }
 }
 
 /// Retrieves articles by tag with optional pagination
-#[get("/api/v1/articles/tags/{tag}")]
+#[get("/articles/tags/{tag}")]
 async fn list_articles_by_tag(
+    req: HttpRequest,
     articles_data: Data<Articles>,
     path: Path<String>,
     query: Query<PaginationParams>,
 ) -> impl Responder {
     let tag = path.into_inner();
-    
+    let total = articles_data.get_article_summary_by_tag_count(&tag);
+    match query.content_page(&articles_data) {
+        Ok(Some((limit, page))) => {
+            let summaries = articles_data.list_article_summaries_by_tag_paginated(&tag, limit, page);
+            let context = format!("Failed to retrieve articles by tag '{}'", tag);
+            let response = complete_articles(&articles_data, summaries, query.fields.as_deref(), &context).await;
+            return with_page_headers(&req, &articles_data, response, total, Some((limit, page)));
+        }
+        Ok(None) => {}
+        Err(e) => return e.error_response(),
+    }
+    let fields = match FieldSelection::parse::<ArticleSummary>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+
     // If both limit and page are provided, use pagination
-    if let (Some(limit), Some(page)) = (query.limit, query.page) {
+    let response = if let (Some(limit), Some(page)) = (query.limit, query.page) {
         match articles_data.list_article_summaries_by_tag_paginated(&tag, limit, page) {
             Ok(articles) => HttpResponse::Ok().json(ApiResponse {
                 success: true,
-                data: articles,
+                data: Sparse(articles.as_slice(), &fields),
                 message: None,
+                error_code: None,
             }),
-            Err(e) => {
-                error!("Error retrieving paginated articles by tag '{}': {:?}", tag, e);
-                HttpResponse::BadRequest().json(ApiResponse::<()> {
-                    success: false,
-                    data: (),
-                    message: Some("Invalid pagination parameters".into()),
-                })
-            }
+            Err(e) => error_response(e, &format!("Failed to retrieve articles by tag '{}'", tag)),
         }
     } else {
         // If no pagination parameters, return all articles with the tag
         match articles_data.list_article_summaries_by_tag(&tag) {
             Ok(articles) => HttpResponse::Ok().json(ApiResponse {
                 success: true,
-                data: articles,
+                data: Sparse(articles.as_slice(), &fields),
                 message: None,
+                error_code: None,
             }),
             Err(e) => {
                 error!("Error retrieving articles by tag '{}': {:?}", tag, e);
-                HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                    success: false,
-                    data: (),
-                    message: Some("Failed to retrieve articles by tag".into()),
-                })
+                Error::from(e).response("Failed to retrieve articles by tag")
             }
         }
-    }
+    };
+    with_page_headers(&req, &articles_data, response, total, query.limit.zip(query.page))
 }
 
 /// Get total number of pages for articles with a specific tag
-#[get("/api/v1/articles/tags/{tag}/pages")]
+#[get("/articles/tags/{tag}/pages")]
 async fn get_tag_pages(
     articles_data: Data<Articles>,
     path: Path<String>,
     query: Query<PaginationParams>,
 ) -> impl Responder {
     let tag = path.into_inner();
-    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let limit = query.limit.unwrap_or_else(|| default_page_size(&articles_data));
     let pages = articles_data.get_article_summary_by_tag_page_count(&tag, limit);
     HttpResponse::Ok().json(ApiResponse {
         success: true,
         data: pages,
         message: None,
+        error_code: None,
     })
 }
 
-/// Retrieves cache statistics
-#[get("/api/v1/articles/cache/stats")]
-async fn get_cache_stats(cache_recorder: Data<Mutex<CacheHit>>) -> impl Responder {
-    let stats = cache_recorder.lock().unwrap();
-    let cache_stats = CacheStats {
-        cache_hit: stats.cache_hit,
-        cache_miss: stats.cache_miss,
-        hit_rate: stats.hit_rate(),
+/// Retrieves articles by keyword with optional pagination
+#[get("/articles/keywords/{keyword}")]
+async fn list_articles_by_keyword(
+    req: HttpRequest,
+    articles_data: Data<Articles>,
+    path: Path<String>,
+    query: Query<PaginationParams>,
+) -> impl Responder {
+    let keyword = path.into_inner();
+    let total = articles_data.get_article_summary_by_keyword_count(&keyword);
+    match query.content_page(&articles_data) {
+        Ok(Some((limit, page))) => {
+            let summaries =
+                articles_data.list_article_summaries_by_keyword_paginated(&keyword, limit, page);
+            let context = format!("Failed to retrieve articles by keyword '{}'", keyword);
+            let response = complete_articles(&articles_data, summaries, query.fields.as_deref(), &context).await;
+            return with_page_headers(&req, &articles_data, response, total, Some((limit, page)));
+        }
+        Ok(None) => {}
+        Err(e) => return e.error_response(),
+    }
+    let fields = match FieldSelection::parse::<ArticleSummary>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+
+    let result = match (query.limit, query.page) {
+        (Some(limit), Some(page)) => {
+            articles_data.list_article_summaries_by_keyword_paginated(&keyword, limit, page)
+        }
+        _ => articles_data.list_article_summaries_by_keyword(&keyword),
     };
+    let response = match result {
+        Ok(articles) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: Sparse(articles.as_slice(), &fields),
+            message: None,
+            error_code: None,
+        }),
+        Err(e) => error_response(e, &format!("Failed to retrieve articles by keyword '{}'", keyword)),
+    };
+    with_page_headers(&req, &articles_data, response, total, query.limit.zip(query.page))
+}
+
+/// Get total number of pages for articles with a specific keyword
+#[get("/articles/keywords/{keyword}/pages")]
+async fn get_keyword_pages(
+    articles_data: Data<Articles>,
+    path: Path<String>,
+    query: Query<PaginationParams>,
+) -> impl Responder {
+    let keyword = path.into_inner();
+    let limit = query.limit.unwrap_or_else(|| default_page_size(&articles_data));
+    let pages = articles_data.get_article_summary_by_keyword_page_count(&keyword, limit);
     HttpResponse::Ok().json(ApiResponse {
         success: true,
-        data: cache_stats,
+        data: pages,
         message: None,
+        error_code: None,
     })
 }
 
-/// Resets cache statistics
-#[post("/api/v1/articles/cache/stats/reset")]
-async fn reset_cache_stats(cache_recorder: Data<Mutex<CacheHit>>) -> impl Responder {
-    cache_recorder.lock().unwrap().reset();
+/// Retrieves cache statistics
+#[get("/articles/cache/stats")]
+async fn get_cache_stats(articles: Data<Articles>) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: articles.cache_stats(),
+        message: None,
+        error_code: None,
+    })
+}
+
+/// Resets cache statistics (requires an `admin` key once keys are configured)
+#[post("/articles/cache/stats/reset")]
+async fn reset_cache_stats(_operator: Operator, articles: Data<Articles>) -> impl Responder {
+    articles.reset_cache_stats();
     HttpResponse::Ok().json(ApiResponse::<()> {
         success: true,
         data: (),
         message: Some("Cache statistics have been reset".into()),
+        error_code: None,
     })
 }
 
 /// Parameters for searching articles
 #[derive(Deserialize)]
 struct SearchParams {
+    #[serde(alias = "q")]
     query: String,
+    /// Comma-separated fields the query is matched against (all if absent).
+    search_fields: Option<String>,
+    /// Only find articles with this tag.
+    tag: Option<String>,
+    /// Only find articles with all of these comma-separated tags.
+    tags: Option<String>,
     limit: Option<usize>,
     page: Option<usize>,
+    /// Comma-separated fields to include in each item (all if absent).
+    fields: Option<String>,
 }
 
-/// Searches for articles (title/description) with optional pagination
-#[get("/api/v1/articles/search")]
+/// Searches for articles (title/description/tags/keywords), optionally only those
+/// with some tags, with optional pagination
+#[get("/articles/search")]
 async fn search_articles(
+    req: HttpRequest,
     articles_data: Data<Articles>,
     query: Query<SearchParams>,
 ) -> impl Responder {
     let query_str = query.query.trim();
+    let search_fields = match SearchFields::parse(query.search_fields.as_deref()) {
+        Ok(search_fields) => search_fields,
+        Err(e) => return e.error_response(),
+    };
+    let tags = requested_tags(query.tag.as_deref(), query.tags.as_deref());
+    let fields = match FieldSelection::parse::<ArticleSummary>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
 
+    let total = articles_data.get_search_article_count(query_str, search_fields, &tags);
     // If both limit and page are provided, process paginated results
-    if let (Some(limit), Some(page)) = (query.limit, query.page) {
-        match articles_data.search_articles_paginated(query_str, limit, page) {
+    let response = if let (Some(limit), Some(page)) = (query.limit, query.page) {
+        match articles_data.search_articles_paginated(query_str, search_fields, &tags, limit, page) {
             Ok(articles) => HttpResponse::Ok().json(ApiResponse {
                 success: true,
-                data: articles,
+                data: Sparse(articles.as_slice(), &fields),
                 message: None,
+                error_code: None,
             }),
-            Err(e) => {
-                error!("Error retrieving paginated search results: {:?}", e);
-                HttpResponse::BadRequest().json(ApiResponse::<()> {
-                    success: false,
-                    data: (),
-                    message: Some("Invalid pagination parameters or page out of range".into()),
-                })
-            }
+            Err(e) => error_response(e, "Failed to search articles"),
         }
     } else {
         // If no pagination parameters, return all matching articles
-        match articles_data.search_articles(query_str) {
+        match articles_data.search_articles(query_str, search_fields, &tags) {
             Ok(articles) => HttpResponse::Ok().json(ApiResponse {
                 success: true,
-                data: articles,
+                data: Sparse(articles.as_slice(), &fields),
                 message: None,
+                error_code: None,
             }),
             Err(e) => {
                 error!("Error searching articles: {:?}", e);
-                HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                    success: false,
-                    data: (),
-                    message: Some("Failed to search articles".into()),
-                })
+                Error::from(e).response("Failed to search articles")
             }
         }
-    }
+    };
+    with_page_headers(&req, &articles_data, response, total, query.limit.zip(query.page))
 }
 
 /// Gets total number of pages for a given search query
-#[get("/api/v1/articles/search/pages")]
+#[get("/articles/search/pages")]
 async fn get_search_pages(
     articles_data: Data<Articles>,
     query: Query<SearchParams>,
 ) -> impl Responder {
     let query_str = query.query.trim();
-    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
-    let pages = articles_data.get_search_article_page_count(query_str, limit);
+    let search_fields = match SearchFields::parse(query.search_fields.as_deref()) {
+        Ok(search_fields) => search_fields,
+        Err(e) => return e.error_response(),
+    };
+    let tags = requested_tags(query.tag.as_deref(), query.tags.as_deref());
+    let limit = query.limit.unwrap_or_else(|| default_page_size(&articles_data));
+    let pages = articles_data.get_search_article_page_count(query_str, search_fields, &tags, limit);
     HttpResponse::Ok().json(ApiResponse {
         success: true,
         data: pages,
         message: None,
+        error_code: None,
     })
 }
+
+/// Refreshes the index when the content repository receives a push.
+///
+/// If a `webhook_secret` is configured, the request must carry a valid GitHub-style
+/// `X-Hub-Signature-256` HMAC of the body; otherwise it needs the same API key as
+/// any other refresh of the index.
+#[cfg(feature = "git")]
+#[post("/webhooks/git")]
+async fn git_webhook(
+    articles_data: Data<Articles>,
+    req: actix_web::HttpRequest,
+    body: web::Bytes,
+) -> impl Responder {
+    let config = articles_data.config();
+    let secret = config
+        .storage
+        .git
+        .as_ref()
+        .and_then(|git| git.webhook_secret.as_deref());
+    if let Some(secret) = secret {
+        let signature = req
+            .headers()
+            .get("X-Hub-Signature-256")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        if !verify_webhook_signature(secret, &body, signature) {
+            warn!("Rejected git webhook with invalid signature");
+            return Error::Unauthorized("Invalid webhook signature".into()).error_response();
+        }
+    } else if let Err(e) = Operator::extract(&req).await {
+        return e.error_response();
+    }
+
+    let articles = articles_data.into_inner();
+    match web::block(move || articles.refresh_index()).await {
+        Ok(Ok(())) => HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            data: (),
+            message: Some("Index refreshed".into()),
+            error_code: None,
+        }),
+        Ok(Err(e)) => {
+            error!("Error refreshing index from webhook: {:?}", e);
+            Error::from(e).response("Failed to refresh index")
+        }
+        Err(e) => {
+            error!("Webhook refresh task failed: {:?}", e);
+            Error::from(e).response("Failed to refresh index")
+        }
+    }
+}
+
+/// Check a `sha256=<hex>` HMAC signature of `body`.
+#[cfg(feature = "git")]
+fn verify_webhook_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    use hmac::{Hmac, Mac};
+
+    let Some(Ok(expected)) = signature.strip_prefix("sha256=").map(hex::decode) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Opens a WebSocket on which every change to the articles is pushed as a JSON
+/// message: articles added, updated or removed, and index refreshes.
+#[cfg(feature = "websocket")]
+#[get("/ws")]
+async fn content_events(
+    req: HttpRequest,
+    body: web::Payload,
+    articles_data: Data<Articles>,
+) -> impl Responder {
+    let (response, session, messages) = match actix_ws::handle(&req, body) {
+        Ok(ws) => ws,
+        Err(e) => return Error::InvalidRequest(e.to_string()).error_response(),
+    };
+    rt::spawn(forward_events(session, messages, articles_data.subscribe()));
+    response
+}
+
+/// Send `events` to a WebSocket client until it disconnects.
+#[cfg(feature = "websocket")]
+async fn forward_events(
+    mut session: Session,
+    mut messages: MessageStream,
+    mut events: broadcast::Receiver<ContentEvent>,
+) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let Ok(json) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+                    if session.text(json).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("WebSocket client fell behind and missed {} events", missed);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = messages.recv() => match message {
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    let _ = session.close(None).await;
+}
+
 /// Configures the API v1 routes
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(list_articles)
-        .service(get_article_pages)
+    let scope = web::scope("/api/v1")
+        .configure(routes)
+        .service(get_version)
+        .service(get_request_stats)
+        .service(reset_request_stats)
+        .service(get_config)
+        .service(reload_config);
+    #[cfg(feature = "backup")]
+    let scope = scope.service(get_backup);
+    cfg.service(scope);
+}
+
+/// Builds the scope serving the API v1 routes for a named content section under
+/// `/api/v1/{section}`, backed by the section's own `Articles` instance and
+/// reaction counts.
+pub fn section_scope(name: &str, articles: Articles, reactions: Option<Data<Reactions>>) -> Scope {
+    let scope = web::scope(&format!("/api/v1/{}", name)).app_data(Data::new(articles));
+    match reactions {
+        Some(reactions) => scope.app_data(reactions),
+        None => scope,
+    }
+    .configure(routes)
+}
+
+/// Registers the API v1 routes relative to the scope they are mounted in.
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_articles).service(create_article);
+    #[cfg(feature = "uploads")]
+    cfg.service(upload_article);
+    cfg.service(get_article_pages)
+        .service(get_article_changes)
         .service(search_articles)
         .service(get_search_pages)
         .service(get_article)
         .service(refresh_index)
+        .service(get_index_report)
+        .service(validate_articles)
         .service(clear_cache)
         .service(refresh_article)
         .service(list_articles_by_tag)
         .service(get_tag_pages)
+        .service(list_articles_by_keyword)
+        .service(get_keyword_pages)
         .service(get_cache_stats)
-        .service(reset_cache_stats);
+        .service(reset_cache_stats)
+        .service(get_status)
+        .service(get_content_stats)
+        .service(export_articles)
+        .service(import_articles)
+        // Registered after the fixed `/articles/...` paths they would otherwise shadow
+        .service(get_article_summary)
+        .service(get_social_metadata)
+        .service(get_structured_data)
+        .service(add_reaction)
+        .service(list_comments)
+        .service(post_comment)
+        .service(list_held_comments)
+        .service(approve_comment)
+        .service(delete_comment)
+        .service(update_article)
+        .service(delete_article);
+    #[cfg(feature = "websocket")]
+    cfg.service(content_events);
+    #[cfg(feature = "git")]
+    cfg.service(git_webhook);
 }
\ No newline at end of file
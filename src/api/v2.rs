@@ -0,0 +1,341 @@
+use actix_web::{
+    get,
+    web::{self, Data, Path, Query},
+    HttpRequest, HttpResponse, Responder, ResponseError, Scope,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::error;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    api::{
+        article_json, default_page_size, error_response, link_with, requested_tags, resolve_article,
+        fields::{FieldSelection, Sparse},
+        public_article, set_pagination_headers, ApiResponse,
+    },
+    articles::{
+        Article, ArticleId, ArticleSummary, Articles, ContentFormat, ListingFilter,
+        ListingKey, ListingOrder, ListingPosition,
+    },
+    error::Error,
+    reactions::Reactions,
+    search::SearchFields,
+};
+
+/// The largest page a client may request.
+pub const MAX_PAGE_SIZE: usize = 100;
+/// How many related articles an article links to.
+const RELATED_LINKS: usize = 5;
+
+/// The path the API v2 routes of a scope are mounted at, used to build links.
+struct ApiBase(String);
+
+impl ApiBase {
+    fn articles(&self) -> String {
+        format!("{}/articles", self.0)
+    }
+
+    fn article(&self, id: ArticleId) -> String {
+        format!("{}/articles/{}", self.0, id)
+    }
+
+    fn tag(&self, tag: &str) -> String {
+        format!("{}/articles?tag={}", self.0, utf8_percent_encode(tag, NON_ALPHANUMERIC))
+    }
+}
+
+#[derive(Deserialize)]
+struct ListingParams {
+    cursor: Option<String>,
+    limit: Option<usize>,
+    /// Only list articles with this tag.
+    tag: Option<String>,
+    /// Only list articles with all of these comma-separated tags.
+    tags: Option<String>,
+    /// Only list articles with this keyword.
+    keyword: Option<String>,
+    /// Only list articles matching this search query.
+    q: Option<String>,
+    /// Comma-separated fields `q` is matched against (all if absent).
+    search_fields: Option<String>,
+    /// Only list articles dated on or after this day (YYYYMMDD).
+    from: Option<u32>,
+    /// Only list articles dated on or before this day (YYYYMMDD).
+    to: Option<u32>,
+    #[serde(default)]
+    sort: ListingOrder,
+    /// Comma-separated fields to include in each item (all if absent).
+    fields: Option<String>,
+}
+
+impl ListingParams {
+    fn filter(&self) -> Result<ListingFilter, Error> {
+        if self.from.zip(self.to).is_some_and(|(from, to)| from > to) {
+            return Err(Error::InvalidRequest("'from' is after 'to'".into()));
+        }
+        Ok(ListingFilter {
+            tags: requested_tags(self.tag.as_deref(), self.tags.as_deref()),
+            keyword: self.keyword.clone().filter(|keyword| !keyword.is_empty()),
+            query: self.q.clone().filter(|q| !q.trim().is_empty()),
+            search_fields: SearchFields::parse(self.search_fields.as_deref())?,
+            from: self.from,
+            to: self.to,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ArticleParams {
+    /// Comma-separated fields to include (all if absent).
+    fields: Option<String>,
+    /// The format of the content (as `markdown_to_html` says if absent).
+    format: Option<ContentFormat>,
+}
+
+/// One page of a cursor-paginated listing.
+#[derive(Serialize)]
+struct CursorPage<T> {
+    items: T,
+    /// How many items the whole listing holds.
+    total: usize,
+    /// Pass as `cursor` to get the next page; `None` on the last page.
+    next_cursor: Option<String>,
+    /// Pass as `cursor` to get the previous page; `None` on the first page.
+    prev_cursor: Option<String>,
+    links: PageLinks,
+}
+
+#[derive(Serialize)]
+struct PageLinks {
+    #[serde(rename = "self")]
+    self_link: String,
+    first: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev: Option<String>,
+}
+
+/// A resource with the links a client can follow from it.
+#[derive(Serialize)]
+struct Linked<T, L> {
+    #[serde(flatten)]
+    item: T,
+    links: L,
+}
+
+#[derive(Serialize)]
+struct SummaryLinks {
+    #[serde(rename = "self")]
+    self_link: String,
+}
+
+#[derive(Serialize)]
+struct ArticleLinks {
+    #[serde(rename = "self")]
+    self_link: String,
+    collection: String,
+    /// The listing of each of the article's tags, by tag.
+    tags: BTreeMap<String, String>,
+    /// Articles sharing the most tags with this one.
+    related: Vec<String>,
+}
+
+/// Lists article summaries, filtered by tags, search query and date range, in date
+/// order a page at a time. Each page carries opaque cursors to its neighbours,
+/// which stay valid across index refreshes, and links to them.
+#[get("/articles")]
+async fn list_articles(
+    req: HttpRequest,
+    articles_data: Data<Articles>,
+    base: Data<ApiBase>,
+    query: Query<ListingParams>,
+) -> impl Responder {
+    let limit = query
+        .limit
+        .unwrap_or_else(|| default_page_size(&articles_data).min(MAX_PAGE_SIZE));
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        let message = format!("'limit' must be between 1 and {}", MAX_PAGE_SIZE);
+        return Error::InvalidRequest(message).error_response();
+    }
+    let position = match query.cursor.as_deref().map(decode_cursor).transpose() {
+        Ok(position) => position,
+        Err(e) => return e.error_response(),
+    };
+    let fields = match FieldSelection::parse::<ArticleSummary>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+
+    let filter = match query.filter() {
+        Ok(filter) => filter,
+        Err(e) => return e.error_response(),
+    };
+
+    let page = articles_data.list_article_summaries_page(&filter, query.sort, position, limit);
+    let next_cursor = page.next.map(ListingPosition::After).map(encode_cursor);
+    let prev_cursor = page.prev.map(ListingPosition::Before).map(encode_cursor);
+    let items = page
+        .items
+        .iter()
+        .map(|summary| Linked {
+            item: Sparse(summary, &fields),
+            links: SummaryLinks {
+                self_link: base.article(summary.id),
+            },
+        })
+        .collect::<Vec<_>>();
+
+    let links = PageLinks {
+        self_link: page_link(&req, query.cursor.as_deref()),
+        first: page_link(&req, None),
+        next: next_cursor.as_deref().map(|cursor| page_link(&req, Some(cursor))),
+        prev: prev_cursor.as_deref().map(|cursor| page_link(&req, Some(cursor))),
+    };
+    let header_links = [("first", Some(&links.first)), ("next", links.next.as_ref()), ("prev", links.prev.as_ref())]
+        .into_iter()
+        .filter_map(|(rel, url)| Some((rel, url?.clone())))
+        .collect::<Vec<_>>();
+    let mut response = HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: CursorPage {
+            items,
+            total: page.total,
+            next_cursor,
+            prev_cursor,
+            links,
+        },
+        message: None,
+        error_code: None,
+    });
+    set_pagination_headers(response.headers_mut(), page.total, &header_links);
+    response
+}
+
+/// Retrieves a specific article by ID, with links to its tags and related articles,
+/// redirecting requests for its aliases
+#[get("/articles/{id}")]
+async fn get_article(
+    articles_data: Data<Articles>,
+    base: Data<ApiBase>,
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<ArticleParams>,
+) -> impl Responder {
+    let article_id = match resolve_article(&req, &articles_data, &path) {
+        Ok(article_id) => article_id,
+        Err(response) => return response,
+    };
+    let fields = match FieldSelection::parse::<Article>(query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return e.error_response(),
+    };
+    // Reading and rendering the article blocks, so it's kept off the async workers
+    let format = query.format;
+    let articles = articles_data.clone().into_inner();
+    let result = web::block(move || match format {
+        Some(format) => articles.get_article_as(article_id, format),
+        None => articles.get_article(article_id),
+    })
+    .await;
+    match result {
+        Ok(Ok((article, _))) => {
+            let links = ArticleLinks {
+                self_link: base.article(article.id),
+                collection: base.articles(),
+                tags: article.tags.iter().map(|tag| (tag.clone(), base.tag(tag))).collect(),
+                related: articles_data
+                    .related_article_ids(article.id, RELATED_LINKS)
+                    .into_iter()
+                    .map(|id| base.article(id))
+                    .collect(),
+            };
+            let response = ApiResponse {
+                success: true,
+                data: Linked {
+                    item: Sparse(&public_article(&articles_data, &req, &article), &fields),
+                    links,
+                },
+                message: None,
+                error_code: None,
+            };
+            article_json(&req, &response, &article.content).await
+        }
+        Ok(Err(e)) => error_response(e, &format!("Failed to load article {}", article_id)),
+        Err(e) => {
+            error!("Article load task failed: {:?}", e);
+            Error::from(e).response(&format!("Failed to load article {}", article_id))
+        }
+    }
+}
+
+/// The URL of the request with its cursor replaced by `cursor`, keeping every
+/// other query parameter as sent.
+fn page_link(req: &HttpRequest, cursor: Option<&str>) -> String {
+    link_with(req, "cursor", cursor)
+}
+
+/// Encode a listing position as an opaque, URL-safe cursor.
+pub fn encode_cursor(position: ListingPosition) -> String {
+    let (direction, key) = match position {
+        ListingPosition::After(key) => ("after", key),
+        ListingPosition::Before(key) => ("before", key),
+    };
+    URL_SAFE_NO_PAD.encode(format!("{}:{}:{}", direction, key.date, key.id))
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<ListingPosition, Error> {
+    let invalid = || Error::InvalidRequest("invalid cursor".into());
+    let decoded = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+    let mut parts = decoded.splitn(3, ':');
+    let (Some(direction), Some(date), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let key = ListingKey {
+        date: date.parse().map_err(|_| invalid())?,
+        id: id.parse().map_err(|_| invalid())?,
+    };
+    match direction {
+        "after" => Ok(ListingPosition::After(key)),
+        "before" => Ok(ListingPosition::Before(key)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Configures the API v2 routes, in a scope mounted below `path_prefix`, which
+/// the links of their responses start with.
+pub fn config(cfg: &mut web::ServiceConfig, path_prefix: &str) {
+    cfg.service(
+        web::scope("/api/v2")
+            .app_data(Data::new(ApiBase(format!("{}/api/v2", path_prefix))))
+            .configure(routes),
+    );
+}
+
+/// Builds the scope serving the API v2 routes for a named content section under
+/// `/api/v2/{section}`, backed by the section's own `Articles` instance and
+/// reaction counts, for a scope mounted below `path_prefix`.
+pub fn section_scope(
+    path_prefix: &str,
+    name: &str,
+    articles: Articles,
+    reactions: Option<Data<Reactions>>,
+) -> Scope {
+    let base = format!("/api/v2/{}", name);
+    let scope = web::scope(&base)
+        .app_data(Data::new(articles))
+        .app_data(Data::new(ApiBase(format!("{}{}", path_prefix, base))));
+    match reactions {
+        Some(reactions) => scope.app_data(reactions),
+        None => scope,
+    }
+    .configure(routes)
+}
+
+/// Registers the API v2 routes relative to the scope they are mounted in.
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_articles).service(get_article);
+}
@@ -1,16 +1,37 @@
-use crate::config;
+use crate::bundle::{self, BundleFile};
+use crate::cache_recorder::{CacheHit, CacheStats};
+use crate::comments::Comments;
+use crate::config::{self, SharedConfig};
+use crate::error::Error;
+use crate::events::{Changes, ContentEvent, EventBus};
 use crate::markdown::MarkdownConverter;
+use crate::reactions::ReactionCounts;
+use crate::search::{self, Matcher, SearchFields, SearchText};
+use crate::stats::ContentStats;
+#[cfg(feature = "sqlite")]
+use crate::sqlite_index::SqliteIndex;
+use crate::store::{
+    self, ArticleStore, FsStore, Problem, ScanEntry, ScanOutcome, ScanReport, ValidationReport,
+};
 use anyhow::{anyhow, bail, Result};
+use arc_swap::{ArcSwap, Guard};
+use bytes::Bytes;
 use dashmap::DashMap;
-use lazy_static::lazy_static;
-use log::{error, info};
+use log::{error, info, warn};
 use lru::LruCache;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
-    fs::{self, File},
-    io::Read,
-    path::PathBuf,
-    sync::{Arc, Mutex},
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, MutexGuard, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 // ===== DATA STRUCTURES =====
@@ -18,16 +39,28 @@ use std::{
 /// A unique identifier for articles.
 pub type ArticleId = i32;
 
+/// Site-specific metadata from an article's `[article.extra]` table, passed
+/// through untouched as a JSON object.
+pub type Extra = Arc<serde_json::Map<String, serde_json::Value>>;
+
 /// Represents a full article with content in HTML.
 #[derive(Clone)]
 pub struct Article {
     pub id: ArticleId,
     pub title: Arc<str>,
     pub description: Arc<str>,
-    pub content: Arc<str>,
+    pub content: Content,
     pub date: u32,
     pub tags: Arc<[String]>,
     pub keywords: Arc<[String]>,
+    /// The URL the article is published at, if known.
+    pub canonical_url: Option<Arc<str>>,
+    pub license: Option<Arc<str>>,
+    pub source: Option<Arc<str>>,
+    pub attribution: Option<Arc<str>>,
+    pub extra: Extra,
+    /// The reactions readers added, by kind; only filled in for API responses.
+    pub reactions: ReactionCounts,
 }
 
 impl Serialize for Article {
@@ -36,277 +69,499 @@ impl Serialize for Article {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("Article", 7)?;
+        let mut state = serializer.serialize_struct("Article", 13)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("title", self.title.as_ref())?;
         state.serialize_field("description", self.description.as_ref())?;
-        state.serialize_field("content", self.content.as_ref())?;
+        state.serialize_field("content", &self.content)?;
         state.serialize_field("date", &self.date)?;
         state.serialize_field("tags", self.tags.as_ref())?;
         state.serialize_field("keywords", self.keywords.as_ref())?;
+        state.serialize_field("canonical_url", &self.canonical_url.as_deref())?;
+        state.serialize_field("license", &self.license.as_deref())?;
+        state.serialize_field("source", &self.source.as_deref())?;
+        state.serialize_field("attribution", &self.attribution.as_deref())?;
+        state.serialize_field("extra", self.extra.as_ref())?;
+        state.serialize_field("reactions", &self.reactions)?;
         state.end()
     }
 }
 
-/// Represents a lighter view of an article (no body content).
-#[derive(Clone)]
-pub struct ArticleSummary {
-    pub id: ArticleId,
-    pub title: Arc<str>,
-    pub description: Arc<str>,
-    pub date: u32,
-    pub tags: Arc<[String]>,
-    pub keywords: Arc<[String]>,
-}
-
-impl Serialize for ArticleSummary {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("ArticleSummary", 6)?;
-        state.serialize_field("id", &self.id)?;
-        state.serialize_field("title", self.title.as_ref())?;
-        state.serialize_field("description", self.description.as_ref())?;
-        state.serialize_field("date", &self.date)?;
-        state.serialize_field("tags", self.tags.as_ref())?;
-        state.serialize_field("keywords", self.keywords.as_ref())?;
-        state.end()
+impl Article {
+    /// A copy of the article with empty content, to be serialized around the
+    /// content's shared JSON encoding (see [`Content::json`]).
+    pub fn without_content(&self) -> Article {
+        Article {
+            content: Content::default(),
+            ..self.clone()
+        }
     }
 }
 
-/// Internal structure describing metadata for an article, as loaded from `metainfo.toml`.
-pub struct Metainfo {
-    id: i32,
-    title: Arc<str>,
-    description: Arc<str>,
-    markdown_path: Arc<str>,
-    date: u32,
-    tags: Arc<[String]>,
-    keywords: Arc<[String]>,
+/// The content of an article, shared by every copy of the article. Its JSON
+/// encoding is made on first use and shared as well, so responses send one
+/// buffer again and again rather than encoding and copying the content anew.
+#[derive(Clone, Default)]
+pub struct Content {
+    text: Arc<str>,
+    json: Arc<OnceLock<Bytes>>,
 }
 
-/// Represents whether an article was just fetched from cache or freshly loaded.
-#[derive(PartialEq)]
-pub enum CachedStatus {
-    Cached,
-    NotCached,
+impl Content {
+    /// The content as a JSON string, quotes included.
+    pub fn json(&self) -> Bytes {
+        self.json
+            .get_or_init(|| {
+                serde_json::to_vec(&*self.text)
+                    .expect("a string always encodes")
+                    .into()
+            })
+            .clone()
+    }
 }
 
-// ===== ARTICLE CACHE =====
+impl Deref for Content {
+    type Target = str;
 
-/// Manages the LRU cache for recently accessed articles.
-struct ArticleCache {
-    cache: Arc<Mutex<LruCache<ArticleId, Article>>>,
+    fn deref(&self) -> &str {
+        &self.text
+    }
 }
 
-impl ArticleCache {
-    fn new(cache: Arc<Mutex<LruCache<ArticleId, Article>>>) -> Self {
-        Self { cache }
+impl From<String> for Content {
+    fn from(text: String) -> Self {
+        Content {
+            text: text.into(),
+            json: Arc::default(),
+        }
     }
+}
 
-    fn get(&self, article_id: ArticleId) -> Option<Article> {
-        let mut cache = self.cache.lock().unwrap();
-        cache.get(&article_id).cloned()
+impl From<&str> for Content {
+    fn from(text: &str) -> Self {
+        Content {
+            text: text.into(),
+            json: Arc::default(),
+        }
     }
+}
 
-    fn put(&self, article_id: ArticleId, article: Article) {
-        let mut cache = self.cache.lock().unwrap();
-        cache.put(article_id, article);
+impl Serialize for Content {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text)
     }
+}
 
-    fn clear(&self) {
-        let mut cache = self.cache.lock().unwrap();
-        cache.clear();
-    }
+/// What a requested article ID or alias refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArticleRef {
+    /// The ID of an existing article.
+    Id(ArticleId),
+    /// An alias of the article with this ID.
+    Moved(ArticleId),
+    /// The ID of a deleted article that left a tombstone.
+    Gone {
+        id: ArticleId,
+        successor: Option<ArticleId>,
+    },
 }
 
-// ===== ARTICLE INDEX =====
+/// A position in an article listing: the date and ID of an article. Listings
+/// resume strictly after it, so articles added or removed elsewhere in the index
+/// don't shift the pages that follow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ListingKey {
+    pub date: u32,
+    pub id: ArticleId,
+}
 
-/// Holds indices for quick lookups: by article ID, by tag, and a sorted list of IDs.
-pub struct ArticleIndex {
-    by_id: DashMap<ArticleId, Arc<Metainfo>>,
-    by_tag: DashMap<String, Vec<ArticleId>>,
-    sorted_ids: Arc<Mutex<Vec<ArticleId>>>,
+/// Where a page of a listing starts or ends.
+#[derive(Clone, Copy, Debug)]
+pub enum ListingPosition {
+    /// The page holds the articles listed after this one.
+    After(ListingKey),
+    /// The page holds the articles listed before this one.
+    Before(ListingKey),
 }
 
-impl ArticleIndex {
-    fn new() -> Self {
-        Self {
-            by_id: DashMap::new(),
-            by_tag: DashMap::new(),
-            sorted_ids: Arc::new(Mutex::new(Vec::new())),
-        }
-    }
+/// Which articles a listing includes. Every given criterion must match.
+#[derive(Default)]
+pub struct ListingFilter {
+    /// Only articles carrying all of these tags.
+    pub tags: Vec<String>,
+    /// Only articles carrying this keyword.
+    pub keyword: Option<String>,
+    /// Only articles matching this search query.
+    pub query: Option<String>,
+    /// The fields `query` is matched against.
+    pub search_fields: SearchFields,
+    /// Only articles dated on or after this day (YYYYMMDD).
+    pub from: Option<u32>,
+    /// Only articles dated on or before this day (YYYYMMDD).
+    pub to: Option<u32>,
+}
 
-    fn clear(&self) {
-        self.by_id.clear();
-        self.by_tag.clear();
-        let mut locked_ids = self.sorted_ids.lock().unwrap();
-        locked_ids.clear();
-    }
+/// The order of a listing, by date and then ID.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListingOrder {
+    #[default]
+    Newest,
+    Oldest,
+}
 
-    fn add_metainfo(&self, metainfo: Arc<Metainfo>) {
-        let article_id = metainfo.id;
-        self.by_id.insert(article_id, Arc::clone(&metainfo));
-        
-        // Build inverted index of tag -> article IDs
-        for tag in metainfo.tags.iter() {
-            self.by_tag.entry(tag.clone()).or_default().push(article_id);
+impl ListingOrder {
+    /// Whether `a` is listed before `b`.
+    fn precedes(self, a: &ListingKey, b: &ListingKey) -> bool {
+        match self {
+            ListingOrder::Newest => a > b,
+            ListingOrder::Oldest => a < b,
         }
     }
+}
 
-    fn sort_indices(&self) {
-        // Sort all IDs globally
-        {
-            let mut all_ids: Vec<_> = self.by_id.iter().map(|e| *e.key()).collect();
-            all_ids.sort_unstable();
-            let mut locked_ids = self.sorted_ids.lock().unwrap();
-            *locked_ids = all_ids;
-        }
-
-        // Sort article IDs within each tag
-        for mut entry in self.by_tag.iter_mut() {
-            entry.value_mut().sort_unstable();
-        }
-    }
+/// The order of the listings that don't choose one, set by `listing_order`: by ID,
+/// or by date and then ID.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultOrder {
+    #[default]
+    Id,
+    Newest,
+    Oldest,
+}
 
-    fn get_all_ids(&self) -> Vec<ArticleId> {
-        let locked_ids = self.sorted_ids.lock().unwrap();
-        locked_ids.clone()
-    }
+/// How a requested tag is matched against the tags of articles, set by
+/// `tag_matching`: exactly, ignoring case, or by [`tag_slug`], so that
+/// `Human Rights` and `human-rights` name the same tag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatching {
+    #[default]
+    Exact,
+    CaseInsensitive,
+    Slug,
+}
 
-    fn get_ids_by_tag(&self, tag: &str) -> Vec<ArticleId> {
-        self.by_tag.get(tag).map(|v| v.clone()).unwrap_or_default()
+/// The canonical form of `tag` for URLs: lowercase, with every run of characters
+/// other than letters and digits replaced by a single `-`, and none at either end.
+pub fn tag_slug(tag: &str) -> String {
+    let mut slug = String::with_capacity(tag.len());
+    for c in tag.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
     }
-
-    fn get_metainfo(&self, article_id: ArticleId) -> Option<Arc<Metainfo>> {
-        self.by_id.get(&article_id).map(|entry| Arc::clone(entry.value()))
+    if slug.ends_with('-') {
+        slug.pop();
     }
+    slug
 }
 
-// ===== FILE STORAGE =====
+/// A page of a listing, with the positions to continue from in either direction.
+pub struct ListingPage {
+    pub items: Vec<ArticleSummary>,
+    /// How many articles the whole listing holds.
+    pub total: usize,
+    /// The last article of the page, if more follow it.
+    pub next: Option<ListingKey>,
+    /// The first article of the page, if more precede it.
+    pub prev: Option<ListingKey>,
+}
 
-/// Handles loading articles from the filesystem
-struct ArticleStorage {
-    source_dir: PathBuf,
+/// Represents a lighter view of an article (no body content).
+#[derive(Clone)]
+pub struct ArticleSummary {
+    pub id: ArticleId,
+    pub title: Arc<str>,
+    pub description: Arc<str>,
+    pub date: u32,
+    pub tags: Arc<[String]>,
+    pub keywords: Arc<[String]>,
+    /// The URL of the article's cover image, for card-style listings.
+    pub cover: Option<Arc<str>>,
+    pub extra: Extra,
+    /// The number of approved comments, if comments are enabled.
+    pub comments: Option<usize>,
 }
 
-impl ArticleStorage {
-    fn new(source_dir: PathBuf) -> Self {
-        Self { source_dir }
+impl Serialize for ArticleSummary {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("ArticleSummary", 9)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("title", self.title.as_ref())?;
+        state.serialize_field("description", self.description.as_ref())?;
+        state.serialize_field("date", &self.date)?;
+        state.serialize_field("tags", self.tags.as_ref())?;
+        state.serialize_field("keywords", self.keywords.as_ref())?;
+        state.serialize_field("cover", &self.cover.as_deref())?;
+        state.serialize_field("extra", self.extra.as_ref())?;
+        state.serialize_field("comments", &self.comments)?;
+        state.end()
     }
+}
 
-    fn load_article(&self, metainfo: &Metainfo) -> Result<Article> {
-        let article_dir = self.source_dir.join(metainfo.id.to_string());
-        if !article_dir.exists() || !article_dir.is_dir() {
-            bail!("Article directory for ID {} not found", metainfo.id);
-        }
+/// A former ID or slug of an article, which keeps resolving to it after it moved.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Alias {
+    Id(ArticleId),
+    Slug(String),
+}
 
-        let md_file_path = article_dir.join(&*metainfo.markdown_path);
-        if !md_file_path.is_file() {
-            bail!(
-                "Markdown file '{}' is missing for article ID {}",
-                metainfo.markdown_path,
-                metainfo.id
-            );
+impl fmt::Display for Alias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alias::Id(id) => write!(f, "{}", id),
+            Alias::Slug(slug) => f.write_str(slug),
         }
-
-        let markdown_content = Self::read_file_as_string(&md_file_path)?;
-        // Convert Markdown to HTML if markdown_to_html is enabled in the config
-        let content = if config::CONFIG.mainconfig.markdown_to_html {
-            markdown_content.to_html_with_config(&config::CONFIG).into()
-        } else {
-            markdown_content.into()
-        };
-
-        Ok(Article {
-            id: metainfo.id,
-            title: Arc::clone(&metainfo.title),
-            description: Arc::clone(&metainfo.description),
-            content,
-            date: metainfo.date,
-            tags: Arc::clone(&metainfo.tags),
-            keywords: Arc::clone(&metainfo.keywords),
-        })
     }
+}
 
-    fn scan_articles(&self, index: &ArticleIndex) -> Result<()> {
-        for entry in fs::read_dir(&self.source_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-            let dir_name = match path.file_name().and_then(|s| s.to_str()) {
-                Some(name) => name,
-                None => continue,
-            };
-            let article_id: ArticleId = match dir_name.parse() {
-                Ok(id) => id,
-                Err(_) => continue,
-            };
-            let metainfo_path = path.join("metainfo.toml");
-            if !metainfo_path.is_file() {
-                continue;
-            }
+/// Who may read an article.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    #[default]
+    Public,
+    /// Left out of every listing, feed and search, and only served to readers
+    /// presenting the article's `token` or an API key with the `read` scope.
+    Private,
+}
 
-            // Parse the TOML file into a Metainfo
-            if let Ok(metainfo) = Self::parse_metainfo(&metainfo_path) {
-                if metainfo.id != article_id {
-                    continue; // skip if mismatch
-                }
-                let meta_arc = Arc::new(metainfo);
-                index.add_metainfo(meta_arc);
-            }
-        }
-        Ok(())
-    }
+/// Structure describing metadata for an article, as loaded from `metainfo.toml`.
+#[derive(PartialEq)]
+pub struct Metainfo {
+    pub id: i32,
+    /// The location holding this article's `metainfo.toml` and content; for the
+    /// filesystem store this is the article directory.
+    pub dir: PathBuf,
+    pub title: Arc<str>,
+    pub description: Arc<str>,
+    pub markdown_path: Arc<str>,
+    pub date: u32,
+    pub tags: Arc<[String]>,
+    pub keywords: Arc<[String]>,
+    /// The article's cover image: a URL, a path on the site, or the name of a file
+    /// in the article directory.
+    pub cover: Option<Arc<str>>,
+    /// Who wrote the article, if not the site's configured author.
+    pub author: Option<Arc<str>>,
+    /// Where the article was first published, if not at its page on this site.
+    pub canonical_url: Option<Arc<str>>,
+    /// Former IDs and slugs that redirect to the article.
+    pub aliases: Arc<[Alias]>,
+    /// The terms the article is published under, as a license name or URL.
+    pub license: Option<Arc<str>>,
+    /// The URL of the work the article republishes or is based on.
+    pub source: Option<Arc<str>>,
+    /// The credit the terms of its `license` or `source` require.
+    pub attribution: Option<Arc<str>>,
+    /// Site-specific metadata from `[article.extra]`.
+    pub extra: Extra,
+    /// Whether the article cache never evicts the article.
+    pub pinned: bool,
+    /// Who may read the article: private when a `token` is set, unless it says otherwise.
+    pub access: Access,
+    /// The token letting reviewers read the article while it's private.
+    pub token: Option<Arc<str>>,
+    /// Whether the metadata came from front matter at the top of the Markdown file
+    /// itself, which must then be stripped before rendering.
+    pub front_matter: bool,
+    /// The searched fields, folded for matching queries against them.
+    pub search_text: Arc<SearchText>,
+}
 
-    /// Parse a `metainfo.toml` file from disk.
-    fn parse_metainfo(path: &PathBuf) -> Result<Metainfo> {
-        let mut file = File::open(path)?;
-        let mut toml_content = String::new();
-        file.read_to_string(&mut toml_content)?;
-        let parsed: toml::Value = toml::from_str(&toml_content)?;
+impl Metainfo {
+    /// Parse the contents of a `metainfo.toml` file for the article located at `dir`.
+    pub fn from_toml(toml_content: &str, dir: PathBuf) -> Result<Self> {
+        let parsed: toml::Value = toml::from_str(toml_content)?;
 
         let article_section = parsed
             .get("article")
-            .ok_or_else(|| anyhow!("No [article] section found in {:?}", path))?;
+            .ok_or_else(|| anyhow!("No [article] section found in {:?}", dir))?;
+
+        Self::from_article_section(article_section, dir)
+    }
+
+    /// Parse the TOML front matter of the flat Markdown file `file_name` in `dir`.
+    /// The fields are the same as in `[article]`, at the top level, except that
+    /// `markdown_path` is always the file itself.
+    pub fn from_front_matter(front_matter: &str, dir: PathBuf, file_name: &str) -> Result<Self> {
+        let mut parsed: toml::Value = toml::from_str(front_matter)?;
+        let table = parsed
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("Invalid front matter in {:?}", dir.join(file_name)))?;
+        table.insert("markdown_path".into(), toml::Value::String(file_name.into()));
+
+        let mut metainfo = Self::from_article_section(&parsed, dir)?;
+        metainfo.front_matter = true;
+        Ok(metainfo)
+    }
 
+    fn from_article_section(article_section: &toml::Value, dir: PathBuf) -> Result<Self> {
         let tags = Self::parse_string_array(article_section, "tags")?;
         let keywords = Self::parse_string_array(article_section, "keywords")?;
+        let title = article_section
+            .get("title")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Missing 'title' in {:?}", dir))?;
+        let description = article_section
+            .get("description")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Missing 'description' in {:?}", dir))?;
+        let search_text = Arc::new(SearchText::new(title, description, &tags, &keywords));
+        let token = article_section.get("token").and_then(|v| v.as_str()).map(Arc::from);
 
         Ok(Metainfo {
             id: article_section
                 .get("id")
                 .and_then(|v| v.as_integer())
-                .ok_or_else(|| anyhow!("Missing 'id' in {:?}", path))? as i32,
-            title: article_section
-                .get("title")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| anyhow!("Missing 'title' in {:?}", path))?
-                .into(),
-            description: article_section
-                .get("description")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| anyhow!("Missing 'description' in {:?}", path))?
-                .into(),
+                .ok_or_else(|| anyhow!("Missing 'id' in {:?}", dir))? as i32,
+            title: title.into(),
+            description: description.into(),
             markdown_path: article_section
                 .get("markdown_path")
                 .and_then(|v| v.as_str())
-                .ok_or_else(|| anyhow!("Missing 'markdown_path' in {:?}", path))?
+                .ok_or_else(|| anyhow!("Missing 'markdown_path' in {:?}", dir))?
                 .into(),
             date: article_section
                 .get("date")
                 .and_then(|v| v.as_integer())
-                .ok_or_else(|| anyhow!("Missing 'date' in {:?}", path))? as u32,
+                .ok_or_else(|| anyhow!("Missing 'date' in {:?}", dir))? as u32,
             tags: tags.into(),
             keywords: keywords.into(),
+            cover: article_section
+                .get("cover")
+                .and_then(|v| v.as_str())
+                .map(Arc::from),
+            author: article_section
+                .get("author")
+                .and_then(|v| v.as_str())
+                .map(Arc::from),
+            canonical_url: article_section
+                .get("canonical_url")
+                .and_then(|v| v.as_str())
+                .map(Arc::from),
+            aliases: match article_section.get("aliases") {
+                Some(aliases) => aliases
+                    .clone()
+                    .try_into::<Vec<Alias>>()
+                    .map_err(|_| anyhow!("'aliases' must be an array of IDs and slugs in {:?}", dir))?
+                    .into(),
+                None => Arc::from([]),
+            },
+            license: article_section
+                .get("license")
+                .and_then(|v| v.as_str())
+                .map(Arc::from),
+            source: article_section
+                .get("source")
+                .and_then(|v| v.as_str())
+                .map(Arc::from),
+            attribution: article_section
+                .get("attribution")
+                .and_then(|v| v.as_str())
+                .map(Arc::from),
+            extra: match article_section.get("extra") {
+                Some(toml::Value::Table(extra)) => Arc::new(
+                    extra.iter().map(|(key, value)| (key.clone(), toml_to_json(value))).collect(),
+                ),
+                Some(_) => bail!("'extra' must be a table in {:?}", dir),
+                None => Extra::default(),
+            },
+            pinned: match article_section.get("pinned") {
+                Some(pinned) => pinned
+                    .as_bool()
+                    .ok_or_else(|| anyhow!("'pinned' must be a boolean in {:?}", dir))?,
+                None => false,
+            },
+            access: match article_section.get("access") {
+                Some(access) => access
+                    .clone()
+                    .try_into()
+                    .map_err(|_| anyhow!("'access' must be 'public' or 'private' in {:?}", dir))?,
+                None if token.is_some() => Access::Private,
+                None => Access::Public,
+            },
+            token,
+            front_matter: false,
+            search_text,
+            dir,
+        })
+    }
+
+    /// Whether the article is left out of listings and only served to reviewers.
+    pub fn is_private(&self) -> bool {
+        self.access == Access::Private
+    }
+
+    /// The URL the article is published at: its `canonical_url` if set (a path
+    /// being resolved against `base_url`), or else its page on the site.
+    pub fn canonical_url(&self, config: &config::Main) -> Option<String> {
+        match &self.canonical_url {
+            Some(url) => Some(config.site_url(url)),
+            None => config.article_url(self.id),
+        }
+    }
+
+    /// The cover image's file in the article directory, unless the cover is a URL
+    /// or a path on the site.
+    pub fn cover_asset(&self) -> Option<&str> {
+        self.cover
+            .as_deref()
+            .filter(|cover| !cover.starts_with('/') && !cover.contains("://"))
+    }
+
+    /// The URL of the article's cover image, if it has one (a path on the site
+    /// when `base_url` isn't set).
+    pub fn cover_url(&self, config: &config::Main) -> Option<String> {
+        let cover = self.cover.as_deref()?;
+        Some(match self.cover_asset() {
+            Some(asset) => config.asset_url(self.id, asset),
+            None => config.site_url(cover),
         })
     }
 
+    /// The ways the metadata breaks the `[validation]` rules, if any.
+    pub fn violations(&self, rules: &config::Validation) -> Vec<String> {
+        let mut violations = Vec::new();
+        let title_length = self.title.chars().count();
+        if let Some(max) = rules.max_title_length
+            && title_length > max
+        {
+            violations.push(format!("title is {} characters long, over {}", title_length, max));
+        }
+        if rules.require_description && self.description.trim().is_empty() {
+            violations.push("description is empty".to_string());
+        }
+        if let Some(max) = rules.max_tags
+            && self.tags.len() > max
+        {
+            violations.push(format!("{} tags, over {}", self.tags.len(), max));
+        }
+        if let Some(chars) = &rules.tag_chars {
+            let allowed = |c: char| c.is_alphanumeric() || chars.contains(c);
+            for tag in self.tags.iter().filter(|tag| !tag.chars().all(allowed)) {
+                violations.push(format!(
+                    "tag '{}' uses characters other than letters, digits and {:?}",
+                    tag, chars
+                ));
+            }
+        }
+        let too_early = rules.min_date.is_some_and(|min| self.date < min);
+        if too_early || rules.max_date.is_some_and(|max| self.date > max) {
+            violations.push(format!("date {} is outside the allowed range", self.date));
+        }
+        violations
+    }
+
     /// Parse an array of strings from a TOML `Value`.
     fn parse_string_array(section: &toml::Value, key: &str) -> Result<Vec<String>> {
         let arr = section
@@ -322,141 +577,2128 @@ impl ArticleStorage {
             })
             .collect()
     }
+}
 
-    /// Read file contents as a UTF-8 string.
-    fn read_file_as_string(path: &PathBuf) -> Result<String> {
-        let mut file = File::open(path)?;
-        let mut content = String::new();
-        file.read_to_string(&mut content)?;
-        Ok(content)
+/// Convert a TOML value to JSON. Datetimes become strings in their TOML form.
+fn toml_to_json(value: &toml::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        toml::Value::String(s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::from(*i),
+        toml::Value::Float(f) => serde_json::Number::from_f64(*f).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(*b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => Value::Array(array.iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table.iter().map(|(key, value)| (key.clone(), toml_to_json(value))).collect(),
+        ),
     }
 }
 
-// ===== SAMPLE ARTICLE =====
+/// What remains of a deleted article, as loaded from a `tombstone.toml` in its
+/// directory: requests for its ID are answered with `410 Gone` instead of `404`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Tombstone {
+    pub id: ArticleId,
+    /// The article replacing the deleted one, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub successor: Option<ArticleId>,
+    /// The location holding the `tombstone.toml`.
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
 
-lazy_static! {
-    /// A sample article, to be optionally injected based on user config.
-    static ref SAMPLE_ARTICLE: Article = Article {
-        id: 0,
-        title: "Universal Declaration of Human Rights".into(),
-        description: "The Universal Declaration of Human Rights is a seminal document ...".into(),
-        content: include_str!("udhr.md")
-            .to_html_with_config(&config::CONFIG)
-            .into(),
-        date: 19481210,
-        tags: vec!["Politics".to_string(), "History".to_string()].into(),
-        keywords: vec!["human rights".to_string(), "united nations".to_string()].into(),
-    };
+/// The layout of a `tombstone.toml` file.
+#[derive(Deserialize, Serialize)]
+struct TombstoneFile {
+    tombstone: Tombstone,
 }
 
-// ===== PAGINATOR =====
+impl Tombstone {
+    /// Parse the contents of a `tombstone.toml` file located at `dir`.
+    pub fn from_toml(toml_content: &str, dir: PathBuf) -> Result<Self> {
+        let file: TombstoneFile = toml::from_str(toml_content)?;
+        Ok(Tombstone { dir, ..file.tombstone })
+    }
 
-/// Helper for article pagination operations
-struct Paginator;
+    /// Serialize the tombstone as the contents of a `tombstone.toml` file.
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(&TombstoneFile { tombstone: self.clone() })?)
+    }
+}
 
-impl Paginator {
-    /// Generic pagination helper: given a slice of items, returns the sub-slice for `page_number` or an error if out of range.
-    fn paginate<T>(
-        data: &[T],
-        max_per_page: usize,
-        page_number: usize,
-    ) -> Result<Option<&[T]>> {
-        if max_per_page == 0 {
-            // If page size is zero, return nothing
-            return Ok(None);
-        }
-        let total_items = data.len();
-        let total_pages = Self::compute_total_pages(total_items, max_per_page);
+/// A new article submitted through the API: its metadata and Markdown source.
+/// Exports hold articles in the same form.
+#[derive(Deserialize, Serialize)]
+pub struct ArticleDraft {
+    /// Assigned automatically (one past the highest existing ID) when omitted.
+    #[serde(default)]
+    pub id: Option<ArticleId>,
+    pub title: String,
+    pub description: String,
+    pub date: u32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub cover: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<Alias>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub attribution: Option<String>,
+    /// Site-specific metadata, written to `[article.extra]`.
+    #[serde(default)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// Whether the article cache never evicts the article.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Who may read the article; private by default when a `token` is given.
+    #[serde(default)]
+    pub access: Option<Access>,
+    /// The token letting reviewers read the article while it's private.
+    #[serde(default)]
+    pub token: Option<String>,
+    pub markdown: String,
+}
+
+/// The Markdown file name used for articles created from a draft.
+const DRAFT_MARKDOWN_FILE: &str = "content.md";
 
-        // If there's at least one page and page_number is out of range
-        if total_pages != 0 && page_number >= total_pages {
-            bail!("Page number out of range");
+impl ArticleDraft {
+    /// Render the draft as a `metainfo.toml` for the article `id`, or as front
+    /// matter when `markdown_path` is `None`.
+    pub fn to_toml(&self, id: ArticleId, markdown_path: Option<&str>) -> Result<String> {
+        #[derive(Serialize)]
+        struct MetainfoFile<'a> {
+            article: ArticleSection<'a>,
+        }
+        #[derive(Serialize)]
+        struct ArticleSection<'a> {
+            id: ArticleId,
+            title: &'a str,
+            description: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            markdown_path: Option<&'a str>,
+            date: u32,
+            tags: &'a [String],
+            keywords: &'a [String],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            cover: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            author: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            canonical_url: Option<&'a str>,
+            #[serde(skip_serializing_if = "<[Alias]>::is_empty")]
+            aliases: &'a [Alias],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            license: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            source: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            attribution: Option<&'a str>,
+            #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+            extra: &'a serde_json::Map<String, serde_json::Value>,
+            #[serde(skip_serializing_if = "std::ops::Not::not")]
+            pinned: bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            access: Option<Access>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            token: Option<&'a str>,
         }
 
-        let start = page_number * max_per_page;
-        let end = (start + max_per_page).min(total_items);
-        Ok(Some(&data[start..end]))
+        let article = ArticleSection {
+            id,
+            title: &self.title,
+            description: &self.description,
+            markdown_path,
+            date: self.date,
+            tags: &self.tags,
+            keywords: &self.keywords,
+            cover: self.cover.as_deref(),
+            author: self.author.as_deref(),
+            canonical_url: self.canonical_url.as_deref(),
+            aliases: &self.aliases,
+            license: self.license.as_deref(),
+            source: self.source.as_deref(),
+            attribution: self.attribution.as_deref(),
+            extra: &self.extra,
+            pinned: self.pinned,
+            access: self.access,
+            token: self.token.as_deref(),
+        };
+        Ok(match markdown_path {
+            Some(_) => toml::to_string(&MetainfoFile { article })?,
+            None => toml::to_string(&article)?,
+        })
     }
 
-    /// Compute how many pages are needed to hold `total_items` items with the given `max_per_page`.
-    fn compute_total_pages(total_items: usize, max_per_page: usize) -> usize {
-        if total_items == 0 || max_per_page == 0 {
-            0
-        } else {
-            (total_items + max_per_page - 1) / max_per_page
+    /// The files of a new article directory holding this draft.
+    fn to_bundle(&self, id: ArticleId) -> Result<Vec<BundleFile>> {
+        Ok(vec![
+            BundleFile {
+                path: "metainfo.toml".into(),
+                content: self.to_toml(id, Some(DRAFT_MARKDOWN_FILE))?.into_bytes(),
+            },
+            BundleFile {
+                path: DRAFT_MARKDOWN_FILE.into(),
+                content: self.markdown.clone().into_bytes(),
+            },
+        ])
+    }
+
+    fn validate(&self, max_article_bytes: usize) -> Result<(), Error> {
+        if self.id.is_some_and(|id| id <= 0) {
+            return Err(Error::InvalidMetadata("'id' must be positive".into()));
+        }
+        if self.title.trim().is_empty() {
+            return Err(Error::InvalidMetadata("'title' must not be empty".into()));
+        }
+        if self.markdown.len() > max_article_bytes {
+            return Err(Error::too_large(max_article_bytes));
         }
+        Ok(())
     }
 }
 
-// ===== MAIN ARTICLES FACADE =====
+/// What an import does with an article whose ID is taken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Leave the existing article alone.
+    #[default]
+    Skip,
+    /// Replace the existing article.
+    Overwrite,
+    /// Import the article under the next free ID.
+    Renumber,
+}
 
-/// Manages a set of articles from a source directory, plus an LRU cache for recently accessed articles.
-pub struct Articles {
-    storage: ArticleStorage,
-    cache: ArticleCache,
-    index: Arc<ArticleIndex>,
+/// An article to import: a draft as exported, or the files of an article directory.
+pub enum ImportItem {
+    Draft(Box<ArticleDraft>),
+    Bundle(Vec<BundleFile>),
 }
 
-impl Clone for Articles {
-    fn clone(&self) -> Self {
-        Articles {
-            storage: ArticleStorage::new(self.storage.source_dir.clone()),
-            cache: ArticleCache::new(Arc::clone(&self.cache.cache)),
-            index: Arc::clone(&self.index),
-        }
-    }
+/// The outcome of an import, article by article.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub created: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub entries: Vec<ImportEntry>,
 }
 
-impl Articles {
-    /// Create a new `Articles` manager, initializing indices from the filesystem.
-    pub fn new(source_dir: PathBuf, cache: Arc<Mutex<LruCache<ArticleId, Article>>>) -> Self {
-        info!("Initializing Articles");
-        let storage = ArticleStorage::new(source_dir);
-        let cache = ArticleCache::new(cache);
-        let index = Arc::new(ArticleIndex::new());
-        
-        let articles = Articles {
-            storage,
-            cache,
-            index,
-        };
-        if let Err(e) = articles.load_index() {
-            error!("Failed to load index: {}", e);
+#[derive(Debug, Serialize)]
+pub struct ImportEntry {
+    /// The ID the article was imported as, or the one it asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<ArticleId>,
+    /// The ID the article asked for, when it was renumbered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renumbered_from: Option<ArticleId>,
+    /// `"created"`, `"overwritten"`, `"skipped"` or `"failed"`.
+    pub outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ImportReport {
+    fn push(&mut self, entry: ImportEntry) {
+        match entry.outcome {
+            "created" => self.created += 1,
+            "overwritten" => self.overwritten += 1,
+            "skipped" => self.skipped += 1,
+            _ => self.failed += 1,
         }
-        articles
+        self.entries.push(entry);
     }
+}
 
-    /// (Re)loads the entire article index from the filesystem.
-    pub fn load_index(&self) -> Result<()> {
-        self.index.clear();
+/// The version of an article's source an article was read from, to tell later
+/// whether the source changed: its modification time, or for stores that don't
+/// track one, such as S3, its fingerprint (e.g. an ETag).
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SourceVersion {
+    Modified(SystemTime),
+    Fingerprint(Arc<str>),
+}
 
-        // Optionally insert the sample article
-        if config::CONFIG.mainconfig.sample_article {
-            let sample_metainfo = Metainfo {
-                id: SAMPLE_ARTICLE.id,
-                title: SAMPLE_ARTICLE.title.clone(),
-                description: SAMPLE_ARTICLE.description.clone(),
-                markdown_path: "udhr.md".into(),
-                date: SAMPLE_ARTICLE.date,
-                tags: SAMPLE_ARTICLE.tags.clone(),
-                keywords: SAMPLE_ARTICLE.keywords.clone(),
-            };
-            let sample_arc = Arc::new(sample_metainfo);
-            self.index.add_metainfo(sample_arc);
-        }
+/// An article held in the LRU cache with its content in each format requested so
+/// far, along with the time it was cached.
+pub struct CacheEntry {
+    /// The article's metadata; its `content` is left empty.
+    article: Article,
+    markdown: Option<CachedContent>,
+    html: Option<CachedContent>,
+    cached_at: Instant,
+    /// The version of the article's source, as the store told before it was read;
+    /// see [`Articles::unchanged`].
+    version: Option<SourceVersion>,
+    /// Whether eviction passes the entry over; see [`Articles::is_pinned`].
+    pinned: bool,
+}
 
-        // Walk the source directory for real articles
-        self.storage.scan_articles(&self.index)?;
+impl CacheEntry {
+    fn content(&self, format: ContentFormat) -> Option<&CachedContent> {
+        match format {
+            ContentFormat::Markdown => self.markdown.as_ref(),
+            ContentFormat::Html => self.html.as_ref(),
+        }
+    }
 
+    /// The number of bytes the entry accounts for in the cache: the size of each
+    /// content held, as stored.
+    fn size(&self) -> usize {
+        self.markdown.as_ref().map_or(0, CachedContent::size)
+            + self.html.as_ref().map_or(0, CachedContent::size)
+    }
+}
+
+/// Article content as the cache holds it: as is, or LZ4-compressed with
+/// `compress_cache` on, to fit more articles into the same memory.
+#[derive(Clone)]
+enum CachedContent {
+    Plain(Content),
+    Compressed(Arc<[u8]>),
+}
+
+impl CachedContent {
+    fn new(content: Content, compress: bool) -> Self {
+        if compress {
+            CachedContent::Compressed(lz4_flex::compress_prepend_size(content.as_bytes()).into())
+        } else {
+            CachedContent::Plain(content)
+        }
+    }
+
+    /// The content, decompressed if need be.
+    fn content(&self) -> Content {
+        match self {
+            CachedContent::Plain(content) => content.clone(),
+            CachedContent::Compressed(bytes) => {
+                let text = lz4_flex::decompress_size_prepended(bytes)
+                    .expect("cached content decompresses");
+                String::from_utf8(text).expect("cached content is UTF-8").into()
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            CachedContent::Plain(content) => content.len(),
+            CachedContent::Compressed(bytes) => bytes.len(),
+        }
+    }
+}
+
+/// The format of an article's content: its Markdown source as written, or rendered
+/// to HTML.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    Markdown,
+    Html,
+}
+
+impl ContentFormat {
+    /// The format articles are served in unless a request asks for another, as
+    /// `markdown_to_html` says.
+    pub fn configured(config: &config::Main) -> Self {
+        if config.markdown_to_html {
+            ContentFormat::Html
+        } else {
+            ContentFormat::Markdown
+        }
+    }
+}
+
+/// Represents whether an article was just fetched from cache or freshly loaded.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CachedStatus {
+    Cached,
+    NotCached,
+}
+
+// ===== ARTICLE CACHE =====
+
+/// How full the article cache is.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheOccupancy {
+    pub entries: usize,
+    /// The most entries it holds (`max_cached_articles`).
+    pub capacity: usize,
+    /// The content bytes held.
+    pub bytes: usize,
+    /// The most content bytes it holds (`max_cache_bytes`), if limited.
+    pub max_bytes: Option<usize>,
+}
+
+/// The number of shards a [`CacheShards`] is split into.
+const CACHE_SHARDS: usize = 16;
+
+/// The storage of the article cache: LRU lists split into shards that are locked
+/// apart, so requests for different articles rarely wait on each other. Cloning it
+/// shares it, so several [`Articles`] instances can use one cache.
+///
+/// The limits on entries and bytes hold across all shards; when one is exceeded,
+/// the least recently used entries of the shards are evicted, starting with the
+/// shard that grew.
+#[derive(Clone)]
+pub struct CacheShards {
+    shards: Arc<[Mutex<LruCache<ArticleId, CacheEntry>>]>,
+    /// The most entries held across all shards.
+    capacity: Arc<AtomicUsize>,
+    /// Entries currently held; only modified while the shard changed is locked.
+    entries: Arc<AtomicUsize>,
+    /// Total content bytes currently held; only modified while the shard changed
+    /// is locked.
+    total_bytes: Arc<AtomicUsize>,
+}
+
+impl CacheShards {
+    /// An empty cache holding up to `capacity` articles.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            shards: (0..CACHE_SHARDS).map(|_| Mutex::new(LruCache::unbounded())).collect(),
+            capacity: Arc::new(AtomicUsize::new(capacity)),
+            entries: Arc::new(AtomicUsize::new(0)),
+            total_bytes: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn shard_of(article_id: ArticleId) -> usize {
+        article_id.rem_euclid(CACHE_SHARDS as ArticleId) as usize
+    }
+
+    fn lock(&self, shard: usize) -> MutexGuard<'_, LruCache<ArticleId, CacheEntry>> {
+        self.shards[shard].lock().unwrap()
+    }
+
+    /// Account for `entry` leaving the cache.
+    fn removed(&self, entry: &CacheEntry) {
+        self.entries.fetch_sub(1, Ordering::Relaxed);
+        self.total_bytes.fetch_sub(entry.size(), Ordering::Relaxed);
+    }
+
+    /// Empty every shard.
+    fn clear(&self) {
+        let mut shards = (0..CACHE_SHARDS).map(|shard| self.lock(shard)).collect::<Vec<_>>();
+        for shard in shards.iter_mut() {
+            shard.clear();
+        }
+        self.entries.store(0, Ordering::Relaxed);
+        self.total_bytes.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Manages the LRU cache for recently accessed articles.
+///
+/// Besides the number of entries (`max_cached_articles`), the cache can be bounded
+/// by the total byte size of the cached article contents (`max_cache_bytes`). The
+/// byte limit and TTL are read from the configuration on every use, so a reload
+/// applies them. Pinned entries count towards both limits but are never evicted to
+/// meet them. Every lookup is counted in the hit and miss statistics.
+#[derive(Clone)]
+struct ArticleCache {
+    cache: CacheShards,
+    stats: Arc<CacheHit>,
+    config: SharedConfig,
+}
+
+impl ArticleCache {
+    fn new(cache: CacheShards, config: SharedConfig) -> Self {
+        Self {
+            cache,
+            stats: Arc::new(CacheHit::new(Arc::clone(&config))),
+            config,
+        }
+    }
+
+    /// Count a lookup that was answered as `status` says.
+    fn record(&self, status: CachedStatus) {
+        match status {
+            CachedStatus::Cached => self.stats.hit(),
+            CachedStatus::NotCached => self.stats.miss(),
+        }
+    }
+
+    fn max_bytes(&self) -> Option<usize> {
+        self.config.load().mainconfig.max_cache_bytes
+    }
+
+    fn ttl(&self) -> Option<Duration> {
+        self.config.load().mainconfig.cache_ttl_seconds.map(Duration::from_secs)
+    }
+
+    fn compress(&self) -> bool {
+        self.config.load().mainconfig.compress_cache
+    }
+
+    /// The cached article with its content in `format`, if that format was cached,
+    /// and the version of its source.
+    fn get(&self, article_id: ArticleId, format: ContentFormat) -> Option<(Article, Option<SourceVersion>)> {
+        let (article, content, version) = {
+            let mut shard = self.cache.lock(CacheShards::shard_of(article_id));
+            let entry = self.live_entry(&mut shard, article_id)?;
+            (entry.article.clone(), entry.content(format)?.clone(), entry.version.clone())
+        };
+        // Decompressing happens outside the lock, so it doesn't hold up the shard
+        let article = Article {
+            content: content.content(),
+            ..article
+        };
+        Some((article, version))
+    }
+
+    /// The entry for `article_id` in its locked `shard`, unless it's missing or
+    /// expired. Entries older than the TTL are dropped so the next access reloads
+    /// them from disk.
+    fn live_entry<'a>(
+        &self,
+        shard: &'a mut LruCache<ArticleId, CacheEntry>,
+        article_id: ArticleId,
+    ) -> Option<&'a CacheEntry> {
+        let expired = match self.ttl() {
+            Some(ttl) => shard.peek(&article_id)?.cached_at.elapsed() > ttl,
+            None => false,
+        };
+        if expired {
+            if let Some(expired) = shard.pop(&article_id) {
+                self.cache.removed(&expired);
+            }
+            return None;
+        }
+        shard.get(&article_id)
+    }
+
+    /// Cache `article`, whose content is in `format` and was read from `version`
+    /// of its source, alongside the other format if that one is cached already from
+    /// the same version. A `pinned` entry is kept until it expires or is removed.
+    fn put(
+        &self,
+        article_id: ArticleId,
+        article: Article,
+        format: ContentFormat,
+        version: Option<SourceVersion>,
+        pinned: bool,
+    ) {
+        let shard_index = CacheShards::shard_of(article_id);
+        let mut shard = self.cache.lock(shard_index);
+        let mut entry = match self.live_entry(&mut shard, article_id) {
+            Some(existing) if existing.version == version => CacheEntry {
+                article: existing.article.clone(),
+                markdown: existing.markdown.clone(),
+                html: existing.html.clone(),
+                cached_at: existing.cached_at,
+                version,
+                pinned,
+            },
+            _ => CacheEntry {
+                article: article.without_content(),
+                markdown: None,
+                html: None,
+                cached_at: Instant::now(),
+                version,
+                pinned,
+            },
+        };
+        let content = CachedContent::new(article.content, self.compress());
+        match format {
+            ContentFormat::Markdown => entry.markdown = Some(content),
+            ContentFormat::Html => entry.html = Some(content),
+        }
+        let size = entry.size();
+
+        // An article larger than the whole byte budget is never cached, nor is
+        // anything when no entries are allowed
+        let max_bytes = self.max_bytes();
+        if max_bytes.is_some_and(|max_bytes| size > max_bytes)
+            || self.cache.capacity.load(Ordering::Relaxed) == 0
+        {
+            if let Some(old) = shard.pop(&article_id) {
+                self.cache.removed(&old);
+            }
+            return;
+        }
+
+        // The shards are unbounded, so `put` only ever returns the replaced entry
+        match shard.put(article_id, entry) {
+            Some(old) => {
+                self.cache.total_bytes.fetch_sub(old.size(), Ordering::Relaxed);
+            }
+            None => {
+                self.cache.entries.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.cache.total_bytes.fetch_add(size, Ordering::Relaxed);
+        drop(shard);
+
+        self.evict_excess(shard_index, article_id, max_bytes);
+    }
+
+    /// Evict least recently used entries until the cache is back within its limits,
+    /// going through the shards from `shard_index` on and sparing pinned entries and
+    /// `kept`, the entry just cached.
+    fn evict_excess(&self, shard_index: usize, kept: ArticleId, max_bytes: Option<usize>) {
+        let over = || {
+            self.cache.entries.load(Ordering::Relaxed) > self.cache.capacity.load(Ordering::Relaxed)
+                || max_bytes.is_some_and(|max_bytes| {
+                    self.cache.total_bytes.load(Ordering::Relaxed) > max_bytes
+                })
+        };
+        let mut idle_shards = 0;
+        let mut next = shard_index;
+        while over() && idle_shards < CACHE_SHARDS {
+            let mut shard = self.cache.lock(next);
+            let evictable = shard
+                .iter()
+                .rev()
+                .find(|&(&id, entry)| id != kept && !entry.pinned)
+                .map(|(&id, _)| id);
+            match evictable.and_then(|id| shard.pop(&id)) {
+                Some(evicted) => {
+                    self.cache.removed(&evicted);
+                    idle_shards = 0;
+                }
+                None => {
+                    idle_shards += 1;
+                    next = (next + 1) % CACHE_SHARDS;
+                }
+            }
+        }
+    }
+
+    /// Pin the cached entries `pinned` says are, and unpin the others.
+    fn repin(&self, pinned: impl Fn(ArticleId) -> bool) {
+        for shard in 0..CACHE_SHARDS {
+            for (&id, entry) in self.cache.lock(shard).iter_mut() {
+                entry.pinned = pinned(id);
+            }
+        }
+    }
+
+    fn remove(&self, article_id: ArticleId) {
+        let mut shard = self.cache.lock(CacheShards::shard_of(article_id));
+        if let Some(old) = shard.pop(&article_id) {
+            self.cache.removed(&old);
+        }
+    }
+
+    fn clear(&self) {
+        self.cache.clear();
+    }
+
+    /// Empty the cache and change how many entries it holds.
+    fn reset(&self, capacity: usize) {
+        self.cache.clear();
+        self.cache.capacity.store(capacity, Ordering::Relaxed);
+    }
+
+    fn occupancy(&self) -> CacheOccupancy {
+        CacheOccupancy {
+            entries: self.cache.entries.load(Ordering::Relaxed),
+            capacity: self.cache.capacity.load(Ordering::Relaxed),
+            bytes: self.cache.total_bytes.load(Ordering::Relaxed),
+            max_bytes: self.max_bytes(),
+        }
+    }
+}
+
+// ===== ARTICLE INDEX =====
+
+/// How many searches an index keeps the results of, for paging through them.
+const SEARCH_CACHE_ENTRIES: usize = 64;
+
+/// How long the results of a search are reused.
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// A search as the results cache tells them apart: tags are sorted and deduplicated,
+/// since their order doesn't change the results.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SearchKey {
+    query: String,
+    fields: SearchFields,
+    tags: Vec<String>,
+}
+
+impl SearchKey {
+    fn new(query: &str, fields: SearchFields, tags: &[String]) -> Self {
+        let mut tags = tags.to_vec();
+        tags.sort_unstable();
+        tags.dedup();
+        Self {
+            query: query.to_string(),
+            fields,
+            tags,
+        }
+    }
+}
+
+/// The ranked IDs a search found, and when it ran.
+struct SearchResults {
+    ran_at: Instant,
+    ids: Arc<[ArticleId]>,
+}
+
+/// Holds indices for quick lookups: by article ID, by tag, by alias, and a sorted
+/// list of IDs, along with the tombstones of deleted articles.
+pub struct ArticleIndex {
+    by_id: DashMap<ArticleId, Arc<Metainfo>>,
+    /// Article IDs by tag as they're added; moved into `postings` once sorted.
+    by_tag: DashMap<String, Vec<ArticleId>>,
+    /// Article IDs by keyword, like `by_tag`.
+    by_keyword: DashMap<String, Vec<ArticleId>>,
+    by_alias: DashMap<String, ArticleId>,
+    tombstones: DashMap<ArticleId, Tombstone>,
+    /// The sorted ID lists, fixed when the index is complete so listings share them.
+    postings: OnceLock<Postings>,
+    /// The tag postings merged by lowercased tag, built on first case-insensitive match.
+    lowercase_tags: OnceLock<HashMap<String, SortedIds>>,
+    /// The tag postings merged by [`tag_slug`], built on first match by slug.
+    slug_tags: OnceLock<HashMap<String, SortedIds>>,
+    /// Computed on first request; every change publishes a new index.
+    content_stats: OnceLock<Arc<ContentStats>>,
+    /// Every article rendered at load time, with `prerender` on, and the version
+    /// of its source.
+    prerendered: DashMap<ArticleId, (Article, Option<SourceVersion>)>,
+    /// The results of recent searches; every change publishes a new index, which
+    /// starts out without any.
+    searches: Mutex<LruCache<SearchKey, SearchResults>>,
+}
+
+impl ArticleIndex {
+    fn new() -> Self {
+        Self {
+            by_id: DashMap::new(),
+            by_tag: DashMap::new(),
+            by_keyword: DashMap::new(),
+            by_alias: DashMap::new(),
+            tombstones: DashMap::new(),
+            postings: OnceLock::new(),
+            lowercase_tags: OnceLock::new(),
+            slug_tags: OnceLock::new(),
+            content_stats: OnceLock::new(),
+            prerendered: DashMap::new(),
+            searches: Mutex::new(LruCache::new(SEARCH_CACHE_ENTRIES)),
+        }
+    }
+
+    fn add_metainfo(&self, metainfo: Arc<Metainfo>) {
+        let article_id = metainfo.id;
+        self.by_id.insert(article_id, Arc::clone(&metainfo));
+        
+        // Build inverted index of tag -> article IDs, which private articles stay out of
+        if !metainfo.is_private() {
+            for tag in metainfo.tags.iter() {
+                self.by_tag.entry(tag.clone()).or_default().push(article_id);
+            }
+            for keyword in metainfo.keywords.iter() {
+                self.by_keyword.entry(keyword.clone()).or_default().push(article_id);
+            }
+        }
+        for alias in metainfo.aliases.iter() {
+            self.by_alias.entry(alias.to_string()).or_insert(article_id);
+        }
+    }
+
+    fn add_tombstone(&self, tombstone: Tombstone) {
+        self.tombstones.insert(tombstone.id, tombstone);
+    }
+
+    fn sort_indices(&self) {
+        self.postings();
+    }
+
+    /// The sorted ID lists, built from the articles added so far on first use.
+    /// Articles added afterwards aren't listed, so an index is only shared once
+    /// it's complete.
+    fn postings(&self) -> &Postings {
+        self.postings.get_or_init(|| {
+            // Sort all listed IDs globally
+            let all_ids: Vec<_> = self.listed_metainfo().iter().map(|m| m.id).collect();
+
+            Postings {
+                all: self.sorted_ids(all_ids),
+                by_tag: self.sorted_postings(&self.by_tag),
+                by_keyword: self.sorted_postings(&self.by_keyword),
+            }
+        })
+    }
+
+    /// Sort the article IDs within each tag or keyword of `inverted`, emptying it.
+    fn sorted_postings(
+        &self,
+        inverted: &DashMap<String, Vec<ArticleId>>,
+    ) -> HashMap<String, SortedIds> {
+        let mut sorted = HashMap::with_capacity(inverted.len());
+        for mut entry in inverted.iter_mut() {
+            let ids = std::mem::take(entry.value_mut());
+            sorted.insert(entry.key().clone(), self.sorted_ids(ids));
+        }
+        inverted.clear();
+        sorted
+    }
+
+    /// The tag postings merged by `key`, for the tags it maps to the same key.
+    fn merged_tags(&self, key: impl Fn(&str) -> String) -> HashMap<String, SortedIds> {
+        let mut merged: HashMap<String, Vec<ArticleId>> = HashMap::new();
+        for (tag, ids) in self.postings().by_tag.iter() {
+            merged.entry(key(tag)).or_default().extend(ids.by_id.iter().copied());
+        }
+        merged.into_iter().map(|(key, ids)| (key, self.sorted_ids(ids))).collect()
+    }
+
+    /// The sorted IDs of the articles carrying `tag`, as `matching` matches it.
+    fn tag_postings(&self, tag: &str, matching: TagMatching) -> Option<&SortedIds> {
+        match matching {
+            TagMatching::Exact => self.postings().by_tag.get(tag),
+            TagMatching::CaseInsensitive => self
+                .lowercase_tags
+                .get_or_init(|| self.merged_tags(str::to_lowercase))
+                .get(&tag.to_lowercase()),
+            TagMatching::Slug => self
+                .slug_tags
+                .get_or_init(|| self.merged_tags(tag_slug))
+                .get(&tag_slug(tag)),
+        }
+    }
+
+    /// `ids` sorted in each order a listing can take, without duplicates.
+    fn sorted_ids(&self, mut ids: Vec<ArticleId>) -> SortedIds {
+        ids.sort_unstable();
+        ids.dedup();
+        let mut oldest = ids
+            .iter()
+            .filter_map(|&id| self.get_metainfo(id))
+            .map(|m| ListingKey { date: m.date, id: m.id })
+            .collect::<Vec<_>>();
+        oldest.sort_unstable();
+        let oldest = oldest.into_iter().map(|key| key.id).collect::<Vec<_>>();
+        let newest = oldest.iter().rev().copied().collect::<Vec<_>>();
+        SortedIds {
+            by_id: ids.into(),
+            newest: newest.into(),
+            oldest: oldest.into(),
+        }
+    }
+
+    fn get_all_ids(&self) -> Arc<[ArticleId]> {
+        self.get_ordered_ids(IdList::All, DefaultOrder::Id)
+    }
+
+    fn get_ids_by_tag(&self, tag: &str, matching: TagMatching) -> Arc<[ArticleId]> {
+        self.get_ordered_ids(IdList::Tag(tag, matching), DefaultOrder::Id)
+    }
+
+    fn get_ids_by_keyword(&self, keyword: &str) -> Arc<[ArticleId]> {
+        self.get_ordered_ids(IdList::Keyword(keyword), DefaultOrder::Id)
+    }
+
+    /// The IDs of the articles in `list`, in `order`.
+    fn get_ordered_ids(&self, list: IdList, order: DefaultOrder) -> Arc<[ArticleId]> {
+        let postings = self.postings();
+        let sorted = match list {
+            IdList::All => Some(&postings.all),
+            IdList::Tag(tag, matching) => self.tag_postings(tag, matching),
+            IdList::Keyword(keyword) => postings.by_keyword.get(keyword),
+        };
+        let Some(sorted) = sorted else {
+            return Arc::from([]);
+        };
+        let ids = match order {
+            DefaultOrder::Id => &sorted.by_id,
+            DefaultOrder::Newest => &sorted.newest,
+            DefaultOrder::Oldest => &sorted.oldest,
+        };
+        Arc::clone(ids)
+    }
+
+    fn tag_count(&self) -> usize {
+        self.postings().by_tag.len()
+    }
+
+    fn get_metainfo(&self, article_id: ArticleId) -> Option<Arc<Metainfo>> {
+        self.by_id.get(&article_id).map(|entry| Arc::clone(entry.value()))
+    }
+
+    fn get_alias_target(&self, alias: &str) -> Option<ArticleId> {
+        self.by_alias.get(alias).map(|entry| *entry.value())
+    }
+
+    fn get_prerendered(&self, article_id: ArticleId) -> Option<(Article, Option<SourceVersion>)> {
+        self.prerendered.get(&article_id).map(|entry| entry.value().clone())
+    }
+
+    /// The results of the search `key`, if it ran less than [`SEARCH_CACHE_TTL`] ago.
+    fn get_search(&self, key: &SearchKey) -> Option<Arc<[ArticleId]>> {
+        let mut searches = self.searches.lock().unwrap();
+        match searches.get(key) {
+            Some(results) if results.ran_at.elapsed() <= SEARCH_CACHE_TTL => {
+                Some(Arc::clone(&results.ids))
+            }
+            Some(_) => {
+                searches.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn put_search(&self, key: SearchKey, ids: Arc<[ArticleId]>) {
+        let results = SearchResults {
+            ran_at: Instant::now(),
+            ids,
+        };
+        self.searches.lock().unwrap().put(key, results);
+    }
+
+    fn get_tombstone(&self, article_id: ArticleId) -> Option<Tombstone> {
+        self.tombstones.get(&article_id).map(|entry| entry.value().clone())
+    }
+
+    /// Where an article or tombstone declaring `article_id` was found, if any.
+    fn declared_by(&self, article_id: ArticleId) -> Option<PathBuf> {
+        self.get_metainfo(article_id)
+            .map(|metainfo| metainfo.dir.clone())
+            .or_else(|| self.get_tombstone(article_id).map(|tombstone| tombstone.dir))
+    }
+
+    /// The ID for a new article: one past the highest ID of an article or a
+    /// tombstone, so deleted articles' IDs aren't reused.
+    fn next_id(&self) -> ArticleId {
+        let highest = self
+            .by_id
+            .iter()
+            .map(|entry| *entry.key())
+            .chain(self.tombstones.iter().map(|entry| *entry.key()))
+            .max();
+        highest.map_or(1, |max| (max + 1).max(1))
+    }
+
+    /// The error for a request for an article that isn't in the index: gone if it
+    /// left a tombstone, not found otherwise.
+    fn missing(&self, article_id: ArticleId) -> Error {
+        match self.get_tombstone(article_id) {
+            Some(tombstone) => Error::Gone {
+                id: article_id,
+                successor: tombstone.successor,
+            },
+            None => Error::NotFound(article_id),
+        }
+    }
+
+    fn all_metainfo(&self) -> Vec<Arc<Metainfo>> {
+        self.by_id.iter().map(|entry| Arc::clone(entry.value())).collect()
+    }
+
+    /// The metadata of every article but the private ones.
+    fn listed_metainfo(&self) -> Vec<Arc<Metainfo>> {
+        self.all_metainfo().into_iter().filter(|m| !m.is_private()).collect()
+    }
+
+    /// The number of articles but the private ones.
+    fn listed_count(&self) -> usize {
+        self.by_id.iter().filter(|entry| !entry.value().is_private()).count()
+    }
+
+    fn tag_counts(&self) -> Vec<(String, usize)> {
+        let mut tags: Vec<_> = self
+            .postings()
+            .by_tag
+            .iter()
+            .map(|(tag, ids)| (tag.clone(), ids.by_id.len()))
+            .collect();
+        tags.sort_unstable();
+        tags
+    }
+}
+
+/// The sorted article IDs of a complete index, overall, per tag and per keyword.
+struct Postings {
+    all: SortedIds,
+    by_tag: HashMap<String, SortedIds>,
+    by_keyword: HashMap<String, SortedIds>,
+}
+
+/// Which articles a list of IDs holds.
+#[derive(Clone, Copy)]
+enum IdList<'a> {
+    All,
+    Tag(&'a str, TagMatching),
+    Keyword(&'a str),
+}
+
+/// The same article IDs sorted by ID and by date, so a listing in any order can
+/// page through a shared list.
+struct SortedIds {
+    by_id: Arc<[ArticleId]>,
+    /// Newest first, by date and then ID.
+    newest: Arc<[ArticleId]>,
+    /// Oldest first, by date and then ID.
+    oldest: Arc<[ArticleId]>,
+}
+
+// ===== RENDER CACHE =====
+
+/// On-disk cache of rendered HTML, keyed by a hash of the Markdown source and the
+/// rendering options, so restarts and LRU evictions don't have to re-run comrak.
+#[derive(Clone)]
+struct RenderCache {
+    cache_dir: Option<PathBuf>,
+}
+
+impl RenderCache {
+    fn new(cache_dir: Option<PathBuf>) -> Self {
+        if let Some(dir) = &cache_dir
+            && let Err(e) = fs::create_dir_all(dir)
+        {
+            warn!("Cannot create render cache directory {:?}: {}", dir, e);
+        }
+        Self { cache_dir }
+    }
+
+    /// Render `markdown` to HTML with the extensions of `config`, reusing a previously
+    /// rendered file when available.
+    fn render(&self, markdown: &str, config: &config::Config) -> String {
+        let cache_dir = match &self.cache_dir {
+            Some(dir) => dir,
+            None => return markdown.to_html_with_config(config),
+        };
+
+        let cache_path = cache_dir.join(format!("{}.html", Self::cache_key(markdown, config)));
+        if let Ok(html) = fs::read_to_string(&cache_path) {
+            return html;
+        }
+
+        let html = markdown.to_html_with_config(config);
+        if let Err(e) = Self::write_atomically(&cache_path, &html) {
+            warn!("Failed to write render cache file {:?}: {}", cache_path, e);
+        }
+        html
+    }
+
+    /// Hash the Markdown source together with the extension settings, so changing
+    /// the rendering configuration never serves stale HTML.
+    fn cache_key(markdown: &str, config: &config::Config) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}", config.extensions).as_bytes());
+        hasher.update(markdown.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Write to a temporary file first so concurrent readers never see partial HTML.
+    fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+        let tmp_path = path.with_extension("html.tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, path)
+    }
+}
+
+// ===== SAMPLE ARTICLE =====
+
+/// The Markdown of the sample article.
+const SAMPLE_MARKDOWN: &str = include_str!("udhr.md");
+
+/// A sample article, to be optionally injected based on user config, rendered with
+/// the extensions of `config`.
+fn build_sample_article(config: &config::Config) -> Article {
+    Article {
+        id: 0,
+        title: "Universal Declaration of Human Rights".into(),
+        description: "The Universal Declaration of Human Rights is a seminal document ...".into(),
+        content: SAMPLE_MARKDOWN.to_html_with_config(config).into(),
+        date: 19481210,
+        tags: vec!["Politics".to_string(), "History".to_string()].into(),
+        keywords: vec!["human rights".to_string(), "united nations".to_string()].into(),
+        canonical_url: config.mainconfig.article_url(0).map(Arc::from),
+        license: None,
+        source: None,
+        attribution: None,
+        extra: Extra::default(),
+        reactions: ReactionCounts::new(),
+    }
+}
+
+// ===== SINGLE-FLIGHT LOADING =====
+
+/// The shared outcome of one in-flight article load, handed to every waiting caller.
+type LoadSlot = Arc<OnceLock<std::result::Result<Article, Error>>>;
+
+/// Coalesces concurrent loads of the same article, so that only one caller reads and
+/// renders it from disk while the others wait for that result.
+#[derive(Clone)]
+struct LoadCoalescer {
+    in_flight: Arc<DashMap<(ArticleId, ContentFormat), LoadSlot>>,
+}
+
+impl LoadCoalescer {
+    fn new() -> Self {
+        Self {
+            in_flight: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Run `load` for `article_id` in `format`, unless a load for the same ID and
+    /// format is already running, in which case block until it finishes and share its
+    /// result.
+    fn load<F>(&self, article_id: ArticleId, format: ContentFormat, load: F) -> Result<Article>
+    where
+        F: FnOnce() -> Result<Article>,
+    {
+        let slot = Arc::clone(
+            self.in_flight
+                .entry((article_id, format))
+                .or_insert_with(|| Arc::new(OnceLock::new()))
+                .value(),
+        );
+
+        let result = slot
+            .get_or_init(|| load().map_err(Error::from))
+            .clone();
+
+        // The first caller to get here retires the slot, so later misses load afresh
+        self.in_flight
+            .remove_if(&(article_id, format), |_, current| Arc::ptr_eq(current, &slot));
+
+        Ok(result?)
+    }
+}
+
+// ===== COALESCED REFRESHES =====
+
+/// How far the index refreshes have come, guarded by the [`RefreshCoalescer`]'s lock.
+#[derive(Default)]
+struct RefreshState {
+    /// The number of refreshes started.
+    started: u64,
+    /// The number of refreshes finished, the last of them with `last_result`.
+    finished: u64,
+    last_result: Option<std::result::Result<(), Error>>,
+    last_finished_at: Option<Instant>,
+    /// Whether a caller is running the next refresh, or waiting to start it.
+    running: bool,
+}
+
+/// Coalesces index refreshes, so that only one rescan runs at a time: every request
+/// made meanwhile waits for the single rescan starting after it and shares its
+/// result. A rescan starts no sooner than the minimum interval after the previous
+/// one finished, and requests made while it waits for that join it as well.
+#[derive(Clone)]
+struct RefreshCoalescer {
+    state: Arc<(Mutex<RefreshState>, Condvar)>,
+}
+
+impl RefreshCoalescer {
+    fn new() -> Self {
+        Self {
+            state: Arc::new((Mutex::new(RefreshState::default()), Condvar::new())),
+        }
+    }
+
+    /// Run `refresh` once the previous refresh is `min_interval` old, unless another
+    /// caller is about to, in which case block until that refresh finishes and share
+    /// its result.
+    fn refresh<F>(&self, min_interval: Duration, refresh: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let (lock, changed) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        // A rescan already running may have read the store before this request
+        let needed = state.started + 1;
+        while state.running && state.finished < needed {
+            state = changed.wait(state).unwrap();
+        }
+        if state.finished >= needed {
+            let result = state.last_result.clone().expect("a finished refresh has a result");
+            return Ok(result?);
+        }
+
+        state.running = true;
+        let wait = state
+            .last_finished_at
+            .map_or(Duration::ZERO, |at| min_interval.saturating_sub(at.elapsed()));
+        drop(state);
+        thread::sleep(wait);
+        lock.lock().unwrap().started += 1;
+
+        let result = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(refresh)) {
+            Ok(result) => result.map_err(Error::from),
+            Err(panic) => {
+                self.finish(Err(Error::from(anyhow!("The index refresh panicked"))));
+                std::panic::resume_unwind(panic)
+            }
+        };
+        self.finish(result.clone());
+        Ok(result?)
+    }
+
+    /// Record the `result` of the refresh that ran, and wake the callers waiting for it.
+    fn finish(&self, result: std::result::Result<(), Error>) {
+        let (lock, changed) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        state.finished += 1;
+        state.last_result = Some(result);
+        state.last_finished_at = Some(Instant::now());
+        state.running = false;
+        changed.notify_all();
+    }
+}
+
+// ===== PAGINATOR =====
+
+/// Helper for article pagination operations
+struct Paginator;
+
+impl Paginator {
+    /// Generic pagination helper: given a slice of items, returns the sub-slice for `page_number`,
+    /// pages being numbered from `first_page` on. A number below it fails with
+    /// [`Error::InvalidRequest`]. An empty result has no pages but isn't an error; any page of it
+    /// is empty. Past the last page of a non-empty result, fails with [`Error::PageOutOfRange`].
+    fn paginate<T>(
+        data: &[T],
+        max_per_page: usize,
+        page_number: usize,
+        first_page: usize,
+    ) -> Result<Option<&[T]>> {
+        if max_per_page == 0 {
+            // If page size is zero, return nothing
+            return Ok(None);
+        }
+        let Some(page_index) = page_number.checked_sub(first_page) else {
+            bail!(Error::InvalidRequest(format!("'page' must be at least {}", first_page)));
+        };
+        let total_items = data.len();
+        let total_pages = Self::compute_total_pages(total_items, max_per_page);
+        if total_pages == 0 {
+            return Ok(Some(&[]));
+        }
+
+        if page_index >= total_pages {
+            bail!(Error::PageOutOfRange {
+                page: page_number,
+                pages: total_pages,
+            });
+        }
+
+        let start = page_index * max_per_page;
+        let end = (start + max_per_page).min(total_items);
+        Ok(Some(&data[start..end]))
+    }
+
+    /// Compute how many pages are needed to hold `total_items` items with the given `max_per_page`.
+    fn compute_total_pages(total_items: usize, max_per_page: usize) -> usize {
+        if total_items == 0 || max_per_page == 0 {
+            0
+        } else {
+            total_items.div_ceil(max_per_page)
+        }
+    }
+}
+
+// ===== MAIN ARTICLES FACADE =====
+
+/// Manages a set of articles from one or more source directories, plus an LRU cache for recently accessed articles.
+pub struct Articles {
+    store: Arc<dyn ArticleStore>,
+    render_cache: RenderCache,
+    cache: ArticleCache,
+    /// The live index; refreshes build a replacement and swap it in atomically.
+    index: Arc<ArcSwap<ArticleIndex>>,
+    loads: LoadCoalescer,
+    refreshes: RefreshCoalescer,
+    /// The built-in sample article, if it's served as ID 0.
+    sample: Option<Article>,
+    /// The articles to pin rather than the configured `pinned_articles`.
+    pinned_articles: Option<Arc<[ArticleId]>>,
+    /// The configuration in effect, shared with the store and replaced on reload.
+    config: SharedConfig,
+    /// Optional SQLite copy of the index answering listing, tag and search queries.
+    #[cfg(feature = "sqlite")]
+    sql_index: Option<Arc<SqliteIndex>>,
+    /// Serializes index reloads and content writes, so neither loses the other's changes.
+    writes: Arc<Mutex<()>>,
+    /// What the last index load found, entry by entry.
+    last_report: Arc<ArcSwap<ScanReport>>,
+    /// Announces articles being added, changed or removed.
+    events: EventBus,
+    /// Whether the index was loaded yet; a lazily built instance loads it on first use.
+    indexed: Arc<AtomicBool>,
+    /// The comments readers left, counted in the summaries.
+    comments: Option<Arc<Comments>>,
+    /// When each article's source was last checked by fingerprint; see
+    /// [`Articles::unchanged`].
+    revalidated: Arc<DashMap<ArticleId, Instant>>,
+}
+
+impl Clone for Articles {
+    fn clone(&self) -> Self {
+        Articles {
+            store: Arc::clone(&self.store),
+            render_cache: self.render_cache.clone(),
+            cache: self.cache.clone(),
+            index: Arc::clone(&self.index),
+            loads: self.loads.clone(),
+            refreshes: self.refreshes.clone(),
+            sample: self.sample.clone(),
+            pinned_articles: self.pinned_articles.clone(),
+            config: Arc::clone(&self.config),
+            #[cfg(feature = "sqlite")]
+            sql_index: self.sql_index.clone(),
+            writes: Arc::clone(&self.writes),
+            last_report: Arc::clone(&self.last_report),
+            events: self.events.clone(),
+            indexed: Arc::clone(&self.indexed),
+            comments: self.comments.clone(),
+            revalidated: Arc::clone(&self.revalidated),
+        }
+    }
+}
+
+// ===== ARTICLES BUILDER =====
+
+/// Configures and builds an [`Articles`] instance; see [`Articles::builder`].
+///
+/// Anything left unset follows the configuration: the store it selects, an LRU
+/// cache of `max_cached_articles` entries and its `sample_article` and
+/// `pinned_articles` settings.
+pub struct ArticlesBuilder {
+    config: Option<SharedConfig>,
+    store: Option<Arc<dyn ArticleStore>>,
+    source_dirs: Vec<PathBuf>,
+    cache: Option<CacheShards>,
+    cache_capacity: Option<usize>,
+    sample_article: Option<bool>,
+    pinned_articles: Option<Vec<ArticleId>>,
+    lazy_index: bool,
+    comments: Option<Arc<Comments>>,
+    #[cfg(feature = "sqlite")]
+    sql_index: Option<Option<Arc<SqliteIndex>>>,
+}
+
+impl ArticlesBuilder {
+    fn new() -> Self {
+        Self {
+            config: None,
+            store: None,
+            source_dirs: Vec::new(),
+            cache: None,
+            cache_capacity: None,
+            sample_article: None,
+            pinned_articles: None,
+            lazy_index: false,
+            comments: None,
+            #[cfg(feature = "sqlite")]
+            sql_index: None,
+        }
+    }
+
+    /// The configuration to follow, [`config::Config::defaults`] if unset.
+    pub fn config(mut self, config: SharedConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Read the articles from `store` rather than the configured one.
+    pub fn store(mut self, store: Arc<dyn ArticleStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Read the articles from this directory on the local filesystem, after any
+    /// added before, rather than from the configured store.
+    pub fn source_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.source_dirs.push(dir.into());
+        self
+    }
+
+    /// Read the articles from these directories, like [`ArticlesBuilder::source_dir`].
+    pub fn source_dirs<I: IntoIterator<Item = P>, P: Into<PathBuf>>(mut self, dirs: I) -> Self {
+        self.source_dirs.extend(dirs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Cache articles in `cache`, which may be shared with other instances.
+    pub fn cache(mut self, cache: CacheShards) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Cache up to `capacity` articles rather than `max_cached_articles`, unless a
+    /// cache is given with [`ArticlesBuilder::cache`].
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Whether to serve the built-in sample article as ID 0.
+    pub fn sample_article(mut self, sample_article: bool) -> Self {
+        self.sample_article = Some(sample_article);
+        self
+    }
+
+    /// Never evict these articles from the cache, besides those whose metadata
+    /// sets `pinned`.
+    pub fn pinned_articles(mut self, ids: Vec<ArticleId>) -> Self {
+        self.pinned_articles = Some(ids);
+        self
+    }
+
+    /// Defer loading the index from the store until it's first needed, rather than
+    /// loading it in [`ArticlesBuilder::build`].
+    pub fn lazy_index(mut self, lazy: bool) -> Self {
+        self.lazy_index = lazy;
+        self
+    }
+
+    /// Count the approved comments in `comments` in the summaries, and serve them
+    /// from the comment endpoints.
+    pub fn comments(mut self, comments: Arc<Comments>) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    /// Answer listing, tag and search queries from `sql_index`, or from the in-memory
+    /// index if `None`, rather than as the `[index]` configuration says.
+    #[cfg(feature = "sqlite")]
+    pub fn sql_index(mut self, sql_index: Option<Arc<SqliteIndex>>) -> Self {
+        self.sql_index = Some(sql_index);
+        self
+    }
+
+    /// Build the instance. Fails if the configured store can't be opened; a failed
+    /// index load is only logged, leaving the index empty until the next one.
+    pub fn build(self) -> Result<Articles> {
+        let config = self
+            .config
+            .unwrap_or_else(|| config::shared(config::Config::defaults()));
+        let current = config.load_full();
+        let store = match self.store {
+            Some(store) => store,
+            None if !self.source_dirs.is_empty() => {
+                Arc::new(FsStore::new(self.source_dirs, Arc::clone(&config))?)
+            }
+            None => store::from_config(&config)?,
+        };
+        let cache = self.cache.unwrap_or_else(|| {
+            CacheShards::new(self.cache_capacity.unwrap_or(current.mainconfig.max_cached_articles))
+        });
+        let sample_article = self.sample_article.unwrap_or(current.mainconfig.sample_article);
+
+        info!("Initializing Articles");
+        let render_cache =
+            RenderCache::new(current.mainconfig.render_cache_dir.clone().map(PathBuf::from));
+        #[allow(unused_mut)]
+        let mut articles = Articles {
+            store,
+            render_cache,
+            cache: ArticleCache::new(cache, Arc::clone(&config)),
+            index: Arc::new(ArcSwap::from_pointee(ArticleIndex::new())),
+            loads: LoadCoalescer::new(),
+            refreshes: RefreshCoalescer::new(),
+            sample: sample_article.then(|| build_sample_article(&current)),
+            pinned_articles: self.pinned_articles.map(Arc::from),
+            config,
+            #[cfg(feature = "sqlite")]
+            sql_index: None,
+            writes: Arc::new(Mutex::new(())),
+            last_report: Arc::new(ArcSwap::from_pointee(ScanReport::default())),
+            events: EventBus::new(),
+            indexed: Arc::new(AtomicBool::new(false)),
+            comments: self.comments,
+            revalidated: Arc::new(DashMap::new()),
+        };
+
+        #[cfg(feature = "sqlite")]
+        match self.sql_index {
+            Some(Some(sql_index)) => articles.attach_sql_index(sql_index),
+            Some(None) => {}
+            None if current.index.backend == "sqlite" => {
+                match SqliteIndex::open(&current.index.sqlite_path) {
+                    Ok(sql_index) => articles.attach_sql_index(Arc::new(sql_index)),
+                    Err(e) => {
+                        error!("Failed to open SQLite index, using the in-memory index: {}", e)
+                    }
+                }
+            }
+            None => {}
+        }
+
+        if !self.lazy_index {
+            articles.ensure_index();
+        }
+        Ok(articles)
+    }
+}
+
+impl Articles {
+    /// Start configuring a new `Articles` manager.
+    pub fn builder() -> ArticlesBuilder {
+        ArticlesBuilder::new()
+    }
+
+    /// Create a new `Articles` manager backed by the given [`ArticleStore`], initializing indices from it.
+    pub fn new(
+        store: Arc<dyn ArticleStore>,
+        cache: CacheShards,
+        config: SharedConfig,
+    ) -> Result<Self> {
+        Self::builder().config(config).store(store).cache(cache).build()
+    }
+
+    /// Answer listing, tag and search queries from `sql_index`, rebuilding it from
+    /// the current index now and on every index load.
+    #[cfg(feature = "sqlite")]
+    pub fn attach_sql_index(&mut self, sql_index: Arc<SqliteIndex>) {
+        let analyzer = search::Analyzer::new(&self.config.load().search);
+        if let Err(e) = sql_index.rebuild(&self.index.load().listed_metainfo(), analyzer) {
+            error!("Failed to build SQLite index: {}", e);
+        }
+        self.sql_index = Some(sql_index);
+    }
+
+    /// (Re)loads the entire article index from the filesystem.
+    ///
+    /// The new index is built off to the side and only swapped in once complete, so
+    /// concurrent readers keep seeing the previous index until then. With
+    /// `strict_duplicate_ids`, a scan that finds duplicate IDs is rejected as a whole.
+    pub fn load_index(&self) -> Result<()> {
+        let _writes = self.writes.lock().unwrap();
+        self.load_index_locked()
+    }
+
+    /// Load the index now if it never was, as a lazily built instance does on first use.
+    fn ensure_index(&self) {
+        if self.indexed.load(Ordering::Acquire) {
+            return;
+        }
+        let _writes = self.writes.lock().unwrap();
+        if !self.indexed.load(Ordering::Acquire)
+            && let Err(e) = self.load_index_locked()
+        {
+            error!("Failed to load index: {}", e);
+        }
+    }
+
+    /// The current index, loading it first if it never was.
+    fn loaded_index(&self) -> Guard<Arc<ArticleIndex>> {
+        self.ensure_index();
+        self.index.load()
+    }
+
+    /// Load the index, with `writes` held by the caller.
+    fn load_index_locked(&self) -> Result<()> {
+        let result = self.scan_and_publish();
+        // Even a failed load counts, so that reads don't retry it over and over
+        self.indexed.store(true, Ordering::Release);
+        result
+    }
+
+    fn scan_and_publish(&self) -> Result<()> {
+        let (index, report) = self.scan_index()?;
+        for entry in report.problems() {
+            warn!(
+                "Skipping {} ({}): {}",
+                entry.path,
+                entry.problem.map(|p| p.to_string()).unwrap_or_default(),
+                entry.message.as_deref().unwrap_or_default()
+            );
+        }
+
+        let duplicates = report
+            .problems()
+            .filter(|entry| matches!(entry.problem, Some(Problem::DuplicateId)))
+            .count();
+        let report = Arc::new(report);
+        self.last_report.store(Arc::clone(&report));
+        if duplicates > 0 && self.config.load().mainconfig.strict_duplicate_ids {
+            bail!(
+                "Rejected the scan: {} article(s) reuse an existing ID (see the index report)",
+                duplicates
+            );
+        }
+
+        let events = Self::index_changes(&self.index.load(), &index);
+        let articles = index.listed_count();
+        self.publish_index(index)?;
+        for event in events {
+            self.events.publish(event);
+        }
+        self.events.publish(ContentEvent::IndexRefreshed { articles });
+        Ok(())
+    }
+
+    /// The article events that replacing the index `old` with `new` amounts to.
+    fn index_changes(old: &ArticleIndex, new: &ArticleIndex) -> Vec<ContentEvent> {
+        let mut events = Vec::new();
+        for entry in new.by_id.iter() {
+            let (id, current) = (*entry.key(), entry.value());
+            let previous = old.get_metainfo(id);
+            if previous.as_deref() != Some(&**current) {
+                events.extend(Self::article_event(id, previous.as_deref(), Some(current)));
+            }
+        }
+        for entry in old.by_id.iter() {
+            if !new.by_id.contains_key(entry.key()) {
+                events.extend(Self::article_event(*entry.key(), Some(entry.value()), None));
+            }
+        }
+        events
+    }
+
+    /// The event announcing that the article `id` changed from `old` to `new`. A
+    /// private article counts as absent, so it's never announced: making an article
+    /// private removes it, and publishing it adds it.
+    fn article_event(
+        id: ArticleId,
+        old: Option<&Metainfo>,
+        new: Option<&Metainfo>,
+    ) -> Option<ContentEvent> {
+        let old = old.filter(|m| !m.is_private());
+        let new = new.filter(|m| !m.is_private());
+        match (old, new) {
+            (None, Some(_)) => Some(ContentEvent::ArticleAdded { id }),
+            (Some(_), Some(_)) => Some(ContentEvent::ArticleUpdated { id }),
+            (Some(_), None) => Some(ContentEvent::ArticleRemoved { id }),
+            (None, None) => None,
+        }
+    }
+
+    /// Subscribe to events announcing changes to the articles.
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<ContentEvent> {
+        self.events.subscribe()
+    }
+
+    /// The articles added, updated and removed since `since`, a generation or a
+    /// Unix timestamp (see [`EventBus::changes_since`]).
+    pub fn changes_since(&self, since: u64) -> Changes {
+        self.ensure_index();
+        self.events.changes_since(since)
+    }
+
+    /// The outcome of every entry examined by the last index load, including a
+    /// load rejected for duplicate IDs.
+    pub fn last_scan_report(&self) -> Arc<ScanReport> {
+        self.last_report.load_full()
+    }
+
+    /// Scan the store into a new, unpublished index, along with a report of every
+    /// entry examined.
+    fn scan_index(&self) -> Result<(ArticleIndex, ScanReport)> {
+        let started = Instant::now();
+        let config = self.config.load();
+        let index = ArticleIndex::new();
+        let mut report = ScanReport::default();
+
+        // Optionally insert the sample article
+        if let Some(sample) = &self.sample {
+            let sample_metainfo = Metainfo {
+                id: sample.id,
+                dir: PathBuf::new(),
+                title: sample.title.clone(),
+                description: sample.description.clone(),
+                markdown_path: "udhr.md".into(),
+                date: sample.date,
+                tags: sample.tags.clone(),
+                keywords: sample.keywords.clone(),
+                cover: None,
+                author: None,
+                canonical_url: None,
+                aliases: Arc::from([]),
+                license: None,
+                source: None,
+                attribution: None,
+                extra: Extra::default(),
+                pinned: false,
+                access: Access::Public,
+                token: None,
+                front_matter: false,
+                search_text: Arc::new(SearchText::new(
+                    &sample.title,
+                    &sample.description,
+                    &sample.tags,
+                    &sample.keywords,
+                )),
+            };
+            let sample_arc = Arc::new(sample_metainfo);
+            index.add_metainfo(sample_arc);
+        }
+
+        // Ask the store for real articles; the first entry to declare an ID wins
+        for entry in self.store.scan()? {
+            let ScanEntry { path, outcome, warning } = entry;
+            let metainfo = match outcome {
+                ScanOutcome::Loaded(metainfo) => metainfo,
+                ScanOutcome::Tombstone(tombstone) => {
+                    let id = tombstone.id;
+                    if let Some(existing) = index.declared_by(id) {
+                        let message = format!("ID {} is already used by {:?}", id, existing);
+                        report.push_failed(&path, Some(id), Problem::DuplicateId, message);
+                        continue;
+                    }
+                    report.push_skipped(&path, format!("tombstone of deleted article {}", id));
+                    index.add_tombstone(tombstone);
+                    continue;
+                }
+                ScanOutcome::Skipped(reason) => {
+                    report.push_skipped(&path, reason);
+                    continue;
+                }
+                ScanOutcome::Failed(problem, message) => {
+                    report.push_failed(&path, None, problem, message);
+                    continue;
+                }
+            };
+            let violations = metainfo.violations(&config.validation);
+            if config.validation.strict && !violations.is_empty() {
+                let message = violations.join("; ");
+                report.push_failed(&path, Some(metainfo.id), Problem::InvalidMetadata, message);
+                continue;
+            }
+            if let Some(existing) = index.declared_by(metainfo.id) {
+                let message = format!("ID {} is already used by {:?}", metainfo.id, existing);
+                report.flag_loaded(
+                    metainfo.id,
+                    format!("ID {} is also declared by {:?}", metainfo.id, path),
+                );
+                report.push_failed(&path, Some(metainfo.id), Problem::DuplicateId, message);
+                continue;
+            }
+            report.push_loaded(&path, metainfo.id);
+            if let Some(warning) = warning {
+                report.flag_loaded(metainfo.id, warning);
+            }
+            for violation in violations {
+                report.flag_loaded(metainfo.id, violation);
+            }
+            index.add_metainfo(Arc::from(metainfo));
+        }
+
+        report.scanned_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        report.duration_ms = started.elapsed().as_millis() as u64;
+        Ok((index, report))
+    }
+
+    /// Re-scan the store and report every problem found, without touching the live index.
+    pub fn validate(&self) -> Result<ValidationReport> {
+        self.scan_index().map(|(_, report)| report.into())
+    }
+
+    /// Sort a fully built index and swap it in as the live one.
+    fn publish_index(&self, index: ArticleIndex) -> Result<()> {
         // Sort indices for efficient access
-        self.index.sort_indices();
+        index.sort_indices();
+
+        if self.config.load().mainconfig.prerender {
+            self.reuse_prerendered(&index);
+            self.prerender(&index);
+        }
+
+        #[cfg(feature = "sqlite")]
+        if let Some(sql_index) = &self.sql_index {
+            let analyzer = search::Analyzer::new(&self.config.load().search);
+            sql_index.rebuild(&index.listed_metainfo(), analyzer)?;
+        }
+
+        // Publish the fully built index
+        self.index.store(Arc::new(index));
+        self.pin_articles(&self.index.load());
+
+        Ok(())
+    }
+
+    /// Whether the cache never evicts the article: it's listed in `pinned_articles`
+    /// or its metadata sets `pinned`.
+    fn is_pinned(&self, index: &ArticleIndex, article_id: ArticleId) -> bool {
+        let config = self.config.load();
+        let configured = match &self.pinned_articles {
+            Some(ids) => ids.as_ref(),
+            None => config.mainconfig.pinned_articles.as_slice(),
+        };
+        configured.contains(&article_id)
+            || index.get_metainfo(article_id).is_some_and(|m| m.pinned)
+    }
+
+    /// Pin the cached articles of `index` as the configuration and their metadata
+    /// say, and cache the pinned articles that aren't yet, so they're hot from the
+    /// start. Pre-rendered articles never need the cache.
+    fn pin_articles(&self, index: &ArticleIndex) {
+        self.cache.repin(|id| self.is_pinned(index, id));
+        let config = self.config.load();
+        let format = ContentFormat::configured(&config.mainconfig);
+        let configured = match &self.pinned_articles {
+            Some(ids) => ids.to_vec(),
+            None => config.mainconfig.pinned_articles.clone(),
+        };
+        let flagged = index.all_metainfo().into_iter().filter(|m| m.pinned).map(|m| m.id);
+        let pinned = configured.into_iter().chain(flagged).collect::<HashSet<_>>();
+        for article_id in pinned {
+            if (article_id == 0 && self.sample.is_some())
+                || index.prerendered.contains_key(&article_id)
+                || self.cache.get(article_id, format).is_some()
+            {
+                continue;
+            }
+            let Some(metainfo) = index.get_metainfo(article_id) else {
+                warn!("Pinned article {} is not in the index", article_id);
+                continue;
+            };
+            match self.build_article(&metainfo, format) {
+                Ok((article, version)) => self.cache.put(article_id, article, format, version, true),
+                Err(e) => warn!("Failed to cache pinned article {}: {}", article_id, e),
+            }
+        }
+    }
+
+    /// Carry the pre-rendered articles of the live index over to `index` where
+    /// neither their metadata nor their source changed, so a reload only renders
+    /// what it must.
+    fn reuse_prerendered(&self, index: &ArticleIndex) {
+        let live = self.index.load();
+        let mut reused = 0;
+        for entry in live.prerendered.iter() {
+            let article_id = *entry.key();
+            let (article, version) = entry.value();
+            let Some(metainfo) = index.get_metainfo(article_id) else {
+                continue;
+            };
+            if index.prerendered.contains_key(&article_id)
+                || live.get_metainfo(article_id).is_none_or(|previous| previous != metainfo)
+                || !self.still_current(&metainfo, version.as_ref())
+            {
+                continue;
+            }
+            index.prerendered.insert(article_id, (article.clone(), version.clone()));
+            reused += 1;
+        }
+        if reused > 0 {
+            info!("Reused {} unchanged pre-rendered article(s)", reused);
+        }
+    }
+
+    /// Render every article of `index` not rendered yet, spread over as many threads
+    /// as there are CPUs, so requests for them never reach the store. An article that
+    /// fails to render is left to be loaded on request.
+    fn prerender(&self, index: &ArticleIndex) {
+        let started = Instant::now();
+        let pending = index
+            .all_metainfo()
+            .into_iter()
+            .filter(|m| !(m.id == 0 && self.sample.is_some()))
+            .filter(|m| !index.prerendered.contains_key(&m.id))
+            .collect::<Vec<_>>();
+        if pending.is_empty() {
+            return;
+        }
+        let format = ContentFormat::configured(&self.config.load().mainconfig);
+        let threads = thread::available_parallelism().map_or(1, usize::from);
+        thread::scope(|scope| {
+            for chunk in pending.chunks(pending.len().div_ceil(threads)) {
+                scope.spawn(move || {
+                    for metainfo in chunk {
+                        match self.build_article(metainfo, format) {
+                            Ok(loaded) => {
+                                index.prerendered.insert(metainfo.id, loaded);
+                            }
+                            Err(e) => warn!("Failed to pre-render article {}: {}", metainfo.id, e),
+                        }
+                    }
+                });
+            }
+        });
+        info!(
+            "Pre-rendered {} article(s) in {} ms",
+            pending.len(),
+            started.elapsed().as_millis()
+        );
+    }
+
+    /// Write a new article to the store and add it to the index without a full
+    /// rescan. Fails with a [`Error`] if the draft is invalid or its ID is taken.
+    pub fn create_article(&self, draft: &ArticleDraft) -> Result<Article> {
+        draft.validate(self.config.load().mainconfig.max_article_bytes)?;
+        self.create_from_files(draft.id, |id| draft.to_bundle(id))
+    }
+
+    /// Publish an uploaded bundle (`metainfo.toml`, Markdown and assets) as a new
+    /// article, after checking that it is complete. Fails with a [`Error`] if
+    /// the bundle is invalid or its ID is taken.
+    pub fn create_from_bundle(&self, files: Vec<BundleFile>) -> Result<Article> {
+        let id = bundle::validate(&files, self.config.load().mainconfig.max_article_bytes)?;
+        self.create_from_files(Some(id), |_| Ok(files))
+    }
+
+    /// Write the files of a new article to the store and add it to the index,
+    /// assigning the next free ID if `id` is `None`.
+    fn create_from_files(
+        &self,
+        id: Option<ArticleId>,
+        files: impl FnOnce(ArticleId) -> Result<Vec<BundleFile>>,
+    ) -> Result<Article> {
+        self.ensure_index();
+        let writes = self.writes.lock().unwrap();
+        let current = self.index.load_full();
+
+        let id = id.unwrap_or_else(|| current.next_id());
+        if current.declared_by(id).is_some() {
+            bail!(Error::Conflict(id));
+        }
+
+        let metainfo = Arc::new(self.store.create(id, &files(id)?)?);
+        info!("Created article {} in {:?}", id, metainfo.dir);
+        self.publish_index_change(&current, id, Some(metainfo), None)?;
+
+        drop(writes);
+        self.get_article(id).map(|(article, _)| article)
+    }
+
+    /// Write many articles to the store at once, resolving taken IDs as `on_conflict`
+    /// says, then reload the index. Articles that can't be imported are reported
+    /// without stopping the others.
+    pub fn import(&self, items: Vec<ImportItem>, on_conflict: OnConflict) -> Result<ImportReport> {
+        self.ensure_index();
+        let _writes = self.writes.lock().unwrap();
+        let current = self.index.load_full();
+        let max_article_bytes = self.config.load().mainconfig.max_article_bytes;
+
+        let mut report = ImportReport::default();
+        let mut written = HashSet::new();
+        let mut next_id = current.next_id();
+        for item in items {
+            let requested = match &item {
+                ImportItem::Draft(draft) => draft.validate(max_article_bytes).map(|_| draft.id),
+                ImportItem::Bundle(files) => bundle::validate(files, max_article_bytes).map(Some),
+            };
+            let entry = match requested {
+                Ok(requested) => {
+                    let result = self.import_item(&current, item, requested, on_conflict, &mut written, &mut next_id);
+                    match result {
+                        Ok((id, outcome)) => ImportEntry {
+                            id: Some(id),
+                            renumbered_from: requested.filter(|&requested| requested != id),
+                            outcome,
+                            message: None,
+                        },
+                        Err(e) => ImportEntry {
+                            id: requested,
+                            renumbered_from: None,
+                            outcome: "failed",
+                            message: Some(e.to_string()),
+                        },
+                    }
+                }
+                Err(e) => ImportEntry {
+                    id: None,
+                    renumbered_from: None,
+                    outcome: "failed",
+                    message: Some(e.to_string()),
+                },
+            };
+            report.push(entry);
+        }
+
+        if report.created + report.overwritten > 0 {
+            info!(
+                "Imported {} new and {} overwritten article(s)",
+                report.created, report.overwritten
+            );
+            for &id in &written {
+                self.cache.remove(id);
+            }
+            self.load_index_locked()?;
+        }
+        Ok(report)
+    }
+
+    /// Write one imported article, returning the ID it got and the outcome. IDs
+    /// `written` earlier in the import count as taken.
+    fn import_item(
+        &self,
+        index: &ArticleIndex,
+        item: ImportItem,
+        requested: Option<ArticleId>,
+        on_conflict: OnConflict,
+        written: &mut HashSet<ArticleId>,
+        next_id: &mut ArticleId,
+    ) -> Result<(ArticleId, &'static str)> {
+        let is_taken = |id: ArticleId, written: &HashSet<ArticleId>| {
+            index.declared_by(id).is_some() || written.contains(&id)
+        };
+        let mut free_id = |written: &HashSet<ArticleId>| {
+            while is_taken(*next_id, written) {
+                *next_id += 1;
+            }
+            *next_id
+        };
+        let id = match requested {
+            None => free_id(written),
+            Some(id) if !is_taken(id, written) => id,
+            Some(id) => match on_conflict {
+                OnConflict::Skip => return Ok((id, "skipped")),
+                OnConflict::Renumber => free_id(written),
+                OnConflict::Overwrite => {
+                    if written.contains(&id) {
+                        bail!(Error::InvalidRequest(format!("ID {} appears twice in the import", id)));
+                    }
+                    let existing = self.writable_metainfo(index, id)?;
+                    match item {
+                        ImportItem::Draft(draft) => {
+                            self.store.update(&existing, &draft)?;
+                        }
+                        ImportItem::Bundle(files) => {
+                            self.store.delete(&existing)?;
+                            self.store.create(id, &files)?;
+                        }
+                    }
+                    written.insert(id);
+                    return Ok((id, "overwritten"));
+                }
+            },
+        };
+
+        let files = match item {
+            ImportItem::Draft(draft) => draft.to_bundle(id)?,
+            ImportItem::Bundle(mut files) => {
+                if requested != Some(id) {
+                    bundle::renumber(&mut files, id)?;
+                }
+                files
+            }
+        };
+        self.store.create(id, &files)?;
+        written.insert(id);
+        Ok((id, "created"))
+    }
+
+    /// Replace the metadata and Markdown source of an existing article, updating
+    /// the index and dropping its cache entry. The draft's ID, if given, must match.
+    pub fn update_article(&self, article_id: ArticleId, draft: &ArticleDraft) -> Result<Article> {
+        draft.validate(self.config.load().mainconfig.max_article_bytes)?;
+        if draft.id.is_some_and(|id| id != article_id) {
+            bail!(Error::InvalidRequest("'id' does not match the article being updated".into()));
+        }
+        self.ensure_index();
+        let writes = self.writes.lock().unwrap();
+        let current = self.index.load_full();
+        let existing = self.writable_metainfo(&current, article_id)?;
+
+        let metainfo = Arc::new(self.store.update(&existing, draft)?);
+        info!("Updated article {}", article_id);
+        self.publish_index_change(&current, article_id, Some(metainfo), None)?;
+        self.cache.remove(article_id);
+
+        drop(writes);
+        self.get_article(article_id).map(|(article, _)| article)
+    }
+
+    /// Remove an article from the store, the index and the cache.
+    pub fn delete_article(&self, article_id: ArticleId) -> Result<()> {
+        self.ensure_index();
+        let _writes = self.writes.lock().unwrap();
+        let current = self.index.load_full();
+        let existing = self.writable_metainfo(&current, article_id)?;
+
+        self.store.delete(&existing)?;
+        info!("Deleted article {}", article_id);
+        self.publish_index_change(&current, article_id, None, None)?;
+        self.cache.remove(article_id);
+        Ok(())
+    }
 
+    /// Delete an article like [`Articles::delete_article`], leaving a tombstone in
+    /// its place so that its ID reports it as gone, and points to `successor` if given.
+    pub fn bury_article(&self, article_id: ArticleId, successor: Option<ArticleId>) -> Result<()> {
+        self.ensure_index();
+        let _writes = self.writes.lock().unwrap();
+        let current = self.index.load_full();
+        let existing = self.writable_metainfo(&current, article_id)?;
+        if let Some(successor) = successor
+            && (successor == article_id || current.get_metainfo(successor).is_none())
+        {
+            bail!(Error::InvalidRequest(format!(
+                "successor {} is not another existing article",
+                successor
+            )));
+        }
+
+        self.store.delete(&existing)?;
+        let tombstone = self.store.bury(&existing, successor)?;
+        info!("Deleted article {}, leaving a tombstone in {:?}", article_id, tombstone.dir);
+        self.publish_index_change(&current, article_id, None, Some(tombstone))?;
+        self.cache.remove(article_id);
+        Ok(())
+    }
+
+    /// Look up an article that may be modified; the built-in sample article can't be.
+    fn writable_metainfo(&self, index: &ArticleIndex, article_id: ArticleId) -> Result<Arc<Metainfo>> {
+        if article_id == 0 && self.sample.is_some() {
+            bail!(Error::InvalidRequest("the sample article is read-only".into()));
+        }
+        index
+            .get_metainfo(article_id)
+            .ok_or_else(|| index.missing(article_id).into())
+    }
+
+    /// Publish a copy of `current` in which `article_id` maps to `metainfo`, or is
+    /// removed when `metainfo` is `None`, optionally leaving `tombstone` behind.
+    fn publish_index_change(
+        &self,
+        current: &ArticleIndex,
+        article_id: ArticleId,
+        metainfo: Option<Arc<Metainfo>>,
+        tombstone: Option<Tombstone>,
+    ) -> Result<()> {
+        let index = ArticleIndex::new();
+        for existing in current.all_metainfo() {
+            if existing.id != article_id {
+                if let Some(article) = current.get_prerendered(existing.id) {
+                    index.prerendered.insert(existing.id, article);
+                }
+                index.add_metainfo(existing);
+            }
+        }
+        for entry in current.tombstones.iter() {
+            index.add_tombstone(entry.value().clone());
+        }
+        if let Some(tombstone) = tombstone {
+            index.add_tombstone(tombstone);
+        }
+        let previous = current.get_metainfo(article_id);
+        let event = Self::article_event(article_id, previous.as_deref(), metainfo.as_deref());
+        if let Some(metainfo) = metainfo {
+            index.add_metainfo(metainfo);
+        }
+        self.publish_index(index)?;
+        if let Some(event) = event {
+            self.events.publish(event);
+        }
         Ok(())
     }
 
-    /// Refresh the index by reloading from the filesystem.
+    /// Refresh the index by reloading from the filesystem. Concurrent refreshes are
+    /// coalesced into one rescan, at most one runs at a time, and none starts sooner
+    /// than `min_refresh_interval_seconds` after the previous one.
     pub fn refresh_index(&self) -> Result<()> {
-        self.load_index()
+        let min_interval = self.config.load().mainconfig.min_refresh_interval_seconds;
+        let min_interval = Duration::from_secs(min_interval.unwrap_or(0));
+        self.refreshes.refresh(min_interval, || self.load_index())
+    }
+
+    /// The revision of the content currently served (e.g. a commit hash), if the store is versioned.
+    pub fn revision(&self) -> Option<String> {
+        self.store.revision()
     }
 
     /// Clear the LRU cache entirely.
@@ -464,41 +2706,273 @@ impl Articles {
         self.cache.clear();
     }
 
-    /// Attempt to retrieve an article by ID. Returns `(Article, CachedStatus)`.
+    /// How full the article cache is.
+    pub fn cache_occupancy(&self) -> CacheOccupancy {
+        self.cache.occupancy()
+    }
+
+    /// How many article lookups were answered from the cache and how many weren't,
+    /// counted while `record_cache_stats` is enabled.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats.stats()
+    }
+
+    /// Start counting cache hits and misses from zero.
+    pub fn reset_cache_stats(&self) {
+        self.cache.stats.reset();
+    }
+
+    /// The comments readers left, if comments are enabled.
+    pub fn comments(&self) -> Option<&Arc<Comments>> {
+        self.comments.as_ref()
+    }
+
+    /// The configuration in effect.
+    pub fn config(&self) -> Arc<config::Config> {
+        self.config.load_full()
+    }
+
+    /// Apply a reloaded configuration: resize the LRU cache to `max_cached_articles`
+    /// and empty it, since cached articles were rendered with the previous settings,
+    /// and render the pre-rendered articles again.
+    pub fn apply_config(&self) {
+        let config = self.config.load();
+        self.cache.reset(config.mainconfig.max_cached_articles);
+        let index = self.index.load();
+        index.searches.lock().unwrap().clear();
+        index.prerendered.clear();
+        if config.mainconfig.prerender {
+            self.prerender(&index);
+        }
+        self.pin_articles(&index);
+    }
+
+    /// Attempt to retrieve an article by ID, in the configured format. Returns
+    /// `(Article, CachedStatus)`.
     pub fn get_article(&self, article_id: ArticleId) -> Result<(Article, CachedStatus)> {
+        let format = ContentFormat::configured(&self.config.load().mainconfig);
+        self.get_article_as(article_id, format)
+    }
+
+    /// Attempt to retrieve an article by ID with its content in `format`. Returns
+    /// `(Article, CachedStatus)`, which is also counted in [`Articles::cache_stats`].
+    pub fn get_article_as(
+        &self,
+        article_id: ArticleId,
+        format: ContentFormat,
+    ) -> Result<(Article, CachedStatus)> {
+        let (article, status) = self.lookup_article(article_id, format)?;
+        self.cache.record(status);
+        Ok((article, status))
+    }
+
+    /// Find an article in the pre-rendered articles or the cache, loading it from
+    /// the store if it's in neither.
+    fn lookup_article(
+        &self,
+        article_id: ArticleId,
+        format: ContentFormat,
+    ) -> Result<(Article, CachedStatus)> {
         // If the user requested sample article #0, provide that if configured.
-        if article_id == 0 && config::CONFIG.mainconfig.sample_article {
-            return Ok((SAMPLE_ARTICLE.clone(), CachedStatus::NotCached));
+        if article_id == 0
+            && let Some(sample) = &self.sample
+        {
+            return Ok((Self::sample_as(sample, format), CachedStatus::NotCached));
         }
 
-        // Check the cache first
-        if let Some(article) = self.cache.get(article_id) {
+        // Pre-rendered articles are served straight from the index, unless their
+        // source changed since
+        let prerendered = format == ContentFormat::configured(&self.config.load().mainconfig);
+        if prerendered
+            && let Some((article, version)) = self.loaded_index().get_prerendered(article_id)
+            && self.unchanged(article_id, version.as_ref())
+        {
             return Ok((article, CachedStatus::Cached));
         }
 
-        // Not in cache, so load from filesystem
-        let article = self.load_article_from_filesystem(article_id)?;
-        self.cache.put(article_id, article.clone());
+        // Check the cache first
+        if let Some((article, version)) = self.cache.get(article_id, format) {
+            if self.unchanged(article_id, version.as_ref()) {
+                return Ok((article, CachedStatus::Cached));
+            }
+            self.cache.remove(article_id);
+        }
+
+        // Not in cache, so load from filesystem; concurrent misses share a single load
+        let article = self.loads.load(article_id, format, || {
+            let cached_source = self
+                .cache
+                .get(article_id, ContentFormat::Markdown)
+                .filter(|(_, version)| self.unchanged(article_id, version.as_ref()));
+            let (article, version) = match (format, cached_source) {
+                // Rendering the cached source spares reading it again
+                (ContentFormat::Html, Some((source, version))) => {
+                    let content = self.render_cache.render(&source.content, &self.config.load());
+                    (Article { content: content.into(), ..source }, version)
+                }
+                _ => self.load_article_from_store(article_id, format)?,
+            };
+            let index = self.index.load();
+            if prerendered && let Some(mut stale) = index.prerendered.get_mut(&article_id) {
+                *stale = (article.clone(), version.clone());
+            }
+            let pinned = self.is_pinned(&index, article_id);
+            self.cache.put(article_id, article.clone(), format, version, pinned);
+            Ok(article)
+        })?;
         Ok((article, CachedStatus::NotCached))
     }
 
-    /// Helper function to load a single article from disk, converting its Markdown to HTML.
-    fn load_article_from_filesystem(&self, article_id: ArticleId) -> Result<Article> {
-        let metainfo = self.index.get_metainfo(article_id)
-            .ok_or_else(|| anyhow!("Article with ID {} not found", article_id))?;
+    /// The sample article with its content in `format`.
+    fn sample_as(sample: &Article, format: ContentFormat) -> Article {
+        match format {
+            ContentFormat::Markdown => Article {
+                content: SAMPLE_MARKDOWN.into(),
+                ..sample.clone()
+            },
+            ContentFormat::Html => sample.clone(),
+        }
+    }
+
+    /// Helper function to load a single article from the store, converting its Markdown to HTML.
+    /// Returns the version of its source along with it.
+    fn load_article_from_store(
+        &self,
+        article_id: ArticleId,
+        format: ContentFormat,
+    ) -> Result<(Article, Option<SourceVersion>)> {
+        let index = self.loaded_index();
+        let metainfo = index.get_metainfo(article_id)
+            .ok_or_else(|| index.missing(article_id))?;
 
         // If the user requested sample article #0, provide that if configured
-        if article_id == 0 && config::CONFIG.mainconfig.sample_article {
-            return Ok(SAMPLE_ARTICLE.clone());
+        if article_id == 0
+            && let Some(sample) = &self.sample
+        {
+            return Ok((Self::sample_as(sample, format), None));
+        }
+        self.build_article(&metainfo, format)
+    }
+
+    /// Load the article described by `metainfo` from the store, converting its
+    /// Markdown to HTML for the `Html` format. Returns the version of its source
+    /// along with it, asked before reading, so that a change made while it's read
+    /// shows at the next check.
+    fn build_article(
+        &self,
+        metainfo: &Metainfo,
+        format: ContentFormat,
+    ) -> Result<(Article, Option<SourceVersion>)> {
+        let config = self.config.load();
+        let version = self.source_version(metainfo).ok().flatten();
+        let markdown_content = self.store.load(metainfo)?;
+        let content = match format {
+            ContentFormat::Html => self.render_cache.render(&markdown_content, &config).into(),
+            ContentFormat::Markdown => markdown_content.into(),
+        };
+
+        let article = Article {
+            id: metainfo.id,
+            title: Arc::clone(&metainfo.title),
+            description: Arc::clone(&metainfo.description),
+            content,
+            date: metainfo.date,
+            tags: Arc::clone(&metainfo.tags),
+            keywords: Arc::clone(&metainfo.keywords),
+            canonical_url: metainfo
+                .canonical_url(&config.mainconfig)
+                .map(Arc::from),
+            license: metainfo.license.clone(),
+            source: metainfo.source.clone(),
+            attribution: metainfo.attribution.clone(),
+            extra: Arc::clone(&metainfo.extra),
+            reactions: ReactionCounts::new(),
+        };
+        Ok((article, version))
+    }
+
+    /// The current version of the source of the article described by `metainfo`:
+    /// its modification time, or else its fingerprint, if the store tracks either.
+    fn source_version(&self, metainfo: &Metainfo) -> Result<Option<SourceVersion>> {
+        if let Some(modified) = self.store.mtime(metainfo)? {
+            return Ok(Some(SourceVersion::Modified(modified)));
         }
+        let fingerprint = self.store.fingerprint(metainfo)?;
+        Ok(fingerprint.map(|fingerprint| SourceVersion::Fingerprint(fingerprint.into())))
+    }
+
+    /// Whether an article read from `version` of its source can stand in for
+    /// reading it again: the store tracks versions and the source wasn't changed
+    /// since.
+    fn still_current(&self, metainfo: &Metainfo, version: Option<&SourceVersion>) -> bool {
+        version.is_some() && self.source_version(metainfo).ok().flatten().as_ref() == version
+    }
 
-        self.storage.load_article(&metainfo)
+    /// Whether the source of a cached or pre-rendered article, at `version` when
+    /// it was read, is still the same, or isn't checked as `revalidate_cache` is
+    /// off. Articles whose store tracks neither modification times nor
+    /// fingerprints are taken as unchanged; one that can't be checked any more,
+    /// e.g. as its file was removed, is not. Fingerprints are checked at most once
+    /// every `revalidate_interval_seconds` per article, as that takes a request.
+    fn unchanged(&self, article_id: ArticleId, version: Option<&SourceVersion>) -> bool {
+        let Some(version) = version else {
+            return true;
+        };
+        let config = self.config.load();
+        if !config.mainconfig.revalidate_cache {
+            return true;
+        }
+        if let SourceVersion::Fingerprint(_) = version {
+            let interval = Duration::from_secs(config.mainconfig.revalidate_interval_seconds);
+            if let Some(checked) = self.revalidated.get(&article_id)
+                && checked.elapsed() < interval
+            {
+                return true;
+            }
+            self.revalidated.insert(article_id, Instant::now());
+        }
+        let index = self.index.load();
+        let Some(metainfo) = index.get_metainfo(article_id) else {
+            return true;
+        };
+        match self.source_version(&metainfo) {
+            Ok(current) => current.is_none_or(|current| &current == version),
+            Err(_) => false,
+        }
     }
 
     /// Force a refresh of a single article from the filesystem, updating the cache.
+    /// An article whose source wasn't modified since it was pre-rendered or cached
+    /// is returned as it is, without reading and rendering it again.
     pub fn refresh_article(&self, article_id: ArticleId) -> Result<Article> {
-        let article = self.load_article_from_filesystem(article_id)?;
-        self.cache.put(article_id, article.clone());
+        let config = self.config.load();
+        let format = ContentFormat::configured(&config.mainconfig);
+        let index = self.loaded_index();
+        if let Some(metainfo) = index.get_metainfo(article_id) {
+            let current = match index.get_prerendered(article_id) {
+                Some(prerendered) => Some(prerendered),
+                None => self.cache.get(article_id, format),
+            };
+            if let Some((article, version)) = current
+                && self.still_current(&metainfo, version.as_ref())
+            {
+                return Ok(article);
+            }
+        }
+        drop(index);
+        let (article, version) = self.load_article_from_store(article_id, format)?;
+        if config.mainconfig.prerender {
+            self.index.load().prerendered.insert(article_id, (article.clone(), version.clone()));
+        }
+        // The other format may be stale, so it's dropped rather than kept alongside
+        self.cache.remove(article_id);
+        let pinned = self.is_pinned(&self.index.load(), article_id);
+        self.cache.put(article_id, article.clone(), format, version, pinned);
+        let metainfo = self.index.load().get_metainfo(article_id);
+        let metainfo = metainfo.as_deref();
+        if let Some(event) = Self::article_event(article_id, metainfo, metainfo) {
+            self.events.publish(event);
+        }
         Ok(article)
     }
 
@@ -511,26 +2985,195 @@ impl Articles {
             date: m.date,
             tags: Arc::clone(&m.tags),
             keywords: Arc::clone(&m.keywords),
+            cover: m.cover_url(&self.config.load().mainconfig).map(Arc::from),
+            extra: Arc::clone(&m.extra),
+            comments: self.comments.as_ref().map(|comments| comments.count(m.id)),
         }
     }
 
     /// Grab article summaries (by looking up `by_id` index) for the given list of IDs.
-    fn get_summaries_from_ids(&self, ids: &[ArticleId]) -> Vec<ArticleSummary> {
+    fn get_summaries_from_ids(&self, index: &ArticleIndex, ids: &[ArticleId]) -> Vec<ArticleSummary> {
         let mut results = Vec::with_capacity(ids.len());
         for &id in ids {
-            if let Some(m) = self.index.get_metainfo(id) {
+            if let Some(m) = index.get_metainfo(id) {
                 results.push(self.build_summary(&m));
             }
         }
         results
     }
 
+    /// Run a query against the SQLite index if one is attached. Errors are logged and
+    /// yield `None`, so callers fall back to the in-memory index.
+    #[cfg(feature = "sqlite")]
+    fn query_sql_index<F>(&self, query: F) -> Option<Vec<ArticleId>>
+    where
+        F: FnOnce(&SqliteIndex) -> Result<Vec<ArticleId>>,
+    {
+        let sql_index = self.sql_index.as_ref()?;
+        match query(sql_index) {
+            Ok(ids) => Some(ids),
+            Err(e) => {
+                error!("SQLite index query failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// All article IDs, sorted.
+    fn all_ids(&self, index: &ArticleIndex) -> Arc<[ArticleId]> {
+        #[cfg(feature = "sqlite")]
+        if let Some(ids) = self.query_sql_index(|sql| sql.all_ids()) {
+            return ids.into();
+        }
+        index.get_all_ids()
+    }
+
+    /// IDs of the articles carrying `tag` as configured by `tag_matching`, sorted.
+    /// Inexact matches are answered from the in-memory index.
+    fn tag_ids(&self, index: &ArticleIndex, tag: &str) -> Arc<[ArticleId]> {
+        let matching = self.config.load().mainconfig.tag_matching;
+        #[cfg(feature = "sqlite")]
+        if matching == TagMatching::Exact
+            && let Some(ids) = self.query_sql_index(|sql| sql.ids_by_tag(tag))
+        {
+            return ids.into();
+        }
+        index.get_ids_by_tag(tag, matching)
+    }
+
+    /// The list of the articles carrying `tag`, as configured by `tag_matching`.
+    fn tag_list<'a>(&self, tag: &'a str) -> IdList<'a> {
+        IdList::Tag(tag, self.config.load().mainconfig.tag_matching)
+    }
+
+    /// IDs of the articles carrying `keyword`, sorted.
+    fn keyword_ids(&self, index: &ArticleIndex, keyword: &str) -> Arc<[ArticleId]> {
+        #[cfg(feature = "sqlite")]
+        if let Some(ids) = self.query_sql_index(|sql| sql.ids_by_keyword(keyword)) {
+            return ids.into();
+        }
+        index.get_ids_by_keyword(keyword)
+    }
+
+    /// IDs of the articles in `list`, in the configured `listing_order`. Date
+    /// orders are answered from the in-memory index.
+    fn listed_ids(&self, index: &ArticleIndex, list: IdList) -> Arc<[ArticleId]> {
+        match (self.config.load().mainconfig.listing_order, list) {
+            (DefaultOrder::Id, IdList::All) => self.all_ids(index),
+            (DefaultOrder::Id, IdList::Tag(tag, _)) => self.tag_ids(index, tag),
+            (DefaultOrder::Id, IdList::Keyword(keyword)) => self.keyword_ids(index, keyword),
+            (order, list) => index.get_ordered_ids(list, order),
+        }
+    }
+
+    /// Keep only the `ids` of articles carrying every one of `tags`.
+    fn retain_tagged(&self, index: &ArticleIndex, ids: &mut Vec<ArticleId>, tags: &[String]) {
+        for tag in tags {
+            let tagged = self.tag_ids(index, tag).iter().copied().collect::<HashSet<_>>();
+            ids.retain(|id| tagged.contains(id));
+        }
+    }
+
+    /// IDs of the articles matching the search `query` in one of `fields`.
+    fn search_ids(&self, index: &ArticleIndex, query: &str, fields: SearchFields) -> Vec<ArticleId> {
+        // The SQLite index searches with FTS5 instead of substring matching
+        #[cfg(feature = "sqlite")]
+        if let Some(ids) = self.query_sql_index(|sql| sql.search_ids(query, fields)) {
+            return ids;
+        }
+
+        let matcher = Matcher::new(query, &self.config.load().search);
+        index
+            .get_all_ids()
+            .iter()
+            .copied()
+            .filter(|&id| index.get_metainfo(id).is_some_and(|m| fields.matches(&m, &matcher)))
+            .collect()
+    }
+
     // ===== PUBLIC API METHODS =====
 
-    /// Return a list of summaries for all articles (sorted by ID).
+    /// Resolve a requested article ID or alias: an existing article's ID resolves
+    /// to itself, a former ID or slug to the article that declares it as an alias,
+    /// and the ID of a deleted article to its tombstone. Returns `None` when none
+    /// of these match.
+    pub fn resolve(&self, requested: &str) -> Option<ArticleRef> {
+        let index = self.loaded_index();
+        let requested_id = requested.parse::<ArticleId>().ok();
+        if let Some(id) = requested_id.filter(|id| index.get_metainfo(*id).is_some()) {
+            return Some(ArticleRef::Id(id));
+        }
+        if let Some(target) = index.get_alias_target(requested) {
+            return Some(ArticleRef::Moved(target));
+        }
+        requested_id
+            .and_then(|id| index.get_tombstone(id))
+            .map(|tombstone| ArticleRef::Gone {
+                id: tombstone.id,
+                successor: tombstone.successor,
+            })
+    }
+
+    /// Return the indexed metadata of an article, without loading its content.
+    pub fn get_metainfo(&self, article_id: ArticleId) -> Result<Arc<Metainfo>> {
+        let index = self.loaded_index();
+        Ok(index
+            .get_metainfo(article_id)
+            .ok_or_else(|| index.missing(article_id))?)
+    }
+
+    /// Every article as a draft of its metadata and Markdown source, in ID order, the
+    /// way `POST /api/v1/articles` takes them. The sample article is left out.
+    pub fn export(&self) -> impl Iterator<Item = Result<ArticleDraft>> + '_ {
+        // Private articles are exported too, so they're read from the whole index
+        let mut metainfos = self
+            .loaded_index()
+            .all_metainfo()
+            .into_iter()
+            .filter(|m| !(m.id == 0 && self.sample.is_some()))
+            .collect::<Vec<_>>();
+        metainfos.sort_unstable_by_key(|m| m.id);
+        metainfos.into_iter().map(|m| {
+            Ok(ArticleDraft {
+                id: Some(m.id),
+                title: m.title.to_string(),
+                description: m.description.to_string(),
+                date: m.date,
+                tags: m.tags.to_vec(),
+                keywords: m.keywords.to_vec(),
+                cover: m.cover.as_deref().map(str::to_string),
+                author: m.author.as_deref().map(str::to_string),
+                canonical_url: m.canonical_url.as_deref().map(str::to_string),
+                aliases: m.aliases.to_vec(),
+                license: m.license.as_deref().map(str::to_string),
+                source: m.source.as_deref().map(str::to_string),
+                attribution: m.attribution.as_deref().map(str::to_string),
+                extra: m.extra.as_ref().clone(),
+                pinned: m.pinned,
+                access: (m.is_private() || m.token.is_some()).then_some(m.access),
+                token: m.token.as_deref().map(str::to_string),
+                markdown: self.store.load(&m)?,
+            })
+        })
+    }
+
+    /// Return the summary of an article from the index, without loading its content.
+    pub fn get_summary(&self, article_id: ArticleId) -> Result<ArticleSummary> {
+        let metainfo = self.get_metainfo(article_id)?;
+        Ok(self.build_summary(&metainfo))
+    }
+
+    /// Return a list of summaries for all articles, in the configured `listing_order`.
     pub fn list_article_summaries(&self) -> Result<Vec<ArticleSummary>> {
-        let ids = self.index.get_all_ids();
-        Ok(self.get_summaries_from_ids(&ids))
+        let index = self.loaded_index();
+        let ids = self.listed_ids(&index, IdList::All);
+        Ok(self.get_summaries_from_ids(&index, &ids))
+    }
+
+    /// The number of the first page of a paginated listing: 1 with `one_based_pages`,
+    /// and 0 otherwise.
+    pub fn first_page(&self) -> usize {
+        self.config.load().mainconfig.first_page()
     }
 
     /// Return a paginated list of summaries for all articles.
@@ -539,24 +3182,170 @@ impl Articles {
         max_per_page: usize,
         page_number: usize,
     ) -> Result<Vec<ArticleSummary>> {
-        let ids = self.index.get_all_ids();
-        let page_slice = match Paginator::paginate(&ids, max_per_page, page_number)? {
-            Some(range) => range,
-            None => return Ok(vec![]),
+        let index = self.loaded_index();
+        let ids = self.listed_ids(&index, IdList::All);
+        let page_slice =
+            match Paginator::paginate(&ids, max_per_page, page_number, self.first_page())? {
+                Some(range) => range,
+                None => return Ok(vec![]),
+            };
+        Ok(self.get_summaries_from_ids(&index, page_slice))
+    }
+
+    /// Return a page of up to `limit` summaries of the articles matching `filter`
+    /// in `order`, starting after or ending before `position`.
+    pub fn list_article_summaries_page(
+        &self,
+        filter: &ListingFilter,
+        order: ListingOrder,
+        position: Option<ListingPosition>,
+        limit: usize,
+    ) -> ListingPage {
+        let index = self.loaded_index();
+        let (ids, other_tags) = match filter.tags.split_first() {
+            Some((tag, rest)) => (self.tag_ids(&index, tag), rest),
+            None => (self.all_ids(&index), &[][..]),
+        };
+        let mut tagged = other_tags
+            .iter()
+            .map(|tag| self.tag_ids(&index, tag).iter().copied().collect::<HashSet<_>>())
+            .collect::<Vec<_>>();
+        if let Some(keyword) = &filter.keyword {
+            tagged.push(self.keyword_ids(&index, keyword).iter().copied().collect());
+        }
+        let found = filter.query.as_ref().map(|query| {
+            self.search_ids(&index, query, filter.search_fields)
+                .into_iter()
+                .collect::<HashSet<_>>()
+        });
+        let mut keyed = ids
+            .iter()
+            .copied()
+            .filter(|id| tagged.iter().all(|tagged| tagged.contains(id)))
+            .filter(|id| found.as_ref().is_none_or(|found| found.contains(id)))
+            .filter_map(|id| index.get_metainfo(id))
+            .filter(|m| filter.from.is_none_or(|from| m.date >= from))
+            .filter(|m| filter.to.is_none_or(|to| m.date <= to))
+            .map(|m| (ListingKey { date: m.date, id: m.id }, m))
+            .collect::<Vec<_>>();
+        match order {
+            ListingOrder::Newest => keyed.sort_unstable_by_key(|(key, _)| std::cmp::Reverse(*key)),
+            ListingOrder::Oldest => keyed.sort_unstable_by_key(|(key, _)| *key),
+        }
+
+        // The window of the listing that makes up the page
+        let (start, end) = match position {
+            None => (0, limit.min(keyed.len())),
+            Some(ListingPosition::After(after)) => {
+                let start = keyed.partition_point(|(key, _)| !order.precedes(&after, key));
+                (start, (start + limit).min(keyed.len()))
+            }
+            Some(ListingPosition::Before(before)) => {
+                let end = keyed.partition_point(|(key, _)| order.precedes(key, &before));
+                (end.saturating_sub(limit), end)
+            }
+        };
+        let window = &keyed[start..end];
+        ListingPage {
+            items: window.iter().map(|(_, m)| self.build_summary(m)).collect(),
+            total: keyed.len(),
+            next: window.last().filter(|_| end < keyed.len()).map(|(key, _)| *key),
+            prev: window.first().filter(|_| start > 0).map(|(key, _)| *key),
+        }
+    }
+
+    /// IDs of up to `limit` articles related to `article_id`: those sharing the most
+    /// tags with it, newest first among equals.
+    pub fn related_article_ids(&self, article_id: ArticleId, limit: usize) -> Vec<ArticleId> {
+        let index = self.loaded_index();
+        let Some(metainfo) = index.get_metainfo(article_id) else {
+            return vec![];
         };
-        Ok(self.get_summaries_from_ids(page_slice))
+        let mut shared_tags: HashMap<ArticleId, usize> = HashMap::new();
+        for tag in metainfo.tags.iter() {
+            for &id in self.tag_ids(&index, tag).iter() {
+                if id != article_id {
+                    *shared_tags.entry(id).or_default() += 1;
+                }
+            }
+        }
+
+        let mut related = shared_tags
+            .into_iter()
+            .filter_map(|(id, shared)| index.get_metainfo(id).map(|m| (shared, m.date, id)))
+            .collect::<Vec<_>>();
+        related.sort_unstable_by(|a, b| b.cmp(a));
+        related.into_iter().take(limit).map(|(_, _, id)| id).collect()
+    }
+
+    #[cfg(feature = "graphql")]
+    /// Return the summaries of those of `ids` that exist and aren't private, in order.
+    pub fn summaries_of(&self, ids: &[ArticleId]) -> Vec<ArticleSummary> {
+        let ids = ids.iter().copied().filter(|&id| !self.is_private(id)).collect::<Vec<_>>();
+        self.get_summaries_from_ids(&self.loaded_index(), &ids)
+    }
+
+    /// Whether the article `article_id` exists and is private.
+    pub fn is_private(&self, article_id: ArticleId) -> bool {
+        self.loaded_index().get_metainfo(article_id).is_some_and(|m| m.is_private())
+    }
+
+    /// Return every tag with the number of articles carrying it, sorted by tag.
+    pub fn list_tags(&self) -> Vec<(String, usize)> {
+        self.loaded_index().tag_counts()
+    }
+
+    /// Return the number of listed articles in the index, leaving out the private
+    /// ones like the listings do.
+    pub fn article_count(&self) -> usize {
+        self.loaded_index().listed_count()
+    }
+
+    /// Return totals, per-tag and per-year article counts and word counts of the
+    /// listed articles. Every article's Markdown is read the first time this is
+    /// called after the index changed.
+    pub fn content_stats(&self) -> Arc<ContentStats> {
+        let index = self.loaded_index();
+        let stats = index.content_stats.get_or_init(|| {
+            let mut stats = ContentStats::default();
+            for metainfo in index.listed_metainfo() {
+                if metainfo.id == 0 && self.sample.is_some() {
+                    stats.add(&metainfo, Some(SAMPLE_MARKDOWN));
+                    continue;
+                }
+                match self.store.load(&metainfo) {
+                    Ok(markdown) => stats.add(&metainfo, Some(&markdown)),
+                    Err(e) => {
+                        warn!("Failed to read article {} for statistics: {}", metainfo.id, e);
+                        stats.add(&metainfo, None);
+                    }
+                }
+            }
+            Arc::new(stats.finish())
+        });
+        Arc::clone(stats)
+    }
+
+    /// Return the number of distinct tags in the index.
+    pub fn tag_count(&self) -> usize {
+        self.loaded_index().tag_count()
+    }
+
+    /// Return the number of articles listed.
+    pub fn get_article_summary_count(&self) -> usize {
+        self.all_ids(&self.loaded_index()).len()
     }
 
     /// Return the number of pages needed given `max_per_page` for *all* articles.
     pub fn get_article_summary_page_count(&self, max_per_page: usize) -> usize {
-        let ids = self.index.get_all_ids();
-        Paginator::compute_total_pages(ids.len(), max_per_page)
+        Paginator::compute_total_pages(self.get_article_summary_count(), max_per_page)
     }
 
-    /// Return all article summaries for a given tag (sorted by ID).
+    /// Return all article summaries for a given tag, in the configured `listing_order`.
     pub fn list_article_summaries_by_tag(&self, tag: &str) -> Result<Vec<ArticleSummary>> {
-        let article_ids = self.index.get_ids_by_tag(tag);
-        Ok(self.get_summaries_from_ids(&article_ids))
+        let index = self.loaded_index();
+        let article_ids = self.listed_ids(&index, self.tag_list(tag));
+        Ok(self.get_summaries_from_ids(&index, &article_ids))
     }
 
     /// Return a paginated list of summaries for a given tag.
@@ -566,57 +3355,159 @@ impl Articles {
         max_per_page: usize,
         page_number: usize,
     ) -> Result<Vec<ArticleSummary>> {
-        let article_ids = self.index.get_ids_by_tag(tag);
-        let page_slice = match Paginator::paginate(&article_ids, max_per_page, page_number)? {
+        let index = self.loaded_index();
+        let article_ids = self.listed_ids(&index, self.tag_list(tag));
+        let page_slice = match Paginator::paginate(
+            &article_ids,
+            max_per_page,
+            page_number,
+            self.first_page(),
+        )? {
             Some(range) => range,
             None => return Ok(vec![]),
         };
-        Ok(self.get_summaries_from_ids(page_slice))
+        Ok(self.get_summaries_from_ids(&index, page_slice))
+    }
+
+    /// Return the number of articles of a given tag.
+    pub fn get_article_summary_by_tag_count(&self, tag: &str) -> usize {
+        self.tag_ids(&self.loaded_index(), tag).len()
     }
 
     /// Return the number of pages needed for articles of a given tag.
     pub fn get_article_summary_by_tag_page_count(&self, tag: &str, max_per_page: usize) -> usize {
-        let article_ids = self.index.get_ids_by_tag(tag);
-        Paginator::compute_total_pages(article_ids.len(), max_per_page)
+        Paginator::compute_total_pages(self.get_article_summary_by_tag_count(tag), max_per_page)
     }
 
-    /// Search articles by `query` in their title or description, returning all matches sorted by ID.
-    pub fn search_articles(&self, query: &str) -> Result<Vec<ArticleSummary>> {
-        let ids = self.index.get_all_ids();
-        let mut results = Vec::new();
-        
-        for &id in &ids {
-            if let Some(m) = self.index.get_metainfo(id) {
-                // Simple substring match
-                if m.title.contains(query) || m.description.contains(query) {
-                    results.push(self.build_summary(&m));
-                }
-            }
+    /// Return all article summaries for a given keyword, in the configured `listing_order`.
+    pub fn list_article_summaries_by_keyword(&self, keyword: &str) -> Result<Vec<ArticleSummary>> {
+        let index = self.loaded_index();
+        let article_ids = self.listed_ids(&index, IdList::Keyword(keyword));
+        Ok(self.get_summaries_from_ids(&index, &article_ids))
+    }
+
+    /// Return a paginated list of summaries for a given keyword.
+    pub fn list_article_summaries_by_keyword_paginated(
+        &self,
+        keyword: &str,
+        max_per_page: usize,
+        page_number: usize,
+    ) -> Result<Vec<ArticleSummary>> {
+        let index = self.loaded_index();
+        let article_ids = self.listed_ids(&index, IdList::Keyword(keyword));
+        let page_slice = match Paginator::paginate(
+            &article_ids,
+            max_per_page,
+            page_number,
+            self.first_page(),
+        )? {
+            Some(range) => range,
+            None => return Ok(vec![]),
+        };
+        Ok(self.get_summaries_from_ids(&index, page_slice))
+    }
+
+    /// Return the number of articles with a specific keyword.
+    pub fn get_article_summary_by_keyword_count(&self, keyword: &str) -> usize {
+        self.keyword_ids(&self.loaded_index(), keyword).len()
+    }
+
+    /// Return the number of pages for articles with a specific keyword.
+    pub fn get_article_summary_by_keyword_page_count(&self, keyword: &str, max_per_page: usize) -> usize {
+        Paginator::compute_total_pages(self.get_article_summary_by_keyword_count(keyword), max_per_page)
+    }
+
+    /// Search articles by `query` in their title, description, tags or keywords, as
+    /// selected by `fields`, among the articles carrying all of `tags`. Returns all
+    /// matches best first: by relevance, boosted for newer articles by the configured
+    /// `recency_decay`, then by ID.
+    pub fn search_articles(
+        &self,
+        query: &str,
+        fields: SearchFields,
+        tags: &[String],
+    ) -> Result<Vec<ArticleSummary>> {
+        let index = self.loaded_index();
+        let ids = self.ranked_search_ids(&index, query, fields, tags);
+        Ok(self.get_summaries_from_ids(&index, &ids))
+    }
+
+    /// The IDs of the search results, best first. Recent searches are answered from
+    /// the index's results cache, so paging through results runs the search once.
+    fn ranked_search_ids(
+        &self,
+        index: &ArticleIndex,
+        query: &str,
+        fields: SearchFields,
+        tags: &[String],
+    ) -> Arc<[ArticleId]> {
+        let key = SearchKey::new(query, fields, tags);
+        if let Some(ids) = index.get_search(&key) {
+            return ids;
         }
-        
-        Ok(results)
+        let mut ids = self.search_ids(index, query, fields);
+        self.retain_tagged(index, &mut ids, tags);
+        let ids: Arc<[ArticleId]> = self.rank_search_results(index, ids, query, fields).into();
+        index.put_search(key, Arc::clone(&ids));
+        ids
+    }
+
+    /// Order the `ids` found for `query` by score, highest first; equal scores keep
+    /// their order.
+    fn rank_search_results(
+        &self,
+        index: &ArticleIndex,
+        ids: Vec<ArticleId>,
+        query: &str,
+        fields: SearchFields,
+    ) -> Vec<ArticleId> {
+        let config = self.config.load();
+        let matcher = Matcher::new(query, &config.search);
+        let today = search::today();
+        let mut scored = ids
+            .into_iter()
+            .filter_map(|id| index.get_metainfo(id))
+            .map(|m| {
+                let boost = search::recency_boost(m.date, today, config.search.recency_decay);
+                (fields.relevance(&m, &matcher) * boost, m.id)
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        scored.into_iter().map(|(_, id)| id).collect()
     }
 
     /// Return a paginated list of search results for `query`.
     pub fn search_articles_paginated(
         &self,
         query: &str,
+        fields: SearchFields,
+        tags: &[String],
         max_per_page: usize,
         page_number: usize,
     ) -> Result<Vec<ArticleSummary>> {
-        let results = self.search_articles(query)?;
-        let page_slice = match Paginator::paginate(&results, max_per_page, page_number)? {
-            Some(range) => range,
-            None => return Ok(vec![]),
-        };
-        Ok(page_slice.to_vec())
+        let index = self.loaded_index();
+        let ids = self.ranked_search_ids(&index, query, fields, tags);
+        let page_slice =
+            match Paginator::paginate(&ids, max_per_page, page_number, self.first_page())? {
+                Some(range) => range,
+                None => return Ok(vec![]),
+            };
+        Ok(self.get_summaries_from_ids(&index, page_slice))
+    }
+
+    /// Return the number of search results for `query`.
+    pub fn get_search_article_count(&self, query: &str, fields: SearchFields, tags: &[String]) -> usize {
+        self.ranked_search_ids(&self.loaded_index(), query, fields, tags).len()
     }
 
     /// Return the total number of pages for a search result.
-    pub fn get_search_article_page_count(&self, query: &str, max_per_page: usize) -> usize {
-        match self.search_articles(query) {
-            Ok(results) => Paginator::compute_total_pages(results.len(), max_per_page),
-            Err(_) => 0,
-        }
+    pub fn get_search_article_page_count(
+        &self,
+        query: &str,
+        fields: SearchFields,
+        tags: &[String],
+        max_per_page: usize,
+    ) -> usize {
+        Paginator::compute_total_pages(self.get_search_article_count(query, fields, tags), max_per_page)
     }
 }
@@ -0,0 +1,150 @@
+use actix_web::{
+    dev::Payload,
+    web::{Data, Query},
+    FromRequest, HttpRequest,
+};
+use serde::Deserialize;
+use std::{
+    future::{ready, Ready},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use crate::{
+    articles::{ArticleId, Articles},
+    config::{Config, Scope},
+    error::Error,
+};
+
+/// The scope a group of routes requires of the API key guarding it.
+pub trait RequiredScope {
+    const SCOPE: Scope;
+}
+
+/// Routes reading what isn't public: the export and private articles.
+pub struct Read;
+
+/// Routes writing articles.
+pub struct Author;
+
+/// Routes operating the server: the configuration and its reloads, backups,
+/// refreshes, cache and statistics resets.
+pub struct Admin;
+
+impl RequiredScope for Read {
+    const SCOPE: Scope = Scope::Read;
+}
+
+impl RequiredScope for Author {
+    const SCOPE: Scope = Scope::Author;
+}
+
+impl RequiredScope for Admin {
+    const SCOPE: Scope = Scope::Admin;
+}
+
+/// Extractor guarding the endpoints that modify content or expose the server's
+/// internals: it only succeeds for requests carrying `Authorization: Bearer <key>`
+/// with one of the configured `[auth] api_keys` granting the scope of `S`. A valid
+/// key without that scope fails with [`Error::Forbidden`].
+pub struct ApiKey<S>(PhantomData<S>);
+
+impl<S: RequiredScope> FromRequest for ApiKey<S> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let result = match (bearer_token(req), served_config(req)) {
+            (Some(token), Some(config)) => check_key(&config, token, S::SCOPE),
+            _ => Err(missing_key()),
+        };
+        ready(result.map(|()| ApiKey(PhantomData)))
+    }
+}
+
+/// Extractor guarding the routine operations (refreshing the index and articles,
+/// clearing the cache, resetting statistics): open to everyone until API keys are
+/// configured, then only to keys with the `admin` scope.
+pub struct Operator;
+
+impl FromRequest for Operator {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let result = match (bearer_token(req), served_config(req)) {
+            (_, Some(config)) if config.auth.api_keys.is_empty() => Ok(()),
+            (Some(token), Some(config)) => check_key(&config, token, Scope::Admin),
+            _ => Err(missing_key()),
+        };
+        ready(result.map(|()| Operator))
+    }
+}
+
+#[derive(Deserialize)]
+struct AccessParams {
+    token: Option<String>,
+}
+
+/// Check that `req` may read the article `article_id`. Anyone may read a public
+/// article, while a private one takes its `token` query parameter or an API key
+/// with the `read` scope; without either it's reported as not found, so that
+/// its existence isn't disclosed.
+pub fn check_access(
+    req: &HttpRequest,
+    articles: &Articles,
+    article_id: ArticleId,
+) -> Result<(), Error> {
+    let Some(metainfo) = articles.get_metainfo(article_id).ok().filter(|m| m.is_private()) else {
+        return Ok(());
+    };
+    let token = Query::<AccessParams>::from_query(req.query_string())
+        .ok()
+        .and_then(|params| params.into_inner().token);
+    let shared = match (&metainfo.token, token) {
+        (Some(expected), Some(token)) => constant_time_eq(expected.as_bytes(), token.as_bytes()),
+        _ => false,
+    };
+    let authenticated = bearer_token(req)
+        .is_some_and(|token| check_key(&articles.config(), token, Scope::Read).is_ok());
+    if shared || authenticated {
+        Ok(())
+    } else {
+        Err(Error::NotFound(article_id))
+    }
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// The keys are those of the configuration the served articles were set up with.
+fn served_config(req: &HttpRequest) -> Option<Arc<Config>> {
+    req.app_data::<Data<Articles>>().map(|articles| articles.config())
+}
+
+fn missing_key() -> Error {
+    Error::Unauthorized("Missing or invalid API key".into())
+}
+
+/// Check `token` against every configured key without short-circuiting on the
+/// first differing byte, then whether the matching key grants `scope`.
+fn check_key(config: &Config, token: &str, scope: Scope) -> Result<(), Error> {
+    let (valid, allowed) = config.auth.api_keys.iter().fold((false, false), |(valid, allowed), key| {
+        let matches = constant_time_eq(key.key().as_bytes(), token.as_bytes());
+        (valid | matches, allowed | (matches & key.allows(scope)))
+    });
+    match (valid, allowed) {
+        (_, true) => Ok(()),
+        (true, false) => Err(Error::Forbidden(format!("The API key lacks the '{}' scope", scope))),
+        (false, false) => Err(missing_key()),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,71 @@
+//! Backup archives of the content kept on disk: every articles directory, and
+//! optionally the render cache, as one gzip-compressed tar archive.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use log::*;
+
+use crate::{config::Config, error::Error};
+
+/// The directories a backup holds, each with the path it is stored under in the
+/// archive: `articles/` for the main articles, `sections/{name}/` for each section,
+/// numbered (`articles/0/`, `articles/1/`, ...) when there are several, and
+/// `render-cache/`. Fails when the articles aren't kept on disk.
+pub fn sources(config: &Config, render_cache: bool) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+    let main_dirs = match config.storage.backend.as_str() {
+        "fs" => config.mainconfig.source_dirs(),
+        "git" => config
+            .storage
+            .git
+            .iter()
+            .map(|git| Path::new(&git.checkout_dir).join(&git.subdir))
+            .collect(),
+        other => {
+            return Err(Error::InvalidRequest(format!(
+                "the '{}' storage backend keeps no articles on disk to back up",
+                other
+            )));
+        }
+    };
+
+    let mut sources = Vec::new();
+    add_dirs(&mut sources, Path::new("articles"), main_dirs);
+    for (name, section) in &config.sections {
+        add_dirs(&mut sources, &Path::new("sections").join(name), section.source_dirs());
+    }
+    if render_cache {
+        let Some(dir) = &config.mainconfig.render_cache_dir else {
+            return Err(Error::InvalidRequest("no render_cache_dir is configured".into()));
+        };
+        sources.push((PathBuf::from(dir), PathBuf::from("render-cache")));
+    }
+    Ok(sources)
+}
+
+fn add_dirs(sources: &mut Vec<(PathBuf, PathBuf)>, prefix: &Path, dirs: Vec<PathBuf>) {
+    if dirs.len() == 1 {
+        sources.extend(dirs.into_iter().map(|dir| (dir, prefix.to_path_buf())));
+    } else {
+        sources.extend(dirs.into_iter().enumerate().map(|(i, dir)| (dir, prefix.join(i.to_string()))));
+    }
+}
+
+/// Write a tar.gz archive of `sources` to `out`, returning it once the archive is
+/// complete. Symlinks are stored as links rather than followed, and directories
+/// that don't exist are left out.
+pub fn write_archive<W: Write>(sources: &[(PathBuf, PathBuf)], out: W) -> std::io::Result<W> {
+    let mut archive = tar::Builder::new(GzEncoder::new(out, Compression::default()));
+    archive.follow_symlinks(false);
+    for (dir, name) in sources {
+        if !dir.is_dir() {
+            warn!("{:?} is not a directory, left out of the backup", dir);
+            continue;
+        }
+        archive.append_dir_all(name, dir)?;
+    }
+    archive.into_inner()?.finish()
+}
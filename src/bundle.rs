@@ -0,0 +1,163 @@
+use crate::articles::{ArticleId, Metainfo};
+use crate::error::Error;
+#[cfg(feature = "uploads")]
+use std::io::{Cursor, Read};
+use std::{
+    collections::HashSet,
+    path::{Component, Path, PathBuf},
+};
+
+/// A file of an article bundle, with its path relative to the article directory.
+pub struct BundleFile {
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+/// Check that `files` form a complete article: safe relative paths, a valid
+/// `metainfo.toml` and the Markdown file it names, within `max_article_bytes`.
+/// Returns the article's ID.
+pub fn validate(files: &[BundleFile], max_article_bytes: usize) -> Result<ArticleId, Error> {
+    let mut seen = HashSet::new();
+    for file in files {
+        if !is_safe_path(&file.path) {
+            return Err(Error::InvalidMetadata(format!("unsafe file path {:?}", file.path)));
+        }
+        if !seen.insert(&file.path) {
+            return Err(Error::InvalidMetadata(format!("duplicate file {:?}", file.path)));
+        }
+    }
+
+    let metainfo_toml = find(files, Path::new("metainfo.toml"))
+        .ok_or_else(|| Error::InvalidMetadata("the bundle has no metainfo.toml".into()))?;
+    let metainfo_toml = std::str::from_utf8(metainfo_toml)
+        .map_err(|_| Error::InvalidMetadata("metainfo.toml is not valid UTF-8".into()))?;
+    let metainfo = Metainfo::from_toml(metainfo_toml, PathBuf::new())
+        .map_err(|e| Error::InvalidMetadata(format!("metainfo.toml: {}", e)))?;
+    if metainfo.id <= 0 {
+        return Err(Error::InvalidMetadata("'id' must be positive".into()));
+    }
+
+    let markdown_path = Path::new(&*metainfo.markdown_path);
+    let markdown = find(files, markdown_path).ok_or_else(|| {
+        Error::InvalidMetadata(format!("the Markdown file '{}' is missing", metainfo.markdown_path))
+    })?;
+    if markdown.len() > max_article_bytes {
+        return Err(Error::too_large(max_article_bytes));
+    }
+    if std::str::from_utf8(markdown).is_err() {
+        return Err(Error::InvalidMetadata(format!(
+            "'{}' is not valid UTF-8",
+            metainfo.markdown_path
+        )));
+    }
+
+    Ok(metainfo.id)
+}
+
+/// Unpack a zip archive into bundle files, refusing archives that would expand
+/// beyond `max_bytes`. If everything is inside a single top-level directory
+/// (as when zipping the article directory itself), that directory is stripped.
+#[cfg(feature = "uploads")]
+pub fn from_zip(bytes: &[u8], max_bytes: usize) -> Result<Vec<BundleFile>, Error> {
+    let invalid = |e: zip::result::ZipError| Error::InvalidMetadata(format!("invalid zip archive: {}", e));
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(invalid)?;
+
+    let mut files = Vec::new();
+    let mut total_bytes = 0usize;
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(invalid)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let path = entry.enclosed_name().ok_or_else(|| {
+            Error::InvalidMetadata(format!("unsafe file path in archive entry {}", i))
+        })?;
+
+        // The declared size can't be trusted, so the read itself is bounded too
+        let remaining = max_bytes.saturating_sub(total_bytes);
+        let mut content = Vec::new();
+        entry
+            .take(remaining as u64 + 1)
+            .read_to_end(&mut content)
+            .map_err(|e| Error::InvalidMetadata(format!("invalid zip archive: {}", e)))?;
+        total_bytes += content.len();
+        if total_bytes > max_bytes {
+            return Err(Error::too_large(max_bytes));
+        }
+        files.push(BundleFile { path, content });
+    }
+
+    strip_common_directory(&mut files);
+    Ok(files)
+}
+
+/// Remove a top-level directory shared by all files, unless `metainfo.toml` is
+/// already at the top level.
+#[cfg(feature = "uploads")]
+fn strip_common_directory(files: &mut [BundleFile]) {
+    if find(files, Path::new("metainfo.toml")).is_some() {
+        return;
+    }
+    let Some(first) = files.first().and_then(|f| f.path.components().next()) else {
+        return;
+    };
+    let prefix = PathBuf::from(first.as_os_str());
+    if files.iter().all(|f| f.path.starts_with(&prefix) && f.path != prefix) {
+        for file in files.iter_mut() {
+            file.path = file.path.strip_prefix(&prefix).unwrap_or(&file.path).to_path_buf();
+        }
+    }
+}
+
+/// Split the files of an archive of several article directories into one bundle
+/// per top-level directory. An archive of a single article is a single bundle, and
+/// files beside the article directories are left out.
+#[cfg(feature = "uploads")]
+pub fn split_directories(files: Vec<BundleFile>) -> Vec<Vec<BundleFile>> {
+    if find(&files, Path::new("metainfo.toml")).is_some() {
+        return vec![files];
+    }
+    let mut bundles: std::collections::BTreeMap<PathBuf, Vec<BundleFile>> = Default::default();
+    for file in files {
+        let mut components = file.path.components();
+        let Some(dir) = components.next() else {
+            continue;
+        };
+        let path = components.as_path().to_path_buf();
+        if !path.as_os_str().is_empty() {
+            let bundle = bundles.entry(PathBuf::from(dir.as_os_str())).or_default();
+            bundle.push(BundleFile { path, content: file.content });
+        }
+    }
+    bundles.into_values().collect()
+}
+
+/// Give the article of a validated bundle another ID, rewriting its `metainfo.toml`.
+pub fn renumber(files: &mut [BundleFile], id: ArticleId) -> Result<(), Error> {
+    let Some(file) = files.iter_mut().find(|f| f.path == Path::new("metainfo.toml")) else {
+        return Err(Error::InvalidMetadata("the bundle has no metainfo.toml".into()));
+    };
+    let invalid = |e: &dyn std::fmt::Display| Error::InvalidMetadata(format!("metainfo.toml: {}", e));
+    let text = std::str::from_utf8(&file.content).map_err(|e| invalid(&e))?;
+    let mut metainfo: toml::Table = toml::from_str(text).map_err(|e| invalid(&e))?;
+    let Some(toml::Value::Table(article)) = metainfo.get_mut("article") else {
+        return Err(invalid(&"no [article] section"));
+    };
+    article.insert("id".into(), toml::Value::Integer(id.into()));
+    file.content = toml::to_string(&metainfo).map_err(|e| invalid(&e))?.into_bytes();
+    Ok(())
+}
+
+fn find<'a>(files: &'a [BundleFile], path: &Path) -> Option<&'a [u8]> {
+    files.iter().find(|f| f.path == path).map(|f| f.content.as_slice())
+}
+
+/// A relative path that stays inside the article directory and contains no
+/// hidden components (which scans would skip anyway).
+fn is_safe_path(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path.components().all(|c| match c {
+            Component::Normal(name) => !name.to_string_lossy().starts_with('.'),
+            _ => false,
+        })
+}
@@ -1,55 +1,243 @@
-use crate::config;
+use dashmap::DashMap;
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-// Counter for cache hits and misses
+use crate::config::SharedConfig;
+
+// Counter for cache hits and misses, kept when `record_cache_stats` is enabled.
+// The counters are atomic so recording never contends between requests.
 pub struct CacheHit {
-    pub cache_hit: u32,
-    pub cache_miss: u32,
+    cache_hit: AtomicU64,
+    cache_miss: AtomicU64,
+    config: SharedConfig,
 }
 
 
 /// Struct to represent cache statistics.
 #[derive(serde::Serialize)]
 pub struct CacheStats {
-    pub cache_hit: u32,
-    pub cache_miss: u32,
+    pub cache_hit: u64,
+    pub cache_miss: u64,
     pub hit_rate: f32,
 }
 
 impl CacheHit {
     // Constrcut a new CacheHit instance
-    pub fn new() -> Self {
+    pub fn new(config: SharedConfig) -> Self {
         Self {
-            cache_hit: 0,
-            cache_miss: 0,
+            cache_hit: AtomicU64::new(0),
+            cache_miss: AtomicU64::new(0),
+            config,
         }
     }
 
     // Increment the cache hit counter
-    pub fn hit(&mut self) {
-        if config::CONFIG.mainconfig.record_cache_stats {
-            self.cache_hit += 1;
+    pub fn hit(&self) {
+        if self.config.load().mainconfig.record_cache_stats {
+            self.cache_hit.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Increment the cache miss counter
+    pub fn miss(&self) {
+        if self.config.load().mainconfig.record_cache_stats {
+            self.cache_miss.fetch_add(1, Ordering::Relaxed);
         }
     }
 
-    // Decrement the cache miss counter
-    pub fn miss(&mut self) {
-        if config::CONFIG.mainconfig.record_cache_stats {
-            self.cache_miss += 1;
+    // Read the counters. Each is read on its own, so a request recorded meanwhile
+    // may be counted in one and not yet in the other.
+    pub fn stats(&self) -> CacheStats {
+        let cache_hit = self.cache_hit.load(Ordering::Relaxed);
+        let cache_miss = self.cache_miss.load(Ordering::Relaxed);
+        CacheStats {
+            cache_hit,
+            cache_miss,
+            hit_rate: hit_rate(cache_hit, cache_miss),
         }
     }
 
     // Calculate the cache hit rate
     pub fn hit_rate(&self) -> f32 {
-        if self.cache_miss == 0 && self.cache_hit == 0 {
-            0.0
-        } else {
-            (self.cache_hit as f32) / (self.cache_hit as f32 + self.cache_miss as f32)
-        }
+        self.stats().hit_rate
     }
 
     // Reset counter
-    pub fn reset(&mut self) {
-        self.cache_hit = 0;
-        self.cache_miss = 0;
+    pub fn reset(&self) {
+        self.cache_hit.store(0, Ordering::Relaxed);
+        self.cache_miss.store(0, Ordering::Relaxed);
+    }
+}
+
+fn hit_rate(cache_hit: u64, cache_miss: u64) -> f32 {
+    if cache_miss == 0 && cache_hit == 0 {
+        0.0
+    } else {
+        (cache_hit as f32) / (cache_hit as f32 + cache_miss as f32)
+    }
+}
+
+// ===== REQUEST STATISTICS =====
+
+/// How many latency buckets each power of two (in microseconds) is split into.
+const BUCKETS_PER_DOUBLING: f64 = 4.0;
+
+/// The number of latency buckets, reaching past 2^36 microseconds (19 hours).
+const LATENCY_BUCKETS: usize = 145;
+
+/// What was recorded for one route: a histogram of the latencies, whose buckets
+/// grow by a fourth of a doubling each, and the number of responses by status.
+struct RouteRecord {
+    requests: u64,
+    total_micros: u64,
+    max_micros: u64,
+    buckets: Vec<u64>,
+    statuses: BTreeMap<u16, u64>,
+}
+
+impl RouteRecord {
+    fn new() -> Self {
+        Self {
+            requests: 0,
+            total_micros: 0,
+            max_micros: 0,
+            buckets: vec![0; LATENCY_BUCKETS],
+            statuses: BTreeMap::new(),
+        }
+    }
+
+    fn bucket_of(micros: u64) -> usize {
+        let bucket = ((micros.max(1) as f64).log2() * BUCKETS_PER_DOUBLING).ceil() as usize;
+        bucket.min(LATENCY_BUCKETS - 1)
+    }
+
+    /// The latency (in milliseconds) that `quantile` of the requests didn't exceed,
+    /// as the upper bound of its bucket, so it's overestimated by up to 19%.
+    fn percentile(&self, quantile: f64) -> f64 {
+        let rank = ((self.requests as f64) * quantile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = 2f64.powf(bucket as f64 / BUCKETS_PER_DOUBLING);
+                return millis(bound.min(self.max_micros as f64));
+            }
+        }
+        millis(self.max_micros as f64)
+    }
+
+    fn stats(&self, route: &str) -> RouteStats {
+        RouteStats {
+            route: route.to_string(),
+            requests: self.requests,
+            latency_ms: Latency {
+                mean: millis(self.total_micros as f64 / self.requests.max(1) as f64),
+                p50: self.percentile(0.50),
+                p95: self.percentile(0.95),
+                p99: self.percentile(0.99),
+                max: millis(self.max_micros as f64),
+            },
+            statuses: self.statuses.clone(),
+        }
+    }
+}
+
+/// `micros` in milliseconds, to the microsecond.
+fn millis(micros: f64) -> f64 {
+    micros.round() / 1000.0
+}
+
+/// Latencies of a route's requests, in milliseconds.
+#[derive(serde::Serialize)]
+pub struct Latency {
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+/// What was recorded for one route.
+#[derive(serde::Serialize)]
+pub struct RouteStats {
+    /// The method and the path pattern, e.g. `GET /api/v1/articles/{id}`.
+    pub route: String,
+    pub requests: u64,
+    pub latency_ms: Latency,
+    /// The number of responses by status code.
+    pub statuses: BTreeMap<u16, u64>,
+}
+
+/// Struct to represent request statistics.
+#[derive(serde::Serialize)]
+pub struct RequestStatsReport {
+    /// When recording started, or the statistics were last reset (seconds since
+    /// the Unix epoch).
+    pub since: u64,
+    pub routes: Vec<RouteStats>,
+}
+
+// Latencies and response statuses per route, kept when `record_request_stats` is
+// enabled. Each route is locked apart, so requests to different routes never
+// contend.
+pub struct RequestStats {
+    routes: DashMap<String, RouteRecord>,
+    since: AtomicU64,
+    config: SharedConfig,
+}
+
+impl RequestStats {
+    pub fn new(config: SharedConfig) -> Self {
+        Self {
+            routes: DashMap::new(),
+            since: AtomicU64::new(unix_now()),
+            config,
+        }
     }
+
+    // Record a request to `route` answered with `status` after `elapsed`
+    pub fn record(&self, route: &str, status: u16, elapsed: Duration) {
+        if !self.config.load().mainconfig.record_request_stats {
+            return;
+        }
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let mut record = match self.routes.get_mut(route) {
+            Some(record) => record,
+            None => self.routes.entry(route.to_string()).or_insert_with(RouteRecord::new),
+        };
+        record.requests += 1;
+        record.total_micros = record.total_micros.saturating_add(micros);
+        record.max_micros = record.max_micros.max(micros);
+        record.buckets[RouteRecord::bucket_of(micros)] += 1;
+        *record.statuses.entry(status).or_insert(0) += 1;
+    }
+
+    // Read the statistics of every route, sorted by route
+    pub fn stats(&self) -> RequestStatsReport {
+        let mut routes = self
+            .routes
+            .iter()
+            .map(|entry| entry.value().stats(entry.key()))
+            .collect::<Vec<_>>();
+        routes.sort_by(|a, b| a.route.cmp(&b.route));
+        RequestStatsReport {
+            since: self.since.load(Ordering::Relaxed),
+            routes,
+        }
+    }
+
+    // Forget every route
+    pub fn reset(&self) {
+        self.routes.clear();
+        self.since.store(unix_now(), Ordering::Relaxed);
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
 }
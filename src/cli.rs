@@ -0,0 +1,48 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use henkaiki::{
+    config::{ConfigSource, Overrides},
+    version,
+};
+
+/// Serves Markdown articles over a JSON API.
+#[derive(Parser)]
+#[command(version, long_version = version::LONG_VERSION, about)]
+pub struct Cli {
+    /// The configuration file [default: $HENKAIKI_CONFIG, ./config.toml,
+    /// $XDG_CONFIG_HOME/henkaiki/config.toml or /etc/henkaiki/config.toml]
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<PathBuf>,
+    /// Listen on this port instead of the configured one
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Serve the articles in this directory instead of the configured ones
+    #[arg(long, value_name = "DIR", global = true)]
+    pub articles_dir: Option<String>,
+    /// Log filter such as `info` or `henkaiki=debug`, instead of `RUST_LOG`
+    #[arg(long, value_name = "FILTER", global = true)]
+    pub log_level: Option<String>,
+    /// Check that the configuration loads, then exit
+    #[arg(long)]
+    pub check_config: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check the content for problems, exiting with status 1 if there are any
+    Validate,
+}
+
+impl Cli {
+    /// Where to load the configuration from, with the overrides given.
+    pub fn config_source(&self) -> anyhow::Result<ConfigSource> {
+        let overrides = Overrides {
+            port: self.port,
+            articles_dir: self.articles_dir.clone(),
+        };
+        ConfigSource::locate(self.config.clone(), overrides)
+    }
+}
@@ -0,0 +1,452 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    articles::ArticleId,
+    config::{self, SharedConfig},
+    error::Error,
+    rate_limit::RateLimiter,
+};
+
+/// Identifies a comment among all those of a store.
+pub type CommentId = u64;
+
+/// The longest author name accepted, in characters.
+const MAX_AUTHOR_LENGTH: usize = 100;
+
+/// Where a comment stands in moderation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentStatus {
+    /// Waiting for a moderator's approval.
+    Pending,
+    /// Held as likely spam, also waiting for a moderator.
+    Spam,
+    /// Shown with the article.
+    Approved,
+}
+
+impl CommentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CommentStatus::Pending => "pending",
+            CommentStatus::Spam => "spam",
+            CommentStatus::Approved => "approved",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "pending" => Some(CommentStatus::Pending),
+            "spam" => Some(CommentStatus::Spam),
+            "approved" => Some(CommentStatus::Approved),
+            _ => None,
+        }
+    }
+}
+
+/// A reader's comment on an article.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Comment {
+    pub id: CommentId,
+    pub article_id: ArticleId,
+    pub author: String,
+    pub body: String,
+    /// When it was posted, in seconds since the Unix epoch.
+    pub created_at: u64,
+    pub status: CommentStatus,
+}
+
+/// Keeps the comments of a set of articles. Calls may block, so the handlers make
+/// them off the async workers.
+pub trait CommentStore: Send + Sync {
+    /// Keep `comment` under a new ID, returning it with that ID.
+    fn insert(&self, comment: Comment) -> Result<Comment>;
+
+    /// The comments of `article_id` with `status`, oldest first.
+    fn of_article(&self, article_id: ArticleId, status: CommentStatus) -> Result<Vec<Comment>>;
+
+    /// The comments waiting for a moderator, pending or spam, oldest first.
+    fn held(&self) -> Result<Vec<Comment>>;
+
+    /// Change the status of comment `id`, returning it; `None` if there's no such comment.
+    fn set_status(&self, id: CommentId, status: CommentStatus) -> Result<Option<Comment>>;
+
+    /// Delete comment `id`, returning it; `None` if there's no such comment.
+    fn delete(&self, id: CommentId) -> Result<Option<Comment>>;
+
+    /// The number of approved comments of every article having any.
+    fn approved_counts(&self) -> Result<HashMap<ArticleId, usize>>;
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct CommentFile {
+    /// The ID given to the last comment, never reused after a deletion.
+    last_id: CommentId,
+    comments: Vec<Comment>,
+}
+
+/// Keeps comments in memory and in a JSON file, rewritten after every change.
+pub struct FileCommentStore {
+    path: PathBuf,
+    file: Mutex<CommentFile>,
+}
+
+impl FileCommentStore {
+    /// Load the comments kept at `path`, starting from none if it doesn't exist yet.
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let file = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Invalid comments file {:?}", path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => CommentFile::default(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        };
+        Ok(FileCommentStore {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn save(&self, file: &CommentFile) -> Result<()> {
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(file)?)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl CommentStore for FileCommentStore {
+    fn insert(&self, mut comment: Comment) -> Result<Comment> {
+        let mut file = self.file.lock().unwrap();
+        file.last_id += 1;
+        comment.id = file.last_id;
+        file.comments.push(comment.clone());
+        self.save(&file)?;
+        Ok(comment)
+    }
+
+    fn of_article(&self, article_id: ArticleId, status: CommentStatus) -> Result<Vec<Comment>> {
+        let file = self.file.lock().unwrap();
+        Ok(file
+            .comments
+            .iter()
+            .filter(|comment| comment.article_id == article_id && comment.status == status)
+            .cloned()
+            .collect())
+    }
+
+    fn held(&self) -> Result<Vec<Comment>> {
+        let file = self.file.lock().unwrap();
+        Ok(file
+            .comments
+            .iter()
+            .filter(|comment| comment.status != CommentStatus::Approved)
+            .cloned()
+            .collect())
+    }
+
+    fn set_status(&self, id: CommentId, status: CommentStatus) -> Result<Option<Comment>> {
+        let mut file = self.file.lock().unwrap();
+        let Some(comment) = file.comments.iter_mut().find(|comment| comment.id == id) else {
+            return Ok(None);
+        };
+        comment.status = status;
+        let comment = comment.clone();
+        self.save(&file)?;
+        Ok(Some(comment))
+    }
+
+    fn delete(&self, id: CommentId) -> Result<Option<Comment>> {
+        let mut file = self.file.lock().unwrap();
+        let Some(position) = file.comments.iter().position(|comment| comment.id == id) else {
+            return Ok(None);
+        };
+        let comment = file.comments.remove(position);
+        self.save(&file)?;
+        Ok(Some(comment))
+    }
+
+    fn approved_counts(&self) -> Result<HashMap<ArticleId, usize>> {
+        let file = self.file.lock().unwrap();
+        let mut counts = HashMap::new();
+        for comment in file.comments.iter().filter(|c| c.status == CommentStatus::Approved) {
+            *counts.entry(comment.article_id).or_default() += 1;
+        }
+        Ok(counts)
+    }
+}
+
+/// Keeps comments in a SQLite database.
+#[cfg(feature = "sqlite")]
+pub struct SqliteCommentStore {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteCommentStore {
+    /// Open (or create) the database at `path`; `:memory:` keeps it in memory.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS comments (
+                 id         INTEGER PRIMARY KEY AUTOINCREMENT,
+                 article_id INTEGER NOT NULL,
+                 author     TEXT NOT NULL,
+                 body       TEXT NOT NULL,
+                 created_at INTEGER NOT NULL,
+                 status     TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS comments_by_article ON comments (article_id, status);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn select(&self, filter: &str, params: impl rusqlite::Params) -> Result<Vec<Comment>> {
+        let conn = self.conn.lock().unwrap();
+        let sql = format!(
+            "SELECT id, article_id, author, body, created_at, status FROM comments {} ORDER BY id",
+            filter
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let comments = stmt.query_map(params, read_comment)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(comments)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn read_comment(row: &rusqlite::Row) -> rusqlite::Result<Comment> {
+    let status: String = row.get(5)?;
+    Ok(Comment {
+        id: row.get::<_, i64>(0)? as CommentId,
+        article_id: row.get(1)?,
+        author: row.get(2)?,
+        body: row.get(3)?,
+        created_at: row.get::<_, i64>(4)? as u64,
+        status: CommentStatus::parse(&status).ok_or_else(|| {
+            rusqlite::Error::InvalidColumnType(5, "status".into(), rusqlite::types::Type::Text)
+        })?,
+    })
+}
+
+#[cfg(feature = "sqlite")]
+impl CommentStore for SqliteCommentStore {
+    fn insert(&self, mut comment: Comment) -> Result<Comment> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO comments (article_id, author, body, created_at, status)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                comment.article_id,
+                comment.author,
+                comment.body,
+                comment.created_at as i64,
+                comment.status.as_str()
+            ],
+        )?;
+        comment.id = conn.last_insert_rowid() as CommentId;
+        Ok(comment)
+    }
+
+    fn of_article(&self, article_id: ArticleId, status: CommentStatus) -> Result<Vec<Comment>> {
+        self.select(
+            "WHERE article_id = ?1 AND status = ?2",
+            rusqlite::params![article_id, status.as_str()],
+        )
+    }
+
+    fn held(&self) -> Result<Vec<Comment>> {
+        self.select("WHERE status != 'approved'", [])
+    }
+
+    fn set_status(&self, id: CommentId, status: CommentStatus) -> Result<Option<Comment>> {
+        let changed = self.conn.lock().unwrap().execute(
+            "UPDATE comments SET status = ?1 WHERE id = ?2",
+            rusqlite::params![status.as_str(), id as i64],
+        )?;
+        if changed == 0 {
+            return Ok(None);
+        }
+        Ok(self.select("WHERE id = ?1", [id as i64])?.pop())
+    }
+
+    fn delete(&self, id: CommentId) -> Result<Option<Comment>> {
+        let Some(comment) = self.select("WHERE id = ?1", [id as i64])?.pop() else {
+            return Ok(None);
+        };
+        self.conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM comments WHERE id = ?1", [id as i64])?;
+        Ok(Some(comment))
+    }
+
+    fn approved_counts(&self) -> Result<HashMap<ArticleId, usize>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT article_id, COUNT(*) FROM comments WHERE status = 'approved' GROUP BY article_id",
+        )?;
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(counts)
+    }
+}
+
+/// The comments readers leave on a set of articles, with the moderation around
+/// them: new comments are checked for spam and, as the `[comments]` settings in
+/// effect say, held for approval, and each client may only post so many a minute.
+/// The approved comments of every article are counted in memory for the summaries.
+pub struct Comments {
+    store: Box<dyn CommentStore>,
+    config: SharedConfig,
+    counts: Mutex<HashMap<ArticleId, usize>>,
+    limiter: RateLimiter,
+}
+
+impl Comments {
+    /// Open the store `settings` configure for `section`, or for the main articles.
+    pub fn open(
+        settings: &config::Comments,
+        section: Option<&str>,
+        config: SharedConfig,
+    ) -> Result<Self> {
+        let path = settings.path_for(section);
+        #[cfg(feature = "sqlite")]
+        if settings.uses_sqlite() {
+            return Self::new(Box::new(SqliteCommentStore::open(&path)?), config);
+        }
+        Self::new(Box::new(FileCommentStore::open(path)?), config)
+    }
+
+    /// Moderate the comments kept in `store`.
+    pub fn new(store: Box<dyn CommentStore>, config: SharedConfig) -> Result<Self> {
+        let counts = store.approved_counts()?;
+        Ok(Comments {
+            store,
+            config,
+            counts: Mutex::new(counts),
+            limiter: RateLimiter::default(),
+        })
+    }
+
+    /// The number of approved comments on `article_id`.
+    pub fn count(&self, article_id: ArticleId) -> usize {
+        self.counts.lock().unwrap().get(&article_id).copied().unwrap_or_default()
+    }
+
+    /// The approved comments on `article_id`, oldest first.
+    pub fn approved(&self, article_id: ArticleId) -> Result<Vec<Comment>> {
+        self.store.of_article(article_id, CommentStatus::Approved)
+    }
+
+    /// The comments waiting for a moderator, oldest first.
+    pub fn held(&self) -> Result<Vec<Comment>> {
+        self.store.held()
+    }
+
+    /// Post a comment by `author` on `article_id` from `client`. It's held as spam
+    /// if it has too many links or a blocked word, held for approval if moderation
+    /// is on, and published otherwise. Fails with [`Error::InvalidRequest`] for an
+    /// empty or overlong author or body, and with [`Error::RateLimited`] once
+    /// `client` posted `max_per_minute` comments within a minute.
+    pub fn post(
+        &self,
+        article_id: ArticleId,
+        author: &str,
+        body: &str,
+        client: Option<IpAddr>,
+    ) -> Result<Comment> {
+        let config = self.config.load();
+        let Some(settings) = &config.comments else {
+            return Err(Error::InvalidRequest("comments are disabled".into()).into());
+        };
+        let (author, body) = (author.trim(), body.trim());
+        if author.is_empty() || body.is_empty() {
+            return Err(Error::InvalidRequest("'author' and 'body' must not be empty".into()).into());
+        }
+        if author.chars().count() > MAX_AUTHOR_LENGTH {
+            return Err(Error::InvalidRequest(format!(
+                "'author' must be at most {} characters",
+                MAX_AUTHOR_LENGTH
+            ))
+            .into());
+        }
+        if body.chars().count() > settings.max_length {
+            return Err(Error::InvalidRequest(format!(
+                "'body' must be at most {} characters",
+                settings.max_length
+            ))
+            .into());
+        }
+        if let Some(client) = client {
+            self.limiter.check(client, settings.max_per_minute, "comments")?;
+        }
+
+        let status = if looks_like_spam(settings, author, body) {
+            CommentStatus::Spam
+        } else if settings.moderation {
+            CommentStatus::Pending
+        } else {
+            CommentStatus::Approved
+        };
+        let comment = self.store.insert(Comment {
+            id: 0,
+            article_id,
+            author: author.to_string(),
+            body: body.to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            status,
+        })?;
+        if status == CommentStatus::Approved {
+            *self.counts.lock().unwrap().entry(article_id).or_default() += 1;
+        }
+        Ok(comment)
+    }
+
+    /// Approve comment `id`, held or not, returning it.
+    pub fn approve(&self, id: CommentId) -> Result<Comment> {
+        let comment = self
+            .store
+            .set_status(id, CommentStatus::Approved)?
+            .ok_or(Error::CommentNotFound(id))?;
+        self.recount(comment.article_id)?;
+        Ok(comment)
+    }
+
+    /// Delete comment `id`, returning it.
+    pub fn delete(&self, id: CommentId) -> Result<Comment> {
+        let comment = self.store.delete(id)?.ok_or(Error::CommentNotFound(id))?;
+        self.recount(comment.article_id)?;
+        Ok(comment)
+    }
+
+    fn recount(&self, article_id: ArticleId) -> Result<()> {
+        let count = self.store.of_article(article_id, CommentStatus::Approved)?.len();
+        self.counts.lock().unwrap().insert(article_id, count);
+        Ok(())
+    }
+}
+
+/// Whether a comment has more links than allowed or contains a blocked word.
+fn looks_like_spam(settings: &config::Comments, author: &str, body: &str) -> bool {
+    let text = format!("{}\n{}", author, body).to_lowercase();
+    let links = text.matches("http://").count() + text.matches("https://").count();
+    links > settings.max_links
+        || settings
+            .blocked_words
+            .iter()
+            .any(|word| !word.is_empty() && text.contains(&word.to_lowercase()))
+}
@@ -0,0 +1,154 @@
+use actix_web::{error::BlockingError, http::StatusCode, HttpResponse, ResponseError};
+use std::{fmt, io, sync::Arc};
+
+use crate::{api::ApiResponse, articles::ArticleId, comments::CommentId, store::ArticleTooLarge};
+
+/// The errors a request can fail with, each mapped to an HTTP status and a stable
+/// `error_code` for clients to match on.
+///
+/// Internals mostly work with `anyhow`; errors of this type raised there survive
+/// the trip and are recovered by the `From<anyhow::Error>` conversion, while
+/// anything unexpected becomes [`Error::Io`] or [`Error::Internal`].
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// No article has the given ID.
+    NotFound(ArticleId),
+    /// The article with the given ID was deleted, and may have been succeeded by another.
+    Gone {
+        id: ArticleId,
+        successor: Option<ArticleId>,
+    },
+    /// No comment has the given ID.
+    CommentNotFound(CommentId),
+    /// An article with the given ID already exists.
+    Conflict(ArticleId),
+    /// Submitted article metadata or content is invalid.
+    InvalidMetadata(String),
+    /// The request can't be processed as sent.
+    InvalidRequest(String),
+    /// Content exceeds a size limit.
+    TooLarge(String),
+    /// The requested page is past the last one.
+    PageOutOfRange { page: usize, pages: usize },
+    /// The request lacks valid credentials.
+    Unauthorized(String),
+    /// The credentials are valid but don't allow the request.
+    Forbidden(String),
+    /// The client sent too many requests of the kind in too short a time.
+    RateLimited(String),
+    /// Reading or writing content failed.
+    Io(Arc<anyhow::Error>),
+    /// Any other failure.
+    Internal(Arc<anyhow::Error>),
+}
+
+impl Error {
+    /// Content larger than `limit` bytes.
+    pub fn too_large(limit: usize) -> Self {
+        Error::TooLarge(format!("Content exceeds the limit of {} bytes", limit))
+    }
+
+    /// The stable, machine-readable name of the error.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::Gone { .. } => "gone",
+            Error::CommentNotFound(_) => "comment_not_found",
+            Error::Conflict(_) => "conflict",
+            Error::InvalidMetadata(_) => "invalid_metadata",
+            Error::InvalidRequest(_) => "invalid_request",
+            Error::TooLarge(_) => "too_large",
+            Error::PageOutOfRange { .. } => "page_out_of_range",
+            Error::Unauthorized(_) => "unauthorized",
+            Error::Forbidden(_) => "forbidden",
+            Error::RateLimited(_) => "rate_limited",
+            Error::Io(_) => "io_error",
+            Error::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Respond with the error's status and code. Server-side failures are described
+    /// by `message` instead of their details, which only belong in the logs.
+    pub fn response(&self, message: &str) -> HttpResponse {
+        let message = if self.status_code().is_server_error() {
+            message.to_string()
+        } else {
+            self.to_string()
+        };
+        HttpResponse::build(self.status_code()).json(ApiResponse::<()> {
+            success: false,
+            data: (),
+            message: Some(message),
+            error_code: Some(self.error_code()),
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(id) => write!(f, "Article with ID {} not found", id),
+            Error::Gone { id, successor: None } => write!(f, "Article with ID {} was deleted", id),
+            Error::Gone { id, successor: Some(successor) } => {
+                write!(f, "Article with ID {} was deleted in favor of article {}", id, successor)
+            }
+            Error::CommentNotFound(id) => write!(f, "Comment with ID {} not found", id),
+            Error::Conflict(id) => write!(f, "Article with ID {} already exists", id),
+            Error::InvalidMetadata(reason) => write!(f, "Invalid article: {}", reason),
+            Error::InvalidRequest(reason) => write!(f, "Invalid request: {}", reason),
+            Error::TooLarge(reason)
+            | Error::Unauthorized(reason)
+            | Error::Forbidden(reason)
+            | Error::RateLimited(reason) => f.write_str(reason),
+            Error::PageOutOfRange { page, pages } => {
+                write!(f, "Page {} is out of range ({} pages)", page, pages)
+            }
+            Error::Io(e) => write!(f, "I/O error: {:#}", e),
+            Error::Internal(e) => write!(f, "{:#}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(error) = e.downcast_ref::<Error>() {
+            error.clone()
+        } else if let Some(too_large) = e.downcast_ref::<ArticleTooLarge>() {
+            Error::TooLarge(too_large.to_string())
+        } else if e.downcast_ref::<io::Error>().is_some() {
+            Error::Io(Arc::new(e))
+        } else {
+            Error::Internal(Arc::new(e))
+        }
+    }
+}
+
+impl From<BlockingError> for Error {
+    fn from(e: BlockingError) -> Self {
+        Error::Internal(Arc::new(e.into()))
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Error::NotFound(_) | Error::CommentNotFound(_) | Error::PageOutOfRange { .. } => {
+                StatusCode::NOT_FOUND
+            }
+            Error::Gone { .. } => StatusCode::GONE,
+            Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::InvalidMetadata(_) | Error::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Error::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            Error::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Error::Io(_) | Error::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.response("Internal server error")
+    }
+}
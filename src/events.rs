@@ -0,0 +1,171 @@
+use crate::articles::ArticleId;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::broadcast;
+
+/// How many events a slow subscriber may fall behind before it misses some.
+const EVENT_BUFFER: usize = 256;
+
+/// How many article changes the change log keeps. Clients syncing from before the
+/// oldest one kept have to sync in full.
+const CHANGE_LOG_ENTRIES: usize = 10_000;
+
+/// `since` values from this one up are Unix timestamps rather than generations,
+/// which never grow that large.
+const FIRST_TIMESTAMP: u64 = 1_000_000_000;
+
+/// A change to the content served by an `Articles` instance.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentEvent {
+    ArticleAdded { id: ArticleId },
+    /// The article's metadata changed, or it was edited or refreshed through the API.
+    ArticleUpdated { id: ArticleId },
+    ArticleRemoved { id: ArticleId },
+    /// A full index load finished; sent after the article events it caused.
+    IndexRefreshed { articles: usize },
+}
+
+/// Fans content events out to every current subscriber, and logs the article
+/// changes among them for clients catching up later.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ContentEvent>,
+    log: Arc<Mutex<ChangeLog>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            sender,
+            log: Arc::new(Mutex::new(ChangeLog::default())),
+        }
+    }
+
+    pub fn publish(&self, event: ContentEvent) {
+        self.log.lock().unwrap().record(&event);
+        // Sending only fails when nobody is listening, which is fine
+        let _ = self.sender.send(event);
+    }
+
+    /// The articles added, updated and removed since `since`: a generation from an
+    /// earlier [`Changes`], or a Unix timestamp.
+    pub fn changes_since(&self, since: u64) -> Changes {
+        self.log.lock().unwrap().since(since)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ContentEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ===== CHANGE LOG =====
+
+/// One logged change to an article.
+struct Change {
+    generation: u64,
+    /// When it happened, in seconds since the UNIX epoch.
+    at: u64,
+    id: ArticleId,
+    kind: ChangeKind,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ChangeKind {
+    Added,
+    Updated,
+    Removed,
+}
+
+/// The latest article changes, each numbered by the generation of the content it
+/// brought about. The first index load logs every article as added, so syncing
+/// from generation 0 lists them all.
+#[derive(Default)]
+struct ChangeLog {
+    changes: VecDeque<Change>,
+    generation: u64,
+    /// The generation of the last change dropped to make room, if any.
+    dropped: Option<u64>,
+}
+
+impl ChangeLog {
+    fn record(&mut self, event: &ContentEvent) {
+        let (id, kind) = match *event {
+            ContentEvent::ArticleAdded { id } => (id, ChangeKind::Added),
+            ContentEvent::ArticleUpdated { id } => (id, ChangeKind::Updated),
+            ContentEvent::ArticleRemoved { id } => (id, ChangeKind::Removed),
+            ContentEvent::IndexRefreshed { .. } => return,
+        };
+        self.generation += 1;
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        self.changes.push_back(Change { generation: self.generation, at, id, kind });
+        if self.changes.len() > CHANGE_LOG_ENTRIES
+            && let Some(dropped) = self.changes.pop_front()
+        {
+            self.dropped = Some(dropped.generation);
+        }
+    }
+
+    fn since(&self, since: u64) -> Changes {
+        let (start, full_sync) = if since >= FIRST_TIMESTAMP {
+            let start = self.changes.partition_point(|change| change.at < since);
+            // Dropped changes may have happened after `since` when none kept did before
+            (start, start == 0 && self.dropped.is_some())
+        } else {
+            let start = self.changes.partition_point(|change| change.generation <= since);
+            // A generation ahead of the log's comes from before a restart
+            let lost = self.dropped.is_some_and(|dropped| since < dropped);
+            (start, lost || since > self.generation)
+        };
+
+        // Each article is listed once, by what its changes amount to overall
+        let mut net = BTreeMap::new();
+        for change in self.changes.range(start..) {
+            net.entry(change.id)
+                .and_modify(|(_, last)| *last = change.kind)
+                .or_insert((change.kind, change.kind));
+        }
+        let mut changes = Changes {
+            generation: self.generation,
+            full_sync,
+            added: Vec::new(),
+            updated: Vec::new(),
+            removed: Vec::new(),
+        };
+        for (id, (first, last)) in net {
+            match (first, last) {
+                (ChangeKind::Added, ChangeKind::Removed) => {}
+                (ChangeKind::Added, _) => changes.added.push(id),
+                (_, ChangeKind::Removed) => changes.removed.push(id),
+                _ => changes.updated.push(id),
+            }
+        }
+        changes
+    }
+}
+
+/// The articles that changed since a point of a client's choosing, in ID order.
+#[derive(Debug, Serialize)]
+pub struct Changes {
+    /// The current generation, to pass as `since` next time.
+    pub generation: u64,
+    /// Whether the log no longer reaches back to `since`, so the client has to
+    /// fetch everything again; the lists then hold only what the log still has.
+    pub full_sync: bool,
+    pub added: Vec<ArticleId>,
+    pub updated: Vec<ArticleId>,
+    pub removed: Vec<ArticleId>,
+}
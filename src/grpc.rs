@@ -0,0 +1,252 @@
+use actix_web::{rt, web};
+use log::*;
+use std::net::SocketAddr;
+use tonic::{Request, Response, Status};
+
+use crate::{
+    articles::{Article as ArticleData, ArticleSummary as SummaryData, Articles},
+    error::Error,
+    search::SearchFields,
+};
+
+// ===== MESSAGES =====
+// These mirror `proto/henkaiki.proto`, which is what clients generate stubs from.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetArticleRequest {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListArticlesRequest {
+    #[prost(uint32, tag = "1")]
+    pub limit: u32,
+    #[prost(uint32, tag = "2")]
+    pub page: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SearchArticlesRequest {
+    #[prost(string, tag = "1")]
+    pub query: String,
+    #[prost(uint32, tag = "2")]
+    pub limit: u32,
+    #[prost(uint32, tag = "3")]
+    pub page: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListArticlesByTagRequest {
+    #[prost(string, tag = "1")]
+    pub tag: String,
+    #[prost(uint32, tag = "2")]
+    pub limit: u32,
+    #[prost(uint32, tag = "3")]
+    pub page: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Article {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    #[prost(string, tag = "2")]
+    pub title: String,
+    #[prost(string, tag = "3")]
+    pub description: String,
+    #[prost(string, tag = "4")]
+    pub content: String,
+    #[prost(uint32, tag = "5")]
+    pub date: u32,
+    #[prost(string, repeated, tag = "6")]
+    pub tags: Vec<String>,
+    #[prost(string, repeated, tag = "7")]
+    pub keywords: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ArticleSummary {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+    #[prost(string, tag = "2")]
+    pub title: String,
+    #[prost(string, tag = "3")]
+    pub description: String,
+    #[prost(uint32, tag = "4")]
+    pub date: u32,
+    #[prost(string, repeated, tag = "5")]
+    pub tags: Vec<String>,
+    #[prost(string, repeated, tag = "6")]
+    pub keywords: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ArticleList {
+    #[prost(message, repeated, tag = "1")]
+    pub items: Vec<ArticleSummary>,
+    #[prost(uint32, tag = "2")]
+    pub pages: u32,
+}
+
+impl From<ArticleData> for Article {
+    fn from(article: ArticleData) -> Self {
+        Article {
+            id: article.id,
+            title: article.title.to_string(),
+            description: article.description.to_string(),
+            content: article.content.to_string(),
+            date: article.date,
+            tags: article.tags.to_vec(),
+            keywords: article.keywords.to_vec(),
+        }
+    }
+}
+
+impl From<SummaryData> for ArticleSummary {
+    fn from(summary: SummaryData) -> Self {
+        ArticleSummary {
+            id: summary.id,
+            title: summary.title.to_string(),
+            description: summary.description.to_string(),
+            date: summary.date,
+            tags: summary.tags.to_vec(),
+            keywords: summary.keywords.to_vec(),
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/henkaiki.v1.Articles.rs"));
+
+use articles_server::ArticlesServer;
+
+// ===== SERVICE =====
+
+/// Serves the `henkaiki.v1.Articles` gRPC service from an `Articles` instance.
+pub struct ArticleService {
+    articles: Articles,
+}
+
+/// Map an error to the gRPC status closest to its HTTP status.
+fn status(e: Error) -> Status {
+    let message = e.to_string();
+    match e {
+        Error::NotFound(_) | Error::CommentNotFound(_) | Error::Gone { .. } => {
+            Status::not_found(message)
+        }
+        Error::Conflict(_) => Status::already_exists(message),
+        Error::InvalidMetadata(_) | Error::InvalidRequest(_) => Status::invalid_argument(message),
+        Error::TooLarge(_) | Error::RateLimited(_) => Status::resource_exhausted(message),
+        Error::PageOutOfRange { .. } => Status::out_of_range(message),
+        Error::Unauthorized(_) => Status::unauthenticated(message),
+        Error::Forbidden(_) => Status::permission_denied(message),
+        Error::Io(_) | Error::Internal(_) => {
+            error!("gRPC request failed: {}", message);
+            Status::internal("Internal server error")
+        }
+    }
+}
+
+/// Respond with one page of `results`, or all of them if `limit` is 0.
+fn article_list(
+    results: anyhow::Result<Vec<SummaryData>>,
+    limit: u32,
+    pages: impl FnOnce(usize) -> usize,
+) -> Result<Response<ArticleList>, Status> {
+    let items = results.map_err(|e| status(e.into()))?;
+    let pages = match limit {
+        0 => usize::from(!items.is_empty()),
+        limit => pages(limit as usize),
+    };
+    Ok(Response::new(ArticleList {
+        items: items.into_iter().map(ArticleSummary::from).collect(),
+        pages: pages as u32,
+    }))
+}
+
+#[tonic::async_trait]
+impl articles_server::Articles for ArticleService {
+    async fn get_article(
+        &self,
+        request: Request<GetArticleRequest>,
+    ) -> Result<Response<Article>, Status> {
+        let id = request.into_inner().id;
+        // Private articles are only served over HTTP, to requests that may read them
+        if self.articles.is_private(id) {
+            return Err(status(Error::NotFound(id)));
+        }
+        let articles = self.articles.clone();
+        let (article, _) = web::block(move || articles.get_article(id))
+            .await
+            .map_err(|e| status(e.into()))?
+            .map_err(|e| status(e.into()))?;
+        Ok(Response::new(article.into()))
+    }
+
+    async fn list_articles(
+        &self,
+        request: Request<ListArticlesRequest>,
+    ) -> Result<Response<ArticleList>, Status> {
+        let ListArticlesRequest { limit, page } = request.into_inner();
+        let results = match limit {
+            0 => self.articles.list_article_summaries(),
+            limit => self
+                .articles
+                .list_article_summaries_paginated(limit as usize, page as usize),
+        };
+        article_list(results, limit, |limit| {
+            self.articles.get_article_summary_page_count(limit)
+        })
+    }
+
+    async fn search_articles(
+        &self,
+        request: Request<SearchArticlesRequest>,
+    ) -> Result<Response<ArticleList>, Status> {
+        let SearchArticlesRequest { query, limit, page } = request.into_inner();
+        let results = match limit {
+            0 => self.articles.search_articles(&query, SearchFields::ALL, &[]),
+            limit => self.articles.search_articles_paginated(
+                &query,
+                SearchFields::ALL,
+                &[],
+                limit as usize,
+                page as usize,
+            ),
+        };
+        article_list(results, limit, |limit| {
+            self.articles
+                .get_search_article_page_count(&query, SearchFields::ALL, &[], limit)
+        })
+    }
+
+    async fn list_articles_by_tag(
+        &self,
+        request: Request<ListArticlesByTagRequest>,
+    ) -> Result<Response<ArticleList>, Status> {
+        let ListArticlesByTagRequest { tag, limit, page } = request.into_inner();
+        let results = match limit {
+            0 => self.articles.list_article_summaries_by_tag(&tag),
+            limit => self
+                .articles
+                .list_article_summaries_by_tag_paginated(&tag, limit as usize, page as usize),
+        };
+        article_list(results, limit, |limit| {
+            self.articles.get_article_summary_by_tag_page_count(&tag, limit)
+        })
+    }
+}
+
+/// Serve the gRPC service on `addr` in the background for as long as the server runs.
+pub fn spawn_server(articles: Articles, addr: SocketAddr) {
+    let service = ArticlesServer::new(ArticleService { articles });
+    rt::spawn(async move {
+        info!("Serving gRPC on {}", addr);
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(service)
+            .serve(addr)
+            .await
+        {
+            error!("gRPC server failed: {}", e);
+        }
+    });
+}
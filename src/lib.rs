@@ -0,0 +1,86 @@
+//! The article engine behind the henkaiki server: it indexes Markdown articles and
+//! their metadata from a [`store`], caches them rendered to HTML, and answers
+//! listing, tag and search queries.
+//!
+//! The `henkaiki` binary serves it over HTTP, but nothing here requires running the
+//! server. To embed the engine, load a [`Config`] and build [`Articles`] from it,
+//! overriding what the configuration says as needed:
+//!
+//! ```no_run
+//! use henkaiki::{config, Articles};
+//!
+//! let source = config::ConfigSource::locate(None, config::Overrides::default())?;
+//! let (config, _warnings) = config::load(&source)?;
+//!
+//! let articles = Articles::builder()
+//!     .config(config::shared(config))
+//!     .source_dir("articles")
+//!     .cache_capacity(50)
+//!     .build()?;
+//! let (article, _) = articles.get_article(1)?;
+//! println!("{}", article.title);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Markdown can also be converted on its own with [`markdown::MarkdownConverter`].
+//! The [`api`] module and its neighbours hold the actix-web handlers the binary
+//! mounts.
+
+#[cfg(feature = "activitypub")]
+pub mod activitypub;
+pub mod api;
+pub mod articles;
+pub mod auth;
+#[cfg(feature = "backup")]
+pub mod backup;
+pub mod bundle;
+pub mod cache_recorder;
+pub mod comments;
+pub mod config;
+pub mod error;
+pub mod events;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod markdown;
+#[cfg(any(feature = "activitypub", feature = "webmention"))]
+mod outbound;
+pub mod proxy;
+pub mod rate_limit;
+pub mod reactions;
+pub mod refresher;
+pub mod reload;
+pub mod search;
+pub mod social;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_index;
+pub mod stats;
+pub mod status;
+pub mod store;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod version;
+#[cfg(feature = "webmention")]
+pub mod webmention;
+
+pub use articles::{Article, ArticleId, Articles};
+pub use config::{Config, SharedConfig};
+pub use error::Error;
+pub use store::{ArticleStore, FsStore};
+pub use version::{build_info, BuildInfo};
+
+/// Section names that would shadow the top-level API routes: the first path segment
+/// of every route below `/api/v1` and `/api/v2`. Section scopes are registered
+/// before these routes, and a matching scope never falls through to them.
+pub const RESERVED_SECTION_NAMES: &[&str] = &[
+    "admin",
+    "articles",
+    "comments",
+    "export",
+    "import",
+    "maintenance",
+    "stats",
+    "status",
+    "version",
+    "webhooks",
+    "ws",
+];
@@ -1,17 +1,52 @@
-use actix_web::{App, HttpResponse, HttpServer, get, middleware, web};
+use actix_web::{
+    App, HttpResponse, HttpServer,
+    dev::{Service, ServiceRequest},
+    get,
+    http::header::{HeaderName, HeaderValue},
+    middleware, web,
+};
+use clap::Parser;
 use log::*;
-use lru::LruCache;
-use std::sync::{Arc, Mutex};
+use std::{sync::Arc, time::Instant};
 
-mod api;
-mod articles;
-mod cache_recorder;
-mod config;
-mod markdown;
+use henkaiki::{
+    api, articles, config, error, proxy, refresher, reload, Articles, RESERVED_SECTION_NAMES,
+};
+#[cfg(feature = "activitypub")]
+use henkaiki::activitypub;
+#[cfg(feature = "grpc")]
+use henkaiki::grpc;
+#[cfg(feature = "webmention")]
+use henkaiki::webmention;
 
-use articles::Articles;
+use henkaiki::api::compression::CompressedCache;
+use henkaiki::cache_recorder::RequestStats;
+use henkaiki::comments::Comments;
+use henkaiki::reactions::Reactions;
+use henkaiki::status::StartTime;
 
-use cache_recorder::CacheHit;
+mod cli;
+
+/// Reject a request whose path, query string or body can't be parsed.
+fn invalid_request(e: impl std::fmt::Display) -> actix_web::Error {
+    error::Error::InvalidRequest(e.to_string()).into()
+}
+
+/// The request line for the access log, with the value of a `token` query
+/// parameter masked, so the secrets of private articles don't end up in the logs.
+fn logged_request_line(req: &ServiceRequest) -> String {
+    let target = match req.query_string() {
+        "" => req.path().to_string(),
+        query => {
+            let params = query
+                .split('&')
+                .map(|param| if param.starts_with("token=") { "token=***" } else { param })
+                .collect::<Vec<_>>();
+            format!("{}?{}", req.path(), params.join("&"))
+        }
+    };
+    format!("{} {} {:?}", req.method(), target, req.version())
+}
 
 /// Health check endpoint to verify that the server is running.
 #[get("/health")]
@@ -20,40 +55,330 @@ async fn health_check() -> impl actix_web::Responder {
         success: true,
         data: "Server is running",
         message: None,
+        error_code: None,
     })
 }
 
+/// Create an independent Articles instance for each named section.
+fn load_sections(shared: &config::SharedConfig) -> std::io::Result<Vec<(String, Articles)>> {
+    let config = shared.load();
+    let mut sections = Vec::new();
+    for (name, section) in &config.sections {
+        if RESERVED_SECTION_NAMES.contains(&name.as_str()) {
+            error!("Section name '{}' is reserved and will be ignored", name);
+            continue;
+        }
+        info!("Loading section '{}'", name);
+        let mut builder = Articles::builder()
+            .config(Arc::clone(shared))
+            .source_dirs(section.source_dirs())
+            .sample_article(section.sample_article)
+            .pinned_articles(section.pinned_articles.clone());
+        if let Some(comments) = open_comments(shared, Some(name))? {
+            builder = builder.comments(comments);
+        }
+        // The configured SQLite index belongs to the main articles
+        #[cfg(feature = "sqlite")]
+        let builder = builder.sql_index(None);
+        let section_articles = builder.build().map_err(std::io::Error::other)?;
+        sections.push((name.clone(), section_articles));
+    }
+    Ok(sections)
+}
+
+/// Open the comments of `section`, or of the main articles, if comments are enabled.
+fn open_comments(
+    shared: &config::SharedConfig,
+    section: Option<&str>,
+) -> std::io::Result<Option<Arc<Comments>>> {
+    let Some(settings) = &shared.load().comments else {
+        return Ok(None);
+    };
+    let comments = Comments::open(settings, section, Arc::clone(shared))
+        .map_err(std::io::Error::other)?;
+    Ok(Some(Arc::new(comments)))
+}
+
+/// Validate the content of the main index and every section, print the problems
+/// found and exit with status 1 if there are any.
+fn validate_content(
+    shared: &config::SharedConfig,
+    cache: articles::CacheShards,
+) -> std::io::Result<()> {
+    // Validation scans the store itself, so the index is never needed
+    let builder = Articles::builder()
+        .config(Arc::clone(shared))
+        .cache(cache)
+        .lazy_index(true);
+    #[cfg(feature = "sqlite")]
+    let builder = builder.sql_index(None);
+    let articles = builder.build().map_err(std::io::Error::other)?;
+    let mut targets = vec![("articles".to_string(), articles)];
+    targets.extend(load_sections(shared)?);
+
+    let mut valid = true;
+    for (name, articles) in &targets {
+        let report = articles.validate().map_err(std::io::Error::other)?;
+        for problem in &report.problems {
+            println!(
+                "{}: {} [{}] {}",
+                name,
+                problem.path,
+                problem.problem.map(|p| p.to_string()).unwrap_or_default(),
+                problem.message.as_deref().unwrap_or_default()
+            );
+        }
+        println!(
+            "{}: {} articles, {} problems",
+            name,
+            report.articles,
+            report.problems.len()
+        );
+        valid &= report.valid;
+    }
+
+    if !valid {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let cli = cli::Cli::parse();
+
     // Initialize the logger
-    env_logger::init();
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(filter) = &cli.log_level {
+        logger.parse_filters(filter);
+    }
+    logger.init();
 
     // Load configuration
-    let config = &*config::CONFIG;
-    info!("Config loaded: {:?}", config);
+    let loaded = cli.config_source().and_then(|source| {
+        let (config, warnings) = config::load(&source)?;
+        Ok((source, config, warnings))
+    });
+    let (source, config, warnings) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    debug!("Config loaded: {:?}", config);
+    let shared_config = config::shared(config);
+    let config = shared_config.load_full();
+
+    // `henkaiki --check-config` only checks that the configuration loads
+    if cli.check_config {
+        println!("Configuration OK\n{}", config.summary());
+        return Ok(());
+    }
+    info!("Configuration:\n{}", config.summary());
 
-    // Initialize the shared LruCache for articles
-    let cache = Arc::new(Mutex::new(LruCache::new(
-        config.mainconfig.max_cached_articles,
-    )));
+    // Initialize the shared cache for articles
+    let cache = articles::CacheShards::new(config.mainconfig.max_cached_articles);
+
+    // `henkaiki validate` checks the content and exits instead of serving it
+    if let Some(cli::Command::Validate) = cli.command {
+        return validate_content(&shared_config, cache);
+    }
 
     // Create the shared Articles instance
-    let articles_instance = Articles::new(
-        config.mainconfig.articles_dir.clone().into(),
-        Arc::clone(&cache),
-    );
+    let mut builder = Articles::builder().config(Arc::clone(&shared_config)).cache(cache.clone());
+    if let Some(comments) = open_comments(&shared_config, None)? {
+        builder = builder.comments(comments);
+    }
+    let articles_instance = builder.build().map_err(std::io::Error::other)?;
+    let sections = load_sections(&shared_config)?;
+
+    // Optionally refresh the indices in the background
+    if let Some(seconds) = config.mainconfig.refresh_interval_seconds {
+        let interval = std::time::Duration::from_secs(seconds);
+        refresher::spawn_periodic_refresh(articles_instance.clone(), interval);
+        for (_, section_articles) in &sections {
+            refresher::spawn_periodic_refresh(section_articles.clone(), interval);
+        }
+    }
+
+    // Optionally serve the gRPC service on a second port
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_port) = config.mainconfig.grpc_port {
+        use std::net::ToSocketAddrs;
+        let addr = (config.mainconfig.address.as_str(), grpc_port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| std::io::Error::other("gRPC address resolved to nothing"))?;
+        grpc::spawn_server(articles_instance.clone(), addr);
+    }
+
+    // Optionally federate the main articles as an ActivityPub actor
+    #[cfg(feature = "activitypub")]
+    let federation = match &config.activitypub {
+        Some(activitypub_config) => {
+            let federation = activitypub::Federation::from_config(
+                activitypub_config,
+                &config.mainconfig.path_prefix,
+            )
+            .map_err(std::io::Error::other)?;
+            let federation = web::Data::new(federation);
+            activitypub::spawn_publisher(federation.clone().into_inner(), articles_instance.clone());
+            Some(federation)
+        }
+        None => None,
+    };
+
+    // Optionally send webmentions for the links of new and changed articles
+    #[cfg(feature = "webmention")]
+    if let Some(webmention_config) = &config.webmention {
+        let webmentions = Arc::new(webmention::Webmentions::from_config(webmention_config));
+        webmention::spawn_sender(webmentions, articles_instance.clone());
+    }
+
+    // Reload the configuration on SIGHUP or through the admin API
+    let mut reloaded_articles = vec![articles_instance.clone()];
+    reloaded_articles.extend(sections.iter().map(|(_, section_articles)| section_articles.clone()));
+    let reloader = web::Data::new(reload::ConfigReloader::new(
+        source,
+        Arc::clone(&shared_config),
+        reloaded_articles,
+    ));
+    #[cfg(unix)]
+    reload::spawn_signal_handler(reloader.clone().into_inner());
+
+    let start_time = web::Data::new(StartTime::now());
+    let request_stats = web::Data::new(RequestStats::new(Arc::clone(&shared_config)));
+    let compressed_cache = config
+        .mainconfig
+        .compressed_cache_bytes
+        .map(|max_bytes| web::Data::new(CompressedCache::new(max_bytes)));
+
+    #[cfg(feature = "graphql")]
+    let graphql_schema = web::Data::new(api::graphql::schema());
 
-    // Construct shared cache recorder
-    let cache_recorder = web::Data::new(Mutex::new(CacheHit::new()));
+    // Optionally count reactions, each section apart from the main articles
+    let load_reactions = |section: Option<&str>| -> std::io::Result<_> {
+        let Some(reactions_config) = &config.reactions else {
+            return Ok(None);
+        };
+        let path = reactions_config.path_for(section);
+        let reactions = Reactions::load(path, Arc::clone(&shared_config))
+            .map_err(std::io::Error::other)?;
+        Ok(Some(web::Data::new(reactions)))
+    };
+    let reactions = load_reactions(None)?;
+    let section_reactions = sections
+        .iter()
+        .map(|(name, _)| load_reactions(Some(name)))
+        .collect::<std::io::Result<Vec<_>>>()?;
 
     // Start the HTTP server
+    let path_prefix = config.mainconfig.path_prefix.clone();
     HttpServer::new(move || {
-        App::new()
-            .wrap(middleware::Logger::default())
+        let revision_source = articles_instance.clone();
+        let logged_config = Arc::clone(&shared_config);
+        let recorded_stats = request_stats.clone();
+        let mut app = App::new()
+            .wrap_fn(move |req, srv| {
+                // Record which content revision served each response
+                let revision = revision_source.revision();
+                let response = srv.call(req);
+                async move {
+                    let mut response = response.await?;
+                    if let Some(value) = revision.and_then(|r| HeaderValue::from_str(&r).ok()) {
+                        response
+                            .headers_mut()
+                            .insert(HeaderName::from_static("x-content-revision"), value);
+                    }
+                    Ok(response)
+                }
+            })
+            .wrap_fn(move |req, srv| {
+                // Time every request for the statistics by route
+                let pattern = req.match_pattern().unwrap_or_else(|| "(unmatched)".to_string());
+                let route = format!("{} {}", req.method(), pattern);
+                let stats = recorded_stats.clone();
+                let started = Instant::now();
+                let response = srv.call(req);
+                async move {
+                    let response = response.await;
+                    let status = match &response {
+                        Ok(response) => response.status(),
+                        Err(e) => e.as_response_error().status_code(),
+                    };
+                    stats.record(&route, status.as_u16(), started.elapsed());
+                    response
+                }
+            })
+            // The default format, with the client's address seen past trusted proxies
+            // and article tokens masked in the request line
+            .wrap(
+                middleware::Logger::new(
+                    r#"%{client}xi "%{request}xi" %s %b "%{Referer}i" "%{User-Agent}i" %T"#,
+                )
+                .custom_request_replace("client", move |req| {
+                    proxy::client_ip(req.head(), &logged_config.load().proxy)
+                        .map_or_else(|| "-".to_string(), |ip| ip.to_string())
+                })
+                .custom_request_replace("request", logged_request_line),
+            )
             .app_data(web::Data::new(articles_instance.clone()))
-            .app_data(cache_recorder.clone())
-            .service(health_check)
-            .configure(api::v1::config)
+            .app_data(reloader.clone())
+            .app_data(start_time.clone())
+            .app_data(request_stats.clone())
+            // Malformed paths, query strings and bodies get the usual error envelope
+            .app_data(web::PathConfig::default().error_handler(|e, _| invalid_request(e)))
+            .app_data(web::QueryConfig::default().error_handler(|e, _| invalid_request(e)))
+            .app_data(web::JsonConfig::default().error_handler(|e, _| invalid_request(e)));
+        if let Some(compressed_cache) = &compressed_cache {
+            app = app.app_data(compressed_cache.clone());
+        }
+        if let Some(reactions) = &reactions {
+            app = app.app_data(reactions.clone());
+        }
+        // Every route is served below `path_prefix`, which may be empty
+        let mut routes = web::scope(&path_prefix).service(health_check);
+        // Section scopes must be registered before the catch-all API scopes
+        for ((name, section_articles), reactions) in sections.iter().zip(&section_reactions) {
+            routes = routes
+                .service(api::v1::section_scope(name, section_articles.clone(), reactions.clone()))
+                .service(api::v2::section_scope(
+                    &path_prefix,
+                    name,
+                    section_articles.clone(),
+                    reactions.clone(),
+                ));
+            #[cfg(feature = "graphql")]
+            {
+                routes =
+                    routes.service(api::graphql::section_resource(name, section_articles.clone()));
+            }
+        }
+        #[cfg(feature = "graphql")]
+        {
+            app = app.app_data(graphql_schema.clone());
+            routes = routes.configure(api::graphql::config);
+        }
+        #[cfg(feature = "dashboard")]
+        {
+            routes = routes.configure(api::dashboard::config);
+        }
+        #[cfg(feature = "activitypub")]
+        if let Some(federation) = &federation {
+            app = app.app_data(federation.clone());
+            routes = routes.configure(activitypub::config);
+        }
+        let v2_prefix = path_prefix.clone();
+        app.service(
+            routes
+                .configure(api::shortlink::config)
+                .configure(api::v1::config)
+                .configure(move |cfg| api::v2::config(cfg, &v2_prefix)),
+        )
     })
     .bind((config.mainconfig.address.clone(), config.mainconfig.port))?
     .run()
@@ -0,0 +1,82 @@
+//! Requests to other servers on behalf of whoever named them, such as the key
+//! and inbox URLs of remote ActivityPub actors or the endpoints of webmention
+//! targets. Hosts resolving to loopback, private, link-local or otherwise
+//! internal addresses are refused, redirects included, so that nobody can aim
+//! the server at its own network.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ureq::{
+    config::Config,
+    http::Uri,
+    unversioned::{
+        resolver::{DefaultResolver, ResolvedSocketAddrs, Resolver},
+        transport::{DefaultConnector, NextTimeout},
+    },
+    Agent,
+};
+
+/// An agent reaching public addresses only, with `config`.
+pub(crate) fn agent(config: Config) -> Agent {
+    Agent::with_parts(config, DefaultConnector::default(), PublicResolver::default())
+}
+
+/// Resolves hosts as usual, failing for any that has an address not reachable
+/// from the internet.
+#[derive(Debug, Default)]
+struct PublicResolver(DefaultResolver);
+
+impl Resolver for PublicResolver {
+    fn resolve(
+        &self,
+        uri: &Uri,
+        config: &Config,
+        timeout: NextTimeout,
+    ) -> Result<ResolvedSocketAddrs, ureq::Error> {
+        let addrs = self.0.resolve(uri, config, timeout)?;
+        if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            let host = uri.host().unwrap_or_default();
+            return Err(ureq::Error::Other(
+                format!("{} resolves to the internal address {}", host, addr.ip()).into(),
+            ));
+        }
+        Ok(addrs)
+    }
+}
+
+/// Whether `ip` is reachable from the internet, rather than the host itself, its
+/// network or a range set aside for special use.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network", carrier-grade NAT and the reserved 240.0.0.0/4
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local fc00::/7 and link-local fe80::/10
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80)
+}
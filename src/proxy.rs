@@ -0,0 +1,151 @@
+//! Requests arriving through reverse proxies such as nginx or Cloudflare. Their
+//! `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` headers are only
+//! honored on connections from the `[proxy]` section's trusted ranges, since any
+//! client can send them.
+
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use actix_web::{dev::RequestHead, http::header};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// An IP address, or a network in CIDR notation such as `10.0.0.0/8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix)).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix)).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+
+    fn max_prefix(network: IpAddr) -> u8 {
+        if network.is_ipv4() { 32 } else { 128 }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid IP address or range '{}'", s);
+        let (network, prefix) = match s.split_once('/') {
+            Some((network, prefix)) => (network, Some(prefix)),
+            None => (s, None),
+        };
+        let network = network.parse::<IpAddr>().map_err(|_| invalid())?.to_canonical();
+        let max_prefix = Self::max_prefix(network);
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok().filter(|&p| p <= max_prefix).ok_or_else(invalid)?,
+            None => max_prefix,
+        };
+        Ok(IpRange { network, prefix })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<IpRange> for String {
+    fn from(range: IpRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.prefix == Self::max_prefix(self.network) {
+            write!(f, "{}", self.network)
+        } else {
+            write!(f, "{}/{}", self.network, self.prefix)
+        }
+    }
+}
+
+impl config::Proxy {
+    fn trusts(&self, ip: IpAddr) -> bool {
+        self.trusted.iter().any(|range| range.contains(ip))
+    }
+
+    /// The peer of the connection, if it is a trusted proxy.
+    fn trusted_peer(&self, head: &RequestHead) -> Option<IpAddr> {
+        head.peer_addr.map(|peer| peer.ip()).filter(|&peer| self.trusts(peer))
+    }
+}
+
+/// The address of the client that sent a request: the peer of the connection, or,
+/// when that is a trusted proxy, the nearest address in `X-Forwarded-For` that
+/// isn't one.
+pub fn client_ip(head: &RequestHead, proxy: &config::Proxy) -> Option<IpAddr> {
+    let peer = head.peer_addr?.ip();
+    if !proxy.trusts(peer) {
+        return Some(peer);
+    }
+    let hops = head
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    let mut client = peer;
+    for hop in hops.into_iter().rev() {
+        // Some proxies add the client's port to IPv4 addresses
+        let Some(ip) = hop
+            .parse::<IpAddr>()
+            .ok()
+            .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        else {
+            break;
+        };
+        client = ip;
+        if !proxy.trusts(ip) {
+            break;
+        }
+    }
+    Some(client)
+}
+
+/// The scheme and host a client addressed, as in `https://blog.example.com`, when
+/// the request came through a trusted proxy: from `X-Forwarded-Proto` (`http` if
+/// absent) and `X-Forwarded-Host` or else `Host`.
+pub fn forwarded_origin(head: &RequestHead, proxy: &config::Proxy) -> Option<String> {
+    proxy.trusted_peer(head)?;
+    let first = |name: &str| {
+        let value = head.headers().get(name)?.to_str().ok()?;
+        Some(value.split(',').next()?.trim()).filter(|value| !value.is_empty())
+    };
+    let scheme = match first("x-forwarded-proto").map(str::to_ascii_lowercase) {
+        None => "http".to_string(),
+        Some(scheme) if scheme == "http" || scheme == "https" => scheme,
+        Some(_) => return None,
+    };
+    let host = first("x-forwarded-host").or_else(|| first(header::HOST.as_str()))?;
+    let valid = |c: char| c.is_ascii_alphanumeric() || "-.:[]".contains(c);
+    host.chars()
+        .all(valid)
+        .then(|| format!("{}://{}", scheme, host.to_ascii_lowercase()))
+}
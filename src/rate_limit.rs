@@ -0,0 +1,47 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::error::Error;
+
+/// How long the actions of one client are counted over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits how often each client address may do something, such as adding a
+/// reaction or posting a comment, counting its actions over a fixed window of a
+/// minute. The limit is given with every action, so it follows reloads.
+#[derive(Default)]
+pub struct RateLimiter {
+    /// When each client's current window started, and the actions it counted since.
+    clients: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Count an action of `client` against its window, failing with
+    /// [`Error::RateLimited`] once it took `max_per_minute` within a minute; `what`
+    /// names the actions in the message, e.g. `reactions`.
+    pub fn check(&self, client: IpAddr, max_per_minute: u32, what: &str) -> Result<(), Error> {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap();
+        if !clients.contains_key(&client) {
+            clients.retain(|_, (started, _)| now.duration_since(*started) < RATE_WINDOW);
+        }
+        let (started, counted) = clients.entry(client).or_insert((now, 0));
+        if now.duration_since(*started) >= RATE_WINDOW {
+            (*started, *counted) = (now, 0);
+        }
+        if *counted >= max_per_minute {
+            let wait = RATE_WINDOW.saturating_sub(now.duration_since(*started));
+            return Err(Error::RateLimited(format!(
+                "Too many {}; try again in {} seconds",
+                what,
+                wait.as_secs().max(1)
+            )));
+        }
+        *counted += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use std::{collections::BTreeMap, fs, net::IpAddr, path::PathBuf, sync::Mutex};
+
+use crate::{articles::ArticleId, config::SharedConfig, error::Error, rate_limit::RateLimiter};
+
+/// The number of reactions of each kind an article received, by kind.
+pub type ReactionCounts = BTreeMap<String, u64>;
+
+/// Counts the reactions readers add to articles, such as likes, so that a static
+/// site gets simple engagement without a third-party service. The counts are kept
+/// in a JSON file, rewritten after every reaction; the kinds accepted and the
+/// rate limit are read from the `[reactions]` settings in effect.
+pub struct Reactions {
+    path: PathBuf,
+    config: SharedConfig,
+    counts: Mutex<BTreeMap<ArticleId, ReactionCounts>>,
+    limiter: RateLimiter,
+}
+
+impl Reactions {
+    /// Load the counts kept at `path`, starting from none if it doesn't exist yet.
+    pub fn load(path: PathBuf, config: SharedConfig) -> Result<Self> {
+        let counts = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Invalid reactions file {:?}", path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        };
+        Ok(Reactions {
+            path,
+            config,
+            counts: Mutex::new(counts),
+            limiter: RateLimiter::default(),
+        })
+    }
+
+    /// The reactions `article_id` received, by kind; empty if none.
+    pub fn counts(&self, article_id: ArticleId) -> ReactionCounts {
+        self.counts.lock().unwrap().get(&article_id).cloned().unwrap_or_default()
+    }
+
+    /// Add a reaction of `kind` to `article_id` from `client`, returning the
+    /// article's counts with it. Fails with [`Error::InvalidRequest`] for a kind
+    /// that isn't accepted, and with [`Error::RateLimited`] once `client` added
+    /// `max_per_minute` reactions within a minute.
+    pub fn add(
+        &self,
+        article_id: ArticleId,
+        kind: &str,
+        client: Option<IpAddr>,
+    ) -> Result<ReactionCounts> {
+        let max_per_minute = match &self.config.load().reactions {
+            Some(reactions) if reactions.accepts(kind) => reactions.max_per_minute,
+            Some(reactions) => {
+                return Err(Error::InvalidRequest(format!(
+                    "unknown reaction '{}' (expected one of: {})",
+                    kind,
+                    reactions.kinds.join(", ")
+                ))
+                .into())
+            }
+            None => return Err(Error::InvalidRequest("reactions are disabled".into()).into()),
+        };
+        if let Some(client) = client {
+            self.limiter.check(client, max_per_minute, "reactions")?;
+        }
+
+        let mut counts = self.counts.lock().unwrap();
+        *counts.entry(article_id).or_default().entry(kind.to_string()).or_default() += 1;
+        let article_counts = counts[&article_id].clone();
+        self.save(&counts)?;
+        Ok(article_counts)
+    }
+
+    fn save(&self, counts: &BTreeMap<ArticleId, ReactionCounts>) -> Result<()> {
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(counts)?)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
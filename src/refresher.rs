@@ -0,0 +1,44 @@
+use crate::articles::Articles;
+use actix_web::{rt, web};
+use log::{error, info};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Maximum factor by which the refresh delay grows after consecutive failures.
+const MAX_BACKOFF_FACTOR: u32 = 8;
+
+/// Spawn a background task that periodically refreshes the article index.
+///
+/// Each delay is randomized by up to 10% so that multiple instances sharing the same
+/// content don't rescan in lockstep, and consecutive failures back off exponentially.
+pub fn spawn_periodic_refresh(articles: Articles, interval: Duration) {
+    info!("Refreshing the article index every {:?}", interval);
+    rt::spawn(async move {
+        let mut backoff_factor = 1;
+        loop {
+            rt::time::sleep(with_jitter(interval * backoff_factor)).await;
+
+            let articles = articles.clone();
+            match web::block(move || articles.refresh_index()).await {
+                Ok(Ok(())) => backoff_factor = 1,
+                Ok(Err(e)) => {
+                    error!("Periodic index refresh failed: {:?}", e);
+                    backoff_factor = (backoff_factor * 2).min(MAX_BACKOFF_FACTOR);
+                }
+                Err(e) => {
+                    error!("Periodic index refresh task failed: {:?}", e);
+                    backoff_factor = (backoff_factor * 2).min(MAX_BACKOFF_FACTOR);
+                }
+            }
+        }
+    });
+}
+
+/// Add a pseudo-random jitter of up to 10% to `delay`.
+fn with_jitter(delay: Duration) -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let max_jitter = delay / 10;
+    delay + max_jitter.mul_f64(f64::from(nanos % 1000) / 1000.0)
+}
@@ -0,0 +1,78 @@
+use crate::{
+    articles::Articles,
+    config::{self, Config, ConfigSource, SharedConfig},
+};
+use anyhow::Result;
+use log::info;
+use std::sync::Arc;
+
+/// Reloads `config.toml` while the server runs, and applies it to every set of
+/// articles being served.
+///
+/// Settings read on every use (Markdown extensions, cache limits, pagination
+/// defaults, API keys, ...) take effect at once; those only read at startup, such as
+/// the storage backend, sections and background tasks, still need a restart.
+pub struct ConfigReloader {
+    source: ConfigSource,
+    /// The configuration shared by `articles`, replaced on reload.
+    config: SharedConfig,
+    articles: Vec<Articles>,
+}
+
+impl ConfigReloader {
+    pub fn new(source: ConfigSource, config: SharedConfig, articles: Vec<Articles>) -> Self {
+        Self {
+            source,
+            config,
+            articles,
+        }
+    }
+
+    /// Where the configuration is read from, and the command line overrides.
+    pub fn source(&self) -> &ConfigSource {
+        &self.source
+    }
+
+    /// The configuration in effect.
+    pub fn config(&self) -> Arc<Config> {
+        self.config.load_full()
+    }
+
+    /// Reload the configuration and apply it to the articles.
+    pub fn reload(&self) -> Result<config::Reload> {
+        let reload = config::reload(&self.source, &self.config)?;
+        for articles in &self.articles {
+            articles.apply_config();
+        }
+        info!("Reloaded the configuration");
+        Ok(reload)
+    }
+}
+
+/// Spawn a background task reloading the configuration whenever the process
+/// receives `SIGHUP`.
+#[cfg(unix)]
+pub fn spawn_signal_handler(reloader: Arc<ConfigReloader>) {
+    use actix_web::{rt, web};
+    use log::error;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            error!("Failed to listen for SIGHUP, only the API can reload the configuration: {}", e);
+            return;
+        }
+    };
+    rt::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("Received SIGHUP, reloading the configuration");
+            let reloader = Arc::clone(&reloader);
+            match web::block(move || reloader.reload()).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => error!("Failed to reload the configuration: {:#}", e),
+                Err(e) => error!("Configuration reload task failed: {:?}", e),
+            }
+        }
+    });
+}
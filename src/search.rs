@@ -0,0 +1,332 @@
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{articles::Metainfo, config, error::Error};
+
+/// The article fields a search query is matched against; all of them by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SearchFields {
+    pub title: bool,
+    pub description: bool,
+    pub tags: bool,
+    pub keywords: bool,
+}
+
+impl Default for SearchFields {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl SearchFields {
+    pub const ALL: SearchFields = SearchFields {
+        title: true,
+        description: true,
+        tags: true,
+        keywords: true,
+    };
+
+    /// The names accepted by [`SearchFields::parse`].
+    pub const NAMES: &'static [&'static str] = &["title", "description", "tags", "keywords"];
+
+    /// Parse a comma-separated list of field names such as `tags,keywords`,
+    /// rejecting unknown names. An absent or empty list selects every field.
+    pub fn parse(fields: Option<&str>) -> Result<Self, Error> {
+        let mut names = fields
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .peekable();
+        if names.peek().is_none() {
+            return Ok(Self::ALL);
+        }
+        let mut selected = SearchFields {
+            title: false,
+            description: false,
+            tags: false,
+            keywords: false,
+        };
+        for name in names {
+            match name {
+                "title" => selected.title = true,
+                "description" => selected.description = true,
+                "tags" => selected.tags = true,
+                "keywords" => selected.keywords = true,
+                _ => {
+                    return Err(Error::InvalidRequest(format!(
+                        "unknown search field '{}' (expected one of: {})",
+                        name,
+                        Self::NAMES.join(", ")
+                    )))
+                }
+            }
+        }
+        Ok(selected)
+    }
+
+    /// The names of the selected fields.
+    pub fn names(&self) -> Vec<&'static str> {
+        let selected = [self.title, self.description, self.tags, self.keywords];
+        Self::NAMES
+            .iter()
+            .zip(selected)
+            .filter_map(|(name, selected)| selected.then_some(*name))
+            .collect()
+    }
+
+    /// The folded text of the selected fields of an article, each tag and keyword
+    /// apart, with the weight a match in it adds to the article's relevance.
+    fn texts<'a>(&self, metainfo: &'a Metainfo) -> Vec<(f64, &'a str)> {
+        let text = &metainfo.search_text;
+        let mut texts = Vec::new();
+        if self.title {
+            texts.push((TITLE_WEIGHT, text.title.as_str()));
+        }
+        if self.description {
+            texts.push((DESCRIPTION_WEIGHT, text.description.as_str()));
+        }
+        if self.tags {
+            texts.extend(text.tags.iter().map(|tag| (TAG_WEIGHT, tag.as_str())));
+        }
+        if self.keywords {
+            texts.extend(text.keywords.iter().map(|keyword| (KEYWORD_WEIGHT, keyword.as_str())));
+        }
+        texts
+    }
+
+    /// Whether the selected fields of an article match `matcher`.
+    pub fn matches(&self, metainfo: &Metainfo, matcher: &Matcher) -> bool {
+        let texts = self.texts(metainfo);
+        match matcher {
+            Matcher::Substring(query) => texts.iter().any(|(_, text)| text.contains(query.as_str())),
+            Matcher::Terms { analyzer, terms } => {
+                let words = texts
+                    .iter()
+                    .flat_map(|(_, text)| analyzer.analyze(text))
+                    .collect::<Vec<_>>();
+                terms
+                    .iter()
+                    .all(|term| words.iter().any(|word| word.starts_with(term.as_str())))
+            }
+        }
+    }
+
+    /// How well an article matches `matcher`: the weights of the fields holding the
+    /// query (or each of its terms), so a match in the title counts most.
+    pub fn relevance(&self, metainfo: &Metainfo, matcher: &Matcher) -> f64 {
+        self.texts(metainfo)
+            .into_iter()
+            .map(|(weight, text)| weight * matcher.hits(text) as f64)
+            .sum()
+    }
+}
+
+/// The searched fields of an article folded to lowercase, computed once when the
+/// article is indexed rather than for every query.
+#[derive(PartialEq)]
+pub struct SearchText {
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+impl SearchText {
+    pub fn new(title: &str, description: &str, tags: &[String], keywords: &[String]) -> Self {
+        Self {
+            title: title.to_lowercase(),
+            description: description.to_lowercase(),
+            tags: tags.iter().map(|tag| tag.to_lowercase()).collect(),
+            keywords: keywords.iter().map(|keyword| keyword.to_lowercase()).collect(),
+        }
+    }
+}
+
+// A match in the title says most about what an article is about, one in its
+// description least.
+const TITLE_WEIGHT: f64 = 3.0;
+const TAG_WEIGHT: f64 = 2.0;
+const KEYWORD_WEIGHT: f64 = 2.0;
+const DESCRIPTION_WEIGHT: f64 = 1.0;
+
+/// A search query, ready to be matched against article text as the `[search]`
+/// settings say.
+pub enum Matcher {
+    /// The query, folded to lowercase, must occur in the folded text.
+    Substring(String),
+    /// Each analyzed term of the query must start an analyzed word of the text.
+    Terms { analyzer: Analyzer, terms: Vec<String> },
+}
+
+impl Matcher {
+    pub fn new(query: &str, settings: &config::Search) -> Self {
+        match Analyzer::new(settings) {
+            Some(analyzer) => {
+                let terms = analyzer.query_terms(query);
+                Matcher::Terms { analyzer, terms }
+            }
+            None => Matcher::Substring(query.to_lowercase()),
+        }
+    }
+
+    /// How many of the query terms occur in the folded `text`. The words of a
+    /// substring query are counted apart, as the SQLite index matches them.
+    fn hits(&self, text: &str) -> usize {
+        match self {
+            Matcher::Substring(query) => {
+                query.split_whitespace().filter(|word| text.contains(word)).count()
+            }
+            Matcher::Terms { analyzer, terms } => {
+                let words = analyzer.analyze(text);
+                terms
+                    .iter()
+                    .filter(|term| words.iter().any(|word| word.starts_with(term.as_str())))
+                    .count()
+            }
+        }
+    }
+}
+
+/// The factor a search score is multiplied by for an article dated `date`
+/// (YYYYMMDD) on day `today`: 1 for a new article, falling as `1 / (1 + decay * years)`
+/// with its age, so 0 leaves scores as they are.
+pub fn recency_boost(date: u32, today: u32, decay: f64) -> f64 {
+    if decay <= 0.0 {
+        return 1.0;
+    }
+    let age_days = (days_from_civil(today) - days_from_civil(date)).max(0);
+    1.0 / (1.0 + decay * age_days as f64 / 365.25)
+}
+
+/// Today's date as YYYYMMDD (UTC).
+pub fn today() -> u32 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400) as i64;
+    civil_from_days(days)
+}
+
+/// The number of days from 1970-01-01 to `date` (YYYYMMDD), in the proleptic
+/// Gregorian calendar.
+fn days_from_civil(date: u32) -> i64 {
+    let (year, month, day) = ((date / 10000) as i64, (date / 100 % 100) as i64, (date % 100) as i64);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date (YYYYMMDD) `days` days after 1970-01-01; the inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> u32 {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year * 10000 + month * 100 + day) as u32
+}
+
+/// The language search analyzes words for, set by `language` in `[search]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// No analysis: queries match plain substrings.
+    #[default]
+    None,
+    English,
+    German,
+    French,
+    Spanish,
+    Italian,
+}
+
+/// Turns text into the words search matches: lowercased, without stop words and
+/// reduced to their stems, so "Caching" and "caches" both become "cach".
+pub struct Analyzer {
+    stemmer: Stemmer,
+    stop_words: &'static [&'static str],
+}
+
+impl Analyzer {
+    /// The analyzer the `[search]` settings select, or `None` for substring matching.
+    pub fn new(settings: &config::Search) -> Option<Self> {
+        let (algorithm, stop_words) = match settings.language {
+            Language::None => return None,
+            Language::English => (Algorithm::English, ENGLISH_STOP_WORDS),
+            Language::German => (Algorithm::German, GERMAN_STOP_WORDS),
+            Language::French => (Algorithm::French, FRENCH_STOP_WORDS),
+            Language::Spanish => (Algorithm::Spanish, SPANISH_STOP_WORDS),
+            Language::Italian => (Algorithm::Italian, ITALIAN_STOP_WORDS),
+        };
+        Some(Self {
+            stemmer: Stemmer::create(algorithm),
+            stop_words: if settings.stop_words { stop_words } else { &[] },
+        })
+    }
+
+    /// The stems of the words of `text` that aren't stop words.
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        self.stems(text, true)
+    }
+
+    /// The terms of a search `query`. A query made of stop words alone keeps them,
+    /// rather than matching every article.
+    pub fn query_terms(&self, query: &str) -> Vec<String> {
+        let terms = self.stems(query, true);
+        if terms.is_empty() {
+            self.stems(query, false)
+        } else {
+            terms
+        }
+    }
+
+    fn stems(&self, text: &str, drop_stop_words: bool) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .filter(|word| !(drop_stop_words && self.stop_words.contains(&word.as_str())))
+            .map(|word| self.stemmer.stem(&word).into_owned())
+            .collect()
+    }
+}
+
+// ===== STOP WORDS =====
+
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "how", "if", "in",
+    "into", "is", "it", "its", "of", "on", "or", "that", "the", "their", "then", "there",
+    "these", "this", "to", "was", "were", "what", "when", "which", "who", "why", "will", "with",
+];
+
+const GERMAN_STOP_WORDS: &[&str] = &[
+    "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "das", "dass", "dem", "den", "der",
+    "des", "die", "ein", "eine", "einem", "einen", "einer", "es", "für", "im", "in", "ist",
+    "mit", "nicht", "noch", "oder", "sich", "sie", "sind", "und", "von", "vom", "wie", "zu",
+    "zum", "zur",
+];
+
+const FRENCH_STOP_WORDS: &[&str] = &[
+    "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "est", "et",
+    "il", "la", "le", "les", "leur", "mais", "ne", "ou", "par", "pas", "pour", "qu", "que",
+    "qui", "sa", "se", "ses", "son", "sur", "un", "une", "d", "l",
+];
+
+const SPANISH_STOP_WORDS: &[&str] = &[
+    "a", "al", "como", "con", "de", "del", "el", "en", "es", "esta", "este", "la", "las",
+    "lo", "los", "más", "no", "o", "para", "pero", "por", "que", "se", "su", "sus", "un",
+    "una", "unos", "y",
+];
+
+const ITALIAN_STOP_WORDS: &[&str] = &[
+    "a", "al", "alla", "che", "come", "con", "da", "dei", "del", "della", "di", "e", "è", "gli",
+    "il", "in", "la", "le", "lo", "ma", "nel", "nella", "non", "o", "per", "più", "si", "su",
+    "un", "una", "uno", "l",
+];
@@ -0,0 +1,139 @@
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use serde_json::{Map, Value, json};
+
+use crate::{articles::Metainfo, config};
+
+/// A `<meta>` tag, keyed by `property` (OpenGraph) or `name` (Twitter Cards).
+pub struct MetaTag {
+    attribute: &'static str,
+    key: String,
+    content: String,
+}
+
+impl MetaTag {
+    fn property(key: &str, content: impl Into<String>) -> Self {
+        MetaTag {
+            attribute: "property",
+            key: key.to_string(),
+            content: content.into(),
+        }
+    }
+
+    fn name(key: &str, content: impl Into<String>) -> Self {
+        MetaTag {
+            attribute: "name",
+            key: key.to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+/// Serialized as the attributes of the tag, e.g. `{"property": "og:title", "content": "..."}`.
+impl Serialize for MetaTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry(self.attribute, &self.key)?;
+        map.serialize_entry("content", &self.content)?;
+        map.end()
+    }
+}
+
+/// The OpenGraph and Twitter Card tags describing an article, for link previews
+/// on social sites. The page URL is only included when `base_url` is configured.
+pub fn social_tags(metainfo: &Metainfo, config: &config::Main) -> Vec<MetaTag> {
+    let image = metainfo.cover_url(config);
+
+    let mut tags = vec![
+        MetaTag::property("og:type", "article"),
+        MetaTag::property("og:title", &*metainfo.title),
+        MetaTag::property("og:description", &*metainfo.description),
+    ];
+    if let Some(url) = metainfo.canonical_url(config) {
+        tags.push(MetaTag::property("og:url", url));
+    }
+    if let Some(image) = &image {
+        tags.push(MetaTag::property("og:image", image));
+    }
+    tags.push(MetaTag::property("article:published_time", iso_date(metainfo.date)));
+    for tag in metainfo.tags.iter() {
+        tags.push(MetaTag::property("article:tag", tag));
+    }
+
+    // A large image card needs an image to show
+    let card = if image.is_some() { "summary_large_image" } else { "summary" };
+    tags.push(MetaTag::name("twitter:card", card));
+    tags.push(MetaTag::name("twitter:title", &*metainfo.title));
+    tags.push(MetaTag::name("twitter:description", &*metainfo.description));
+    if let Some(image) = image {
+        tags.push(MetaTag::name("twitter:image", image));
+    }
+    tags
+}
+
+/// The schema.org `BlogPosting` describing an article, as JSON-LD for search
+/// engines' rich results.
+pub fn structured_data(metainfo: &Metainfo, config: &config::Main) -> Value {
+    let mut posting = Map::new();
+    posting.insert("@context".into(), json!("https://schema.org"));
+    posting.insert("@type".into(), json!("BlogPosting"));
+    posting.insert("headline".into(), json!(&*metainfo.title));
+    posting.insert("description".into(), json!(&*metainfo.description));
+    posting.insert("datePublished".into(), json!(iso_date(metainfo.date)));
+    let author = metainfo.author.as_deref().or(config.author.as_deref());
+    if let Some(author) = author {
+        posting.insert("author".into(), json!({"@type": "Person", "name": author}));
+    }
+    if !metainfo.keywords.is_empty() {
+        posting.insert("keywords".into(), json!(metainfo.keywords.join(", ")));
+    }
+    if let Some(url) = metainfo.canonical_url(config) {
+        posting.insert("url".into(), json!(url));
+        posting.insert("mainEntityOfPage".into(), json!(url));
+    }
+    if let Some(cover) = metainfo.cover_url(config) {
+        posting.insert("image".into(), json!(cover));
+    }
+    if let Some(license) = &metainfo.license {
+        posting.insert("license".into(), json!(&**license));
+    }
+    if let Some(source) = &metainfo.source {
+        posting.insert("isBasedOn".into(), json!(&**source));
+    }
+    if let Some(attribution) = &metainfo.attribution {
+        posting.insert("creditText".into(), json!(&**attribution));
+    }
+    Value::Object(posting)
+}
+
+/// Render tags as `<meta>` elements, one per line, followed by the structured data
+/// as a JSON-LD `<script>`, ready to go in a page's `<head>`.
+pub fn to_html(tags: &[MetaTag], structured_data: &Value) -> String {
+    let mut html: String = tags
+        .iter()
+        .map(|tag| {
+            format!(
+                "<meta {}=\"{}\" content=\"{}\">\n",
+                tag.attribute,
+                escape(&tag.key),
+                escape(&tag.content)
+            )
+        })
+        .collect();
+    // `</` would end the script early wherever it appears in a string
+    let json = structured_data.to_string().replace("</", "<\\/");
+    html.push_str(&format!("<script type=\"application/ld+json\">{}</script>\n", json));
+    html
+}
+
+/// A `YYYYMMDD` date as `YYYY-MM-DD`.
+fn iso_date(date: u32) -> String {
+    format!("{:04}-{:02}-{:02}", date / 10000, date / 100 % 100, date % 100)
+}
+
+/// Escape text for use in a double-quoted HTML attribute.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
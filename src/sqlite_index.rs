@@ -0,0 +1,167 @@
+use crate::{
+    articles::{ArticleId, Metainfo},
+    search::{Analyzer, SearchFields},
+};
+use anyhow::Result;
+use arc_swap::ArcSwapOption;
+use rusqlite::{params, Connection};
+use std::{path::Path, sync::{Arc, Mutex}};
+
+/// A SQLite copy of the article metadata (with an FTS5 table for search), rebuilt
+/// from the store on every index load and used to answer listing, tag filtering and
+/// search queries with SQL.
+pub struct SqliteIndex {
+    conn: Mutex<Connection>,
+    /// The analyzer the full-text table was last filled with, which queries must
+    /// then go through too; `None` for the words as written.
+    analyzer: ArcSwapOption<Analyzer>,
+}
+
+impl SqliteIndex {
+    /// Open (or create) the database at `path`; `:memory:` keeps it in memory.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS articles (
+                 id          INTEGER PRIMARY KEY,
+                 title       TEXT NOT NULL,
+                 description TEXT NOT NULL,
+                 date        INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS article_tags (
+                 article_id INTEGER NOT NULL REFERENCES articles(id),
+                 tag        TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS article_tags_by_tag ON article_tags (tag, article_id);
+             CREATE TABLE IF NOT EXISTS article_keywords (
+                 article_id INTEGER NOT NULL REFERENCES articles(id),
+                 keyword    TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS article_keywords_by_keyword
+                 ON article_keywords (keyword, article_id);
+             -- Refilled on every rebuild, so it's recreated in case its columns changed
+             DROP TABLE IF EXISTS articles_fts;
+             CREATE VIRTUAL TABLE articles_fts
+                 USING fts5(title, description, tags, keywords);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            analyzer: ArcSwapOption::empty(),
+        })
+    }
+
+    /// Replace the whole database contents with `metainfos` in a single transaction,
+    /// so concurrent queries see either the old or the new index. With an `analyzer`,
+    /// the full-text table holds the stems of the words rather than the words.
+    pub fn rebuild(&self, metainfos: &[Arc<Metainfo>], analyzer: Option<Analyzer>) -> Result<()> {
+        let text = |text: &str| match &analyzer {
+            Some(analyzer) => analyzer.analyze(text).join(" "),
+            None => text.to_string(),
+        };
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "DELETE FROM article_tags;
+             DELETE FROM article_keywords;
+             DELETE FROM articles;
+             DELETE FROM articles_fts;",
+        )?;
+        {
+            let mut insert_article = tx.prepare(
+                "INSERT INTO articles (id, title, description, date) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let mut insert_tag =
+                tx.prepare("INSERT INTO article_tags (article_id, tag) VALUES (?1, ?2)")?;
+            let mut insert_keyword =
+                tx.prepare("INSERT INTO article_keywords (article_id, keyword) VALUES (?1, ?2)")?;
+            let mut insert_fts = tx.prepare(
+                "INSERT INTO articles_fts (rowid, title, description, tags, keywords)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+
+            for m in metainfos {
+                insert_article.execute(params![m.id, &*m.title, &*m.description, m.date])?;
+                for tag in m.tags.iter() {
+                    insert_tag.execute(params![m.id, tag])?;
+                }
+                for keyword in m.keywords.iter() {
+                    insert_keyword.execute(params![m.id, keyword])?;
+                }
+                insert_fts.execute(params![
+                    m.id,
+                    text(&m.title),
+                    text(&m.description),
+                    text(&m.tags.join(" ")),
+                    text(&m.keywords.join(" "))
+                ])?;
+            }
+        }
+        tx.commit()?;
+        self.analyzer.store(analyzer.map(Arc::new));
+        Ok(())
+    }
+
+    /// All article IDs, sorted.
+    pub fn all_ids(&self) -> Result<Vec<ArticleId>> {
+        self.query_ids("SELECT id FROM articles ORDER BY id", [])
+    }
+
+    /// IDs of the articles carrying `tag`, sorted.
+    pub fn ids_by_tag(&self, tag: &str) -> Result<Vec<ArticleId>> {
+        self.query_ids(
+            "SELECT article_id FROM article_tags WHERE tag = ?1 ORDER BY article_id",
+            [tag],
+        )
+    }
+
+    /// IDs of the articles carrying `keyword`, sorted.
+    pub fn ids_by_keyword(&self, keyword: &str) -> Result<Vec<ArticleId>> {
+        self.query_ids(
+            "SELECT article_id FROM article_keywords WHERE keyword = ?1 ORDER BY article_id",
+            [keyword],
+        )
+    }
+
+    /// IDs of the articles matching a full-text `query` on the selected `fields`,
+    /// sorted. Every whitespace-separated term (or analyzed term) must match a word
+    /// prefix.
+    pub fn search_ids(&self, query: &str, fields: SearchFields) -> Result<Vec<ArticleId>> {
+        let terms = match &*self.analyzer.load() {
+            Some(analyzer) => analyzer.query_terms(query),
+            None => query.split_whitespace().map(str::to_string).collect(),
+        };
+        let fts_query = Self::to_fts_query(&terms);
+        if fts_query.is_empty() {
+            return self.all_ids();
+        }
+        let columns = fields.names();
+        if columns.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Restrict every term to the selected columns
+        let fts_query = format!("{{{}}} : ({})", columns.join(" "), fts_query);
+        self.query_ids(
+            "SELECT rowid FROM articles_fts WHERE articles_fts MATCH ?1 ORDER BY rowid",
+            [fts_query],
+        )
+    }
+
+    /// Quote each term so user input can never be interpreted as FTS5 syntax.
+    fn to_fts_query(terms: &[String]) -> String {
+        terms
+            .iter()
+            .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn query_ids<P: rusqlite::Params>(&self, sql: &str, params: P) -> Result<Vec<ArticleId>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(sql)?;
+        let ids = stmt
+            .query_map(params, |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<ArticleId>>>()?;
+        Ok(ids)
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::articles::Metainfo;
+
+/// What the articles add up to, computed from the index and the articles' Markdown.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContentStats {
+    pub articles: usize,
+    /// The words in the Markdown of every article.
+    pub words: usize,
+    /// The mean number of words per article, rounded down.
+    pub average_words: usize,
+    /// The number of articles carrying each tag.
+    pub tags: BTreeMap<String, usize>,
+    /// The number of articles published in each year.
+    pub years: BTreeMap<u32, usize>,
+    /// Articles whose Markdown couldn't be read, left out of the word counts.
+    pub unreadable: usize,
+}
+
+impl ContentStats {
+    /// Count an article, with its Markdown if it could be read.
+    pub fn add(&mut self, metainfo: &Metainfo, markdown: Option<&str>) {
+        self.articles += 1;
+        for tag in metainfo.tags.iter() {
+            *self.tags.entry(tag.clone()).or_default() += 1;
+        }
+        // Dates are written as YYYYMMDD
+        *self.years.entry(metainfo.date / 10_000).or_default() += 1;
+        match markdown {
+            Some(markdown) => self.words += count_words(markdown),
+            None => self.unreadable += 1,
+        }
+    }
+
+    /// Work out the averages once every article has been added.
+    pub fn finish(mut self) -> Self {
+        let counted = self.articles - self.unreadable;
+        self.average_words = self.words.checked_div(counted).unwrap_or(0);
+        self
+    }
+}
+
+/// The number of words in `markdown`: whitespace-separated runs holding at least
+/// one letter or digit, so that markup such as `#`, `-` or `|---|` isn't counted.
+pub fn count_words(markdown: &str) -> usize {
+    markdown
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
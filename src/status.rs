@@ -0,0 +1,66 @@
+use serde::Serialize;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::articles::{Articles, CacheOccupancy};
+
+/// When the server started, for reporting its uptime.
+pub struct StartTime {
+    instant: Instant,
+    /// In seconds since the UNIX epoch.
+    timestamp: u64,
+}
+
+impl StartTime {
+    pub fn now() -> Self {
+        Self {
+            instant: Instant::now(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        }
+    }
+}
+
+/// A snapshot of the server and one set of articles, for dashboards.
+#[derive(Serialize)]
+pub struct Status {
+    /// When the server started, in seconds since the UNIX epoch.
+    pub started_at: u64,
+    pub uptime_seconds: u64,
+    pub articles: usize,
+    pub tags: usize,
+    pub cache: CacheOccupancy,
+    pub index: IndexStatus,
+}
+
+/// The outcome of the last index load.
+#[derive(Serialize)]
+pub struct IndexStatus {
+    /// When it finished, in seconds since the UNIX epoch (0 before the first load).
+    pub refreshed_at: u64,
+    pub duration_ms: u64,
+    pub loaded: usize,
+    pub skipped: usize,
+    /// Entries that couldn't be loaded, such as malformed metadata or duplicate IDs.
+    pub errors: usize,
+}
+
+impl Status {
+    pub fn collect(articles: &Articles, start_time: &StartTime) -> Self {
+        let report = articles.last_scan_report();
+        Self {
+            started_at: start_time.timestamp,
+            uptime_seconds: start_time.instant.elapsed().as_secs(),
+            articles: articles.article_count(),
+            tags: articles.tag_count(),
+            cache: articles.cache_occupancy(),
+            index: IndexStatus {
+                refreshed_at: report.scanned_at,
+                duration_ms: report.duration_ms,
+                loaded: report.loaded,
+                skipped: report.skipped,
+                errors: report.failed,
+            },
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::{fs, path::Path};
+
+/// The name of the file holding ignore patterns for a directory and its subdirectories.
+pub const IGNORE_FILE_NAME: &str = ".henkaikiignore";
+
+/// Decides which entries a filesystem scan considers, from the configured
+/// `include`/`exclude` glob patterns. Patterns are matched against paths relative
+/// to the articles directory being scanned.
+pub struct ScanFilter {
+    /// When set, only articles matching one of these patterns are loaded.
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl ScanFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(build_glob_set(include.iter().map(String::as_str))?)
+        };
+        Ok(Self {
+            include,
+            exclude: build_glob_set(exclude.iter().map(String::as_str))?,
+        })
+    }
+
+    /// Whether an entry (and everything below it) is excluded.
+    pub fn excludes(&self, relative_path: &Path) -> bool {
+        self.exclude.is_match(relative_path)
+    }
+
+    /// Whether an article at `relative_path` passes the `include` patterns.
+    pub fn includes(&self, relative_path: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(relative_path))
+    }
+}
+
+/// The patterns of a `.henkaikiignore` file, which apply to the directory holding
+/// it and everything below.
+///
+/// One glob per line; blank lines and lines starting with `#` are ignored. As with
+/// `.gitignore`, a pattern without a `/` matches at any depth, a leading `/`
+/// anchors it to the file's directory, and a trailing `/` is ignored.
+pub struct IgnoreFile {
+    patterns: GlobSet,
+}
+
+impl IgnoreFile {
+    /// Load the ignore file of `dir`, if it has one.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(IGNORE_FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let line = line.trim_end_matches('/');
+                match line.strip_prefix('/') {
+                    Some(anchored) => anchored.to_string(),
+                    None if line.contains('/') => line.to_string(),
+                    None => format!("**/{}", line),
+                }
+            })
+            .collect::<Vec<_>>();
+        let patterns = build_glob_set(patterns.iter().map(String::as_str))
+            .with_context(|| format!("Invalid pattern in {:?}", path))?;
+        Ok(Some(Self { patterns }))
+    }
+
+    /// Whether a path relative to the ignore file's directory is ignored.
+    pub fn ignores(&self, relative_path: &Path) -> bool {
+        self.patterns.is_match(relative_path)
+    }
+}
+
+/// Compile glob patterns where `*` doesn't cross directory separators.
+fn build_glob_set<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid glob pattern '{}'", pattern))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
@@ -0,0 +1,114 @@
+use super::{ArticleStore, FsStore, ScanEntry};
+use crate::articles::Metainfo;
+use crate::config::{GitStorage, SharedConfig};
+use anyhow::{bail, Context, Result};
+use arc_swap::ArcSwapOption;
+use log::{info, warn};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// Serves articles from a local checkout of a git repository, pulling new commits
+/// on every scan so that publishing content is a `git push` followed by a refresh.
+///
+/// The repository uses the filesystem layout; the checkout is read through an
+/// [`FsStore`].
+pub struct GitStore {
+    url: String,
+    branch: String,
+    checkout_dir: PathBuf,
+    files: FsStore,
+    /// The commit currently checked out.
+    revision: ArcSwapOption<String>,
+}
+
+impl GitStore {
+    /// Clone the repository unless a checkout already exists.
+    pub fn new(config: &GitStorage, shared: SharedConfig) -> Result<Self> {
+        let checkout_dir = PathBuf::from(&config.checkout_dir);
+        if !checkout_dir.join(".git").is_dir() {
+            info!("Cloning {} into {:?}", config.url, checkout_dir);
+            run_git(
+                None,
+                &[
+                    "clone",
+                    "--branch",
+                    &config.branch,
+                    &config.url,
+                    &checkout_dir.to_string_lossy(),
+                ],
+            )?;
+        }
+
+        let store = Self {
+            url: config.url.clone(),
+            branch: config.branch.clone(),
+            files: FsStore::new(vec![checkout_dir.join(&config.subdir)], shared)?,
+            checkout_dir,
+            revision: ArcSwapOption::empty(),
+        };
+        store.update_revision()?;
+        Ok(store)
+    }
+
+    /// Fetch the configured branch and move the checkout to its tip.
+    fn pull(&self) -> Result<()> {
+        run_git(Some(&self.checkout_dir), &["fetch", &self.url, &self.branch])?;
+        run_git(Some(&self.checkout_dir), &["reset", "--hard", "FETCH_HEAD"])?;
+        self.update_revision()
+    }
+
+    fn update_revision(&self) -> Result<()> {
+        let head = run_git(Some(&self.checkout_dir), &["rev-parse", "HEAD"])?;
+        let previous = self.revision.swap(Some(Arc::new(head.clone())));
+        if previous.as_deref() != Some(&head) {
+            info!("Serving content from commit {}", head);
+        }
+        Ok(())
+    }
+}
+
+impl ArticleStore for GitStore {
+    fn scan(&self) -> Result<Vec<ScanEntry>> {
+        // A failed pull shouldn't take the site down; keep serving the current checkout
+        if let Err(e) = self.pull() {
+            warn!("Failed to pull {}: {:#}", self.url, e);
+        }
+        self.files.scan()
+    }
+
+    fn load(&self, metainfo: &Metainfo) -> Result<String> {
+        self.files.load(metainfo)
+    }
+
+    fn mtime(&self, metainfo: &Metainfo) -> Result<Option<SystemTime>> {
+        self.files.mtime(metainfo)
+    }
+
+    fn revision(&self) -> Option<String> {
+        self.revision.load().as_deref().cloned()
+    }
+}
+
+/// Run a git command, returning its trimmed standard output.
+fn run_git(dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
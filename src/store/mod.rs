@@ -0,0 +1,694 @@
+use crate::articles::{ArticleDraft, ArticleId, Metainfo, Tombstone};
+use crate::bundle::BundleFile;
+use crate::config::{SharedConfig, SymlinkPolicy};
+use crate::error::Error;
+use anyhow::{bail, Result};
+use filter::{IgnoreFile, ScanFilter};
+pub use report::{Problem, ScanEntry, ScanOutcome, ScanReport, ValidationReport};
+use log::warn;
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+mod filter;
+mod report;
+#[cfg(feature = "git")]
+pub mod git;
+#[cfg(feature = "s3")]
+pub mod s3;
+
+/// Build the article store selected by the `[storage]` configuration, reading its
+/// settings from `shared`.
+pub fn from_config(shared: &SharedConfig) -> Result<Arc<dyn ArticleStore>> {
+    let config = shared.load();
+    match config.storage.backend.as_str() {
+        "fs" => Ok(Arc::new(FsStore::new(config.mainconfig.source_dirs(), Arc::clone(shared))?)),
+        #[cfg(feature = "s3")]
+        "s3" => {
+            let s3_config = config
+                .storage
+                .s3
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Storage backend 's3' requires a [storage.s3] section"))?;
+            Ok(Arc::new(s3::S3Store::new(s3_config, Arc::clone(shared))?))
+        }
+        #[cfg(feature = "git")]
+        "git" => {
+            let git_config = config
+                .storage
+                .git
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Storage backend 'git' requires a [storage.git] section"))?;
+            Ok(Arc::new(git::GitStore::new(git_config, Arc::clone(shared))?))
+        }
+        other => bail!(
+            "Unknown or disabled storage backend '{}' (is the matching cargo feature enabled?)",
+            other
+        ),
+    }
+}
+
+// ===== STORE TRAIT =====
+
+/// A backend that article metadata and Markdown sources are read from.
+///
+/// `Articles` only talks to its store through this trait, so alternative backends
+/// (object storage, git, databases, in-memory fixtures) can be plugged in.
+pub trait ArticleStore: Send + Sync {
+    /// Discover all articles in the store, reporting the outcome of every entry
+    /// examined. When two entries share an ID, the earlier one wins.
+    fn scan(&self) -> Result<Vec<ScanEntry>>;
+
+    /// Load the raw Markdown source of an article.
+    fn load(&self, metainfo: &Metainfo) -> Result<String>;
+
+    /// The last modification time of an article's source, if the backend tracks one.
+    fn mtime(&self, metainfo: &Metainfo) -> Result<Option<SystemTime>>;
+
+    /// An opaque token that changes whenever an article's source changes (e.g. an
+    /// ETag), for backends where that is cheaper or more reliable than `mtime`. It's
+    /// only asked for when `mtime` returns `None`.
+    fn fingerprint(&self, _metainfo: &Metainfo) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// The revision of the content as a whole (e.g. a commit hash), for versioned backends.
+    fn revision(&self) -> Option<String> {
+        None
+    }
+
+    /// Write the files of a new article with the given ID (a validated bundle
+    /// including its `metainfo.toml`), returning its metadata as a scan would find
+    /// it. Backends are read-only unless they override this.
+    fn create(&self, _id: ArticleId, _files: &[BundleFile]) -> Result<Metainfo> {
+        bail!("This storage backend is read-only")
+    }
+
+    /// Overwrite an existing article's metadata and Markdown source.
+    fn update(&self, _metainfo: &Metainfo, _draft: &ArticleDraft) -> Result<Metainfo> {
+        bail!("This storage backend is read-only")
+    }
+
+    /// Remove an article and everything stored alongside it.
+    fn delete(&self, _metainfo: &Metainfo) -> Result<()> {
+        bail!("This storage backend is read-only")
+    }
+
+    /// Write a tombstone for a deleted article, where a scan finds it in place of
+    /// the article, returning it as the scan would find it.
+    fn bury(&self, _metainfo: &Metainfo, _successor: Option<ArticleId>) -> Result<Tombstone> {
+        bail!("This storage backend is read-only")
+    }
+}
+
+// ===== FILE STORAGE =====
+
+/// A path from article metadata that resolves outside the article directory.
+#[derive(Debug)]
+pub struct PathEscape(pub String);
+
+impl std::fmt::Display for PathEscape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' resolves outside the article directory", self.0)
+    }
+}
+
+impl std::error::Error for PathEscape {}
+
+/// A symlink the `symlinks` policy doesn't allow reading through.
+#[derive(Debug)]
+pub struct SymlinkRejected(pub PathBuf);
+
+impl std::fmt::Display for SymlinkRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} is a symlink, which the symlinks policy rejects", self.0)
+    }
+}
+
+impl std::error::Error for SymlinkRejected {}
+
+/// A file larger than `max_article_bytes`, which isn't read into memory.
+#[derive(Debug, Clone)]
+pub struct ArticleTooLarge {
+    pub size: u64,
+    pub limit: usize,
+}
+
+impl std::fmt::Display for ArticleTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the file is {} bytes, more than the max_article_bytes limit of {} bytes",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for ArticleTooLarge {}
+
+
+/// Loads articles from one or more directories on the local filesystem, where each
+/// article lives in a directory named after its ID containing a `metainfo.toml`, or
+/// is a single `*.md` file whose front matter supplies the metadata. A directory
+/// named after an ID that holds a `tombstone.toml` instead marks a deleted article.
+pub struct FsStore {
+    /// Source directories, merged into one index in this order.
+    source_dirs: Vec<PathBuf>,
+    filter: ScanFilter,
+    config: SharedConfig,
+}
+
+impl FsStore {
+    /// Create a store over `source_dirs`, filtered by the configured `include` and
+    /// `exclude` patterns.
+    pub fn new(source_dirs: Vec<PathBuf>, config: SharedConfig) -> Result<Self> {
+        let main = &config.load().mainconfig;
+        let filter = ScanFilter::new(&main.include, &main.exclude)?;
+        Ok(Self {
+            source_dirs,
+            filter,
+            config,
+        })
+    }
+
+    /// Scan one directory for article directories and flat Markdown files,
+    /// descending into other subdirectories when `recursive_scan` is enabled.
+    ///
+    /// `ignores` holds the `.henkaikiignore` files of `dir` and its ancestors, along
+    /// with the directory each applies to.
+    fn scan_dir(
+        &self,
+        root: &Path,
+        dir: &Path,
+        ignores: &mut Vec<(PathBuf, IgnoreFile)>,
+        found: &mut Vec<ScanEntry>,
+    ) -> Result<()> {
+        let ignore_file = IgnoreFile::load(dir)?;
+        let pushed = ignore_file.is_some();
+        if let Some(ignore_file) = ignore_file {
+            ignores.push((dir.to_path_buf(), ignore_file));
+        }
+        let result = self.scan_entries(root, dir, ignores, found);
+        if pushed {
+            ignores.pop();
+        }
+        result
+    }
+
+    fn scan_entries(
+        &self,
+        root: &Path,
+        dir: &Path,
+        ignores: &mut Vec<(PathBuf, IgnoreFile)>,
+        found: &mut Vec<ScanEntry>,
+    ) -> Result<()> {
+        // Sort entries so that scans are deterministic across filesystems
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.sort();
+
+        for path in paths {
+            let relative_path = path.strip_prefix(root).unwrap_or(&path);
+            let is_markdown = path.is_file() && path.extension().is_some_and(|ext| ext == "md");
+            let dir_name = match path.file_name().and_then(|s| s.to_str()) {
+                Some(name) if path.is_dir() && !name.starts_with('.') => Some(name),
+                _ => None, // never descend into hidden directories
+            };
+            if !is_markdown && dir_name.is_none() {
+                continue;
+            }
+            if let Some(rejected) = self.check_symlink(root, dir, &path) {
+                found.push(rejected);
+                continue;
+            }
+            if self.filter.excludes(relative_path) {
+                found.push(ScanEntry::skipped(&path, "excluded by an exclude pattern"));
+                continue;
+            }
+            if Self::is_ignored(&path, ignores) {
+                found.push(ScanEntry::skipped(&path, "ignored by .henkaikiignore"));
+                continue;
+            }
+
+            if is_markdown {
+                if !self.filter.includes(relative_path) {
+                    found.push(ScanEntry::skipped(&path, "not matched by an include pattern"));
+                    continue;
+                }
+                found.push(match self.parse_flat_article(&path) {
+                    Ok(Some((mut metainfo, replaced))) => {
+                        let utf8 = Self::utf8_warning(&[(&path, replaced)]);
+                        let warnings = [utf8, self.check_cover(&mut metainfo)];
+                        ScanEntry::loaded(&path, metainfo).with_warning(Self::joined(warnings))
+                    }
+                    Ok(None) => ScanEntry::skipped(&path, "Markdown file without front matter"),
+                    Err(e) => ScanEntry::failed(&path, Problem::of(&e), e.to_string()),
+                });
+                continue;
+            }
+            let Some(dir_name) = dir_name else {
+                continue;
+            };
+
+            let metainfo_path = path.join("metainfo.toml");
+            let tombstone_path = path.join("tombstone.toml");
+            if !metainfo_path.is_file() && !tombstone_path.is_file() {
+                // Directories that aren't articles themselves may group articles (e.g. by year)
+                if !self.config.load().mainconfig.recursive_scan {
+                    found.push(ScanEntry::skipped(&path, "no metainfo.toml"));
+                } else if let Err(e) = self.scan_dir(root, &path, ignores, found) {
+                    warn!("Failed to scan directory {:?}: {}", path, e);
+                    found.push(ScanEntry::failed(&path, Problem::Unreadable, e.to_string()));
+                }
+                continue;
+            }
+            if !self.filter.includes(relative_path) {
+                found.push(ScanEntry::skipped(&path, "not matched by an include pattern"));
+                continue;
+            }
+            if metainfo_path.is_file() {
+                found.push(self.check_article_dir(&path, dir_name, &metainfo_path));
+            } else {
+                found.push(self.check_tombstone_dir(&path, dir_name, &tombstone_path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the tombstone of a deleted article, whose directory name must be its ID.
+    fn check_tombstone_dir(&self, path: &Path, dir_name: &str, tombstone_path: &Path) -> ScanEntry {
+        let Ok(article_id) = dir_name.parse::<ArticleId>() else {
+            return ScanEntry::failed(
+                path,
+                Problem::IdMismatch,
+                format!("directory name '{}' is not an article ID", dir_name),
+            );
+        };
+        let tombstone = self.check_file_symlink(tombstone_path)
+            .and_then(|_| self.read_file_as_string(tombstone_path))
+            .and_then(|toml_content| Tombstone::from_toml(&toml_content, path.to_path_buf()));
+        match tombstone {
+            Ok(tombstone) if tombstone.id == article_id => ScanEntry::tombstone(path, tombstone),
+            Ok(tombstone) => ScanEntry::failed(
+                path,
+                Problem::IdMismatch,
+                format!("tombstone ID {} does not match the directory name", tombstone.id),
+            ),
+            Err(e) => ScanEntry::failed(tombstone_path, Problem::of(&e), e.to_string()),
+        }
+    }
+
+    /// Parse and check an article directory: its name must be the declared ID and
+    /// the Markdown file must exist.
+    fn check_article_dir(&self, path: &Path, dir_name: &str, metainfo_path: &Path) -> ScanEntry {
+        let Ok(article_id) = dir_name.parse::<ArticleId>() else {
+            return ScanEntry::failed(
+                path,
+                Problem::IdMismatch,
+                format!("directory name '{}' is not an article ID", dir_name),
+            );
+        };
+
+        // Parse the TOML file into a Metainfo
+        let (mut metainfo, metainfo_replaced) = match self.parse_metainfo(metainfo_path) {
+            Ok(parsed) => parsed,
+            Err(e) => return ScanEntry::failed(metainfo_path, Problem::of(&e), e.to_string()),
+        };
+        if metainfo.id != article_id {
+            return ScanEntry::failed(
+                path,
+                Problem::IdMismatch,
+                format!("metainfo ID {} does not match the directory name", metainfo.id),
+            );
+        }
+        let markdown_path = match self.markdown_file(&metainfo) {
+            Ok(markdown_path) => markdown_path,
+            Err(e) => {
+                let problem = match Problem::of(&e) {
+                    Problem::UnsafePath => Problem::UnsafePath,
+                    _ => Problem::MissingMarkdown,
+                };
+                return ScanEntry::failed(path, problem, e.to_string());
+            }
+        };
+        // The Markdown file isn't needed for the index, so it's only read to flag
+        // invalid UTF-8 when that is replaced rather than failing the load
+        let markdown_replaced = self.config.load().mainconfig.lossy_utf8
+            && self.read_text(&markdown_path).is_ok_and(|(_, replaced)| replaced);
+        let utf8 = Self::utf8_warning(&[
+            (metainfo_path, metainfo_replaced),
+            (&markdown_path, markdown_replaced),
+        ]);
+        let warnings = [utf8, self.check_cover(&mut metainfo)];
+        ScanEntry::loaded(path, metainfo).with_warning(Self::joined(warnings))
+    }
+
+    /// Drop a cover naming a file that isn't in the article directory, so no broken
+    /// image URL is served, returning the warning to report.
+    fn check_cover(&self, metainfo: &mut Metainfo) -> Option<String> {
+        let asset = metainfo.cover_asset()?;
+        let path = metainfo.dir.join(asset);
+        let contained = path.is_file()
+            && self.check_file_symlink(&path).is_ok()
+            && match (metainfo.dir.canonicalize(), path.canonicalize()) {
+                (Ok(dir), Ok(path)) => path.starts_with(dir),
+                _ => false,
+            };
+        if contained {
+            return None;
+        }
+        let warning = format!("cover '{}' is not a file in the article directory, ignored", asset);
+        metainfo.cover = None;
+        Some(warning)
+    }
+
+    /// Parse the `metainfo.toml` of an article just written, as a scan would.
+    fn parse_written(&self, metainfo_path: &Path) -> Result<Metainfo> {
+        let (metainfo, _) = self.parse_metainfo(metainfo_path)?;
+        Ok(self.checked_cover(metainfo))
+    }
+
+    /// `metainfo` with [`FsStore::check_cover`] applied, the warning logged.
+    fn checked_cover(&self, mut metainfo: Metainfo) -> Metainfo {
+        if let Some(warning) = self.check_cover(&mut metainfo) {
+            warn!("Article {}: {}", metainfo.id, warning);
+        }
+        metainfo
+    }
+
+    /// The warnings of an entry, as one message.
+    fn joined<const N: usize>(warnings: [Option<String>; N]) -> Option<String> {
+        let warnings = warnings.into_iter().flatten().collect::<Vec<_>>();
+        (!warnings.is_empty()).then(|| warnings.join("; "))
+    }
+
+    /// Apply the `symlinks` policy to an entry of `dir`, returning the report entry
+    /// for a symlink that mustn't be followed. Symlinks back to an ancestor of
+    /// `dir` are never followed, so recursive scans can't loop.
+    fn check_symlink(&self, root: &Path, dir: &Path, path: &Path) -> Option<ScanEntry> {
+        if !Self::is_symlink(path) {
+            return None;
+        }
+        let policy = self.config.load().mainconfig.symlinks;
+        if policy == SymlinkPolicy::Reject {
+            return Some(ScanEntry::skipped(path, "symlink rejected by the symlinks policy"));
+        }
+
+        let target = match path.canonicalize() {
+            Ok(target) => target,
+            Err(e) => {
+                let message = format!("broken symlink: {}", e);
+                return Some(ScanEntry::failed(path, Problem::Unreadable, message));
+            }
+        };
+        if dir.canonicalize().is_ok_and(|dir| dir.starts_with(&target)) {
+            let message = "symlink to an enclosing directory";
+            return Some(ScanEntry::failed(path, Problem::UnsafePath, message));
+        }
+        if policy == SymlinkPolicy::Contained
+            && !root.canonicalize().is_ok_and(|root| target.starts_with(root))
+        {
+            let message = format!("symlink target {:?} lies outside the articles directory", target);
+            return Some(ScanEntry::failed(path, Problem::UnsafePath, message));
+        }
+        None
+    }
+
+    fn is_symlink(path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    }
+
+    /// With the `reject` policy, refuse to read a file of an article through a symlink.
+    fn check_file_symlink(&self, path: &Path) -> Result<()> {
+        let policy = self.config.load().mainconfig.symlinks;
+        if policy == SymlinkPolicy::Reject && Self::is_symlink(path) {
+            bail!(SymlinkRejected(path.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// Whether any applicable `.henkaikiignore` file ignores `path`.
+    fn is_ignored(path: &Path, ignores: &[(PathBuf, IgnoreFile)]) -> bool {
+        ignores.iter().any(|(base, ignore_file)| {
+            path.strip_prefix(base)
+                .is_ok_and(|relative_path| ignore_file.ignores(relative_path))
+        })
+    }
+
+    /// Parse a `metainfo.toml` file from disk, also telling whether invalid UTF-8 in
+    /// it was replaced.
+    fn parse_metainfo(&self, path: &Path) -> Result<(Metainfo, bool)> {
+        self.check_file_symlink(path)?;
+        let (toml_content, replaced) = self.read_text(path)?;
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok((Metainfo::from_toml(&toml_content, dir)?, replaced))
+    }
+
+    /// Parse the front matter of a flat Markdown file, if it has any, also telling
+    /// whether invalid UTF-8 in the file was replaced.
+    fn parse_flat_article(&self, path: &Path) -> Result<Option<(Metainfo, bool)>> {
+        let (content, replaced) = self.read_text(path)?;
+        let Some((front_matter, _)) = split_front_matter(&content) else {
+            return Ok(None);
+        };
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+        let metainfo = Metainfo::from_front_matter(front_matter, dir, file_name)?;
+        Ok(Some((metainfo, replaced)))
+    }
+
+    /// Replace a file's contents by writing a sibling temporary file and renaming
+    /// it over the original, so readers never see a partial write.
+    fn write_atomically(path: &Path, content: &str) -> Result<()> {
+        let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+        let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+        fs::write(&temp_path, content)?;
+        if let Err(e) = fs::rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Resolve the Markdown file of an article, checking that it exists and that
+    /// it doesn't lie outside the article directory (e.g. `markdown_path = "../../etc/passwd"`).
+    fn markdown_file(&self, metainfo: &Metainfo) -> Result<PathBuf> {
+        let article_dir = &metainfo.dir;
+        if !article_dir.exists() || !article_dir.is_dir() {
+            bail!("Article directory for ID {} not found", metainfo.id);
+        }
+
+        let md_file_path = article_dir.join(&*metainfo.markdown_path);
+        if !md_file_path.is_file() {
+            bail!(
+                "Markdown file '{}' is missing for article ID {}",
+                metainfo.markdown_path,
+                metainfo.id
+            );
+        }
+
+        self.check_file_symlink(&md_file_path)?;
+
+        // Compare canonical paths, so neither `..` nor absolute paths can escape
+        let canonical_dir = article_dir.canonicalize()?;
+        let canonical_path = md_file_path.canonicalize()?;
+        if !canonical_path.starts_with(&canonical_dir) {
+            bail!(PathEscape(metainfo.markdown_path.to_string()));
+        }
+        Ok(canonical_path)
+    }
+
+    /// Read a file as a UTF-8 string, refusing files larger than `max_article_bytes`.
+    fn read_file_as_string(&self, path: &Path) -> Result<String> {
+        self.read_text(path).map(|(text, _)| text)
+    }
+
+    /// [`FsStore::read_file_as_string`], also telling whether invalid UTF-8 in the file
+    /// was replaced.
+    fn read_text(&self, path: &Path) -> Result<(String, bool)> {
+        let limit = self.config.load().mainconfig.max_article_bytes;
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        if size > limit as u64 {
+            bail!(ArticleTooLarge { size, limit });
+        }
+
+        // The file may grow between the size check and the read
+        let mut content = Vec::new();
+        file.take(limit as u64 + 1).read_to_end(&mut content)?;
+        if content.len() > limit {
+            bail!(ArticleTooLarge { size: content.len() as u64, limit });
+        }
+        decode_text(&path.to_string_lossy(), content, self.config.load().mainconfig.lossy_utf8)
+    }
+
+    /// A warning naming the files among `files` whose invalid UTF-8 was replaced
+    /// when they were read, for the scan report.
+    fn utf8_warning(files: &[(&Path, bool)]) -> Option<String> {
+        let replaced = files
+            .iter()
+            .filter(|(_, replaced)| *replaced)
+            .map(|(path, _)| path.file_name().unwrap_or_default().to_string_lossy())
+            .collect::<Vec<_>>();
+        if replaced.is_empty() {
+            return None;
+        }
+        Some(format!("invalid UTF-8 replaced in {}", replaced.join(", ")))
+    }
+}
+
+impl ArticleStore for FsStore {
+    fn scan(&self) -> Result<Vec<ScanEntry>> {
+        let mut found = Vec::new();
+        for source_dir in &self.source_dirs {
+            self.scan_dir(source_dir, source_dir, &mut Vec::new(), &mut found)?;
+        }
+        Ok(found)
+    }
+
+    fn load(&self, metainfo: &Metainfo) -> Result<String> {
+        let md_file_path = self.markdown_file(metainfo)?;
+        let content = self.read_file_as_string(&md_file_path)?;
+        if metainfo.front_matter
+            && let Some((_, body)) = split_front_matter(&content)
+        {
+            return Ok(body.to_string());
+        }
+        Ok(content)
+    }
+
+    fn mtime(&self, metainfo: &Metainfo) -> Result<Option<SystemTime>> {
+        let md_file_path = self.markdown_file(metainfo)?;
+        Ok(Some(fs::metadata(md_file_path)?.modified()?))
+    }
+
+    /// New articles are written to `{first source dir}/{id}/`. The directory is
+    /// assembled under a hidden name first, so a concurrent scan never sees it half-written.
+    fn create(&self, id: ArticleId, files: &[BundleFile]) -> Result<Metainfo> {
+        let Some(source_dir) = self.source_dirs.first() else {
+            bail!("No articles directory to write to");
+        };
+        let article_dir = source_dir.join(id.to_string());
+        if article_dir.exists() {
+            bail!(Error::Conflict(id));
+        }
+
+        let staging_dir = source_dir.join(format!(".{}.tmp", id));
+        if staging_dir.exists() {
+            fs::remove_dir_all(&staging_dir)?; // left over from an interrupted write
+        }
+        fs::create_dir_all(&staging_dir)?;
+        let result = files
+            .iter()
+            .try_for_each(|file| {
+                let path = staging_dir.join(&file.path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, &file.content)
+            })
+            .and_then(|_| fs::rename(&staging_dir, &article_dir));
+        if let Err(e) = result {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(e.into());
+        }
+
+        self.parse_written(&article_dir.join("metainfo.toml"))
+    }
+
+    /// Flat Markdown files get new front matter; article directories get a new
+    /// `metainfo.toml` that keeps the existing `markdown_path`.
+    fn update(&self, metainfo: &Metainfo, draft: &ArticleDraft) -> Result<Metainfo> {
+        self.markdown_file(metainfo)?; // never write through an unsafe markdown_path
+        let md_file_path = metainfo.dir.join(&*metainfo.markdown_path);
+        if metainfo.front_matter {
+            let front_matter = draft.to_toml(metainfo.id, None)?;
+            let content = format!("+++\n{}+++\n{}", front_matter, draft.markdown);
+            Self::write_atomically(&md_file_path, &content)?;
+            let (metainfo, _) = self.parse_flat_article(&md_file_path)?
+                .ok_or_else(|| anyhow::anyhow!("Front matter missing after writing {:?}", md_file_path))?;
+            return Ok(self.checked_cover(metainfo));
+        }
+
+        let metainfo_path = metainfo.dir.join("metainfo.toml");
+        let metainfo_toml = draft.to_toml(metainfo.id, Some(&metainfo.markdown_path))?;
+        Self::write_atomically(&md_file_path, &draft.markdown)?;
+        Self::write_atomically(&metainfo_path, &metainfo_toml)?;
+        self.parse_written(&metainfo_path)
+    }
+
+    /// Article directories are first renamed to a hidden name, so they vanish from
+    /// scans at once even if removing their contents fails halfway.
+    fn delete(&self, metainfo: &Metainfo) -> Result<()> {
+        if metainfo.front_matter {
+            fs::remove_file(metainfo.dir.join(&*metainfo.markdown_path))?;
+            return Ok(());
+        }
+
+        let dir_name = metainfo.dir.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+        let trash_dir = metainfo.dir.with_file_name(format!(".{}.deleted", dir_name));
+        if trash_dir.exists() {
+            fs::remove_dir_all(&trash_dir)?;
+        }
+        fs::rename(&metainfo.dir, &trash_dir)?;
+        fs::remove_dir_all(&trash_dir)?;
+        Ok(())
+    }
+
+    /// The tombstone goes in the article's directory, which for a flat Markdown file
+    /// is a new `{id}/` directory next to where it was.
+    fn bury(&self, metainfo: &Metainfo, successor: Option<ArticleId>) -> Result<Tombstone> {
+        let dir = if metainfo.front_matter {
+            metainfo.dir.join(metainfo.id.to_string())
+        } else {
+            metainfo.dir.clone()
+        };
+        let tombstone = Tombstone {
+            id: metainfo.id,
+            successor,
+            dir,
+        };
+        fs::create_dir_all(&tombstone.dir)?;
+        Self::write_atomically(&tombstone.dir.join("tombstone.toml"), &tombstone.to_toml()?)?;
+        Ok(tombstone)
+    }
+}
+
+/// Decode the contents of a file or object, telling whether anything was replaced.
+/// Invalid UTF-8 fails the read, unless `lossy` (the `lossy_utf8` setting) is set,
+/// in which case it's replaced by U+FFFD and logged.
+fn decode_text(name: &str, bytes: Vec<u8>, lossy: bool) -> Result<(String, bool)> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok((text, false)),
+        Err(e) if lossy => {
+            warn!("Replaced invalid UTF-8 in {}", name);
+            Ok((String::from_utf8_lossy(e.as_bytes()).into_owned(), true))
+        }
+        Err(e) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not valid UTF-8: {}", name, e.utf8_error()),
+        )
+        .into()),
+    }
+}
+
+/// Split a Markdown document starting with TOML front matter delimited by `+++`
+/// lines into the front matter and the remaining body.
+fn split_front_matter(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("+++\n")
+        .or_else(|| content.strip_prefix("+++\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "+++" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
@@ -0,0 +1,219 @@
+use crate::articles::{ArticleId, Metainfo, Tombstone};
+use serde::Serialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// What made a scanned entry unusable as an article.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Problem {
+    /// The entry couldn't be read.
+    Unreadable,
+    /// `metainfo.toml` or the front matter isn't valid TOML.
+    MalformedToml,
+    /// A required field is missing or has the wrong type.
+    InvalidMetadata,
+    /// The Markdown file named by `markdown_path` doesn't exist.
+    MissingMarkdown,
+    /// A path resolves outside where it may point: `markdown_path` outside the
+    /// article directory, or a symlink the `symlinks` policy doesn't allow.
+    UnsafePath,
+    /// A file is larger than `max_article_bytes`.
+    TooLarge,
+    /// The ID doesn't match the article directory's name.
+    IdMismatch,
+    /// Another article already declared the same ID.
+    DuplicateId,
+}
+
+impl Problem {
+    /// Classify an error from reading and parsing an article's metadata.
+    pub fn of(e: &anyhow::Error) -> Self {
+        if e.downcast_ref::<super::PathEscape>().is_some()
+            || e.downcast_ref::<super::SymlinkRejected>().is_some()
+        {
+            Problem::UnsafePath
+        } else if e.downcast_ref::<super::ArticleTooLarge>().is_some() {
+            Problem::TooLarge
+        } else if e.downcast_ref::<toml::de::Error>().is_some() {
+            Problem::MalformedToml
+        } else if e.downcast_ref::<std::io::Error>().is_some() {
+            Problem::Unreadable
+        } else {
+            Problem::InvalidMetadata
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Problem::Unreadable => "unreadable",
+            Problem::MalformedToml => "malformed_toml",
+            Problem::InvalidMetadata => "invalid_metadata",
+            Problem::MissingMarkdown => "missing_markdown",
+            Problem::UnsafePath => "unsafe_path",
+            Problem::TooLarge => "too_large",
+            Problem::IdMismatch => "id_mismatch",
+            Problem::DuplicateId => "duplicate_id",
+        })
+    }
+}
+
+/// The outcome of examining one entry during a scan.
+pub enum ScanOutcome {
+    Loaded(Box<Metainfo>),
+    /// The tombstone of a deleted article.
+    Tombstone(Tombstone),
+    /// Deliberately not an article (e.g. excluded by a pattern), with the reason.
+    Skipped(String),
+    /// Meant to be an article but unusable.
+    Failed(Problem, String),
+}
+
+/// An entry (file, directory or object) examined by a store's scan.
+pub struct ScanEntry {
+    pub path: PathBuf,
+    pub outcome: ScanOutcome,
+    /// Something worth reporting about an entry that loaded anyway.
+    pub warning: Option<String>,
+}
+
+impl ScanEntry {
+    pub fn loaded(path: &Path, metainfo: Metainfo) -> Self {
+        Self::new(path, ScanOutcome::Loaded(Box::new(metainfo)))
+    }
+
+    pub fn tombstone(path: &Path, tombstone: Tombstone) -> Self {
+        Self::new(path, ScanOutcome::Tombstone(tombstone))
+    }
+
+    pub fn skipped(path: &Path, reason: impl Into<String>) -> Self {
+        Self::new(path, ScanOutcome::Skipped(reason.into()))
+    }
+
+    pub fn failed(path: &Path, problem: Problem, message: impl Into<String>) -> Self {
+        Self::new(path, ScanOutcome::Failed(problem, message.into()))
+    }
+
+    pub fn with_warning(mut self, warning: Option<String>) -> Self {
+        self.warning = warning;
+        self
+    }
+
+    fn new(path: &Path, outcome: ScanOutcome) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            outcome,
+            warning: None,
+        }
+    }
+}
+
+/// A serializable account of every entry a scan examined.
+#[derive(Clone, Default, Serialize)]
+pub struct ScanReport {
+    /// When the scan finished, in seconds since the UNIX epoch (0 before the first scan).
+    pub scanned_at: u64,
+    /// How long the scan took, in milliseconds.
+    pub duration_ms: u64,
+    pub loaded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub entries: Vec<ReportEntry>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ReportEntry {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<ArticleId>,
+    /// `"loaded"`, `"skipped"` or `"failed"`.
+    pub outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<Problem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ScanReport {
+    pub fn push_loaded(&mut self, path: &Path, id: ArticleId) {
+        self.loaded += 1;
+        self.push(path, Some(id), "loaded", None, None);
+    }
+
+    /// Attach a warning to the loaded entry for `id`, keeping any earlier warnings.
+    pub fn flag_loaded(&mut self, id: ArticleId, warning: String) {
+        let loaded = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.outcome == "loaded" && entry.id == Some(id));
+        if let Some(entry) = loaded {
+            entry.message = Some(match entry.message.take() {
+                Some(earlier) => format!("{}; {}", earlier, warning),
+                None => warning,
+            });
+        }
+    }
+
+    pub fn push_skipped(&mut self, path: &Path, reason: String) {
+        self.skipped += 1;
+        self.push(path, None, "skipped", None, Some(reason));
+    }
+
+    pub fn push_failed(
+        &mut self,
+        path: &Path,
+        id: Option<ArticleId>,
+        problem: Problem,
+        message: String,
+    ) {
+        self.failed += 1;
+        self.push(path, id, "failed", Some(problem), Some(message));
+    }
+
+    fn push(
+        &mut self,
+        path: &Path,
+        id: Option<ArticleId>,
+        outcome: &'static str,
+        problem: Option<Problem>,
+        message: Option<String>,
+    ) {
+        self.entries.push(ReportEntry {
+            path: path.to_string_lossy().into_owned(),
+            id,
+            outcome,
+            problem,
+            message,
+        });
+    }
+
+    /// The entries that failed to load.
+    pub fn problems(&self) -> impl Iterator<Item = &ReportEntry> {
+        self.entries.iter().filter(|entry| entry.problem.is_some())
+    }
+}
+
+/// The problems found by a validation scan.
+#[derive(Serialize)]
+pub struct ValidationReport {
+    /// Whether no problems were found.
+    pub valid: bool,
+    /// The number of articles that would be loaded.
+    pub articles: usize,
+    pub problems: Vec<ReportEntry>,
+}
+
+impl From<ScanReport> for ValidationReport {
+    fn from(report: ScanReport) -> Self {
+        let problems = report.problems().cloned().collect::<Vec<_>>();
+        ValidationReport {
+            valid: problems.is_empty(),
+            articles: report.loaded,
+            problems,
+        }
+    }
+}
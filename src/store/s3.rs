@@ -0,0 +1,180 @@
+use super::{decode_text, ArticleStore, ArticleTooLarge, Problem, ScanEntry};
+use crate::articles::{ArticleId, Metainfo, Tombstone};
+use crate::config::{S3Storage, SharedConfig};
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use s3::{creds::Credentials, Bucket, Region};
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Loads articles from an S3-compatible bucket, using the same layout as the
+/// filesystem store: `{prefix}{id}/metainfo.toml` next to the article's Markdown,
+/// or `{prefix}{id}/tombstone.toml` for a deleted article.
+pub struct S3Store {
+    bucket: Box<Bucket>,
+    prefix: String,
+    shared: SharedConfig,
+}
+
+impl S3Store {
+    pub fn new(config: &S3Storage, shared: SharedConfig) -> Result<Self> {
+        let region = match &config.endpoint {
+            Some(endpoint) => Region::Custom {
+                region: config.region.clone(),
+                endpoint: endpoint.clone(),
+            },
+            None => config.region.parse()?,
+        };
+        let credentials = match (&config.access_key, &config.secret_key) {
+            (Some(access_key), Some(secret_key)) => {
+                Credentials::new(Some(access_key), Some(secret_key), None, None, None)?
+            }
+            _ => Credentials::default()?,
+        };
+
+        let mut bucket = Bucket::new(&config.bucket, region, credentials)?;
+        if config.path_style {
+            bucket = bucket.with_path_style();
+        }
+
+        // Normalize the prefix so keys can be built as `{prefix}{id}/...`
+        let mut prefix = config.prefix.trim_start_matches('/').to_string();
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+
+        Ok(Self {
+            bucket,
+            prefix,
+            shared,
+        })
+    }
+
+    /// Fetch an object as a UTF-8 string, treating non-2xx responses as errors and
+    /// refusing objects larger than `max_article_bytes` before downloading them. The
+    /// `size` is that of the listing, if the object was listed; otherwise it's asked
+    /// for with a HEAD request.
+    fn get_string(&self, key: &str, size: Option<u64>) -> Result<String> {
+        let limit = self.shared.load().mainconfig.max_article_bytes;
+        let size = match size {
+            Some(size) => size,
+            None => self.object_size(key)?,
+        };
+        if size > limit as u64 {
+            bail!(ArticleTooLarge { size, limit });
+        }
+
+        let response = self.bucket.get_object(key)?;
+        if !(200..300).contains(&response.status_code()) {
+            bail!("GET {} returned status {}", key, response.status_code());
+        }
+        // The object may have been replaced by a larger one since its size was checked
+        let bytes = response.to_vec();
+        if bytes.len() > limit {
+            bail!(ArticleTooLarge { size: bytes.len() as u64, limit });
+        }
+        let lossy = self.shared.load().mainconfig.lossy_utf8;
+        decode_text(key, bytes, lossy).map(|(text, _)| text)
+    }
+
+    /// The size of an object, from a HEAD request.
+    fn object_size(&self, key: &str) -> Result<u64> {
+        let (head, status) = self.bucket.head_object(key)?;
+        if !(200..300).contains(&status) {
+            bail!("HEAD {} returned status {}", key, status);
+        }
+        let length = head
+            .content_length
+            .ok_or_else(|| anyhow!("HEAD {} returned no Content-Length", key))?;
+        Ok(length.max(0) as u64)
+    }
+
+    /// The object key of an article's Markdown file.
+    fn markdown_key(metainfo: &Metainfo) -> String {
+        format!("{}/{}", metainfo.dir.to_string_lossy(), metainfo.markdown_path)
+    }
+}
+
+impl ArticleStore for S3Store {
+    fn scan(&self) -> Result<Vec<ScanEntry>> {
+        let mut found = Vec::new();
+        for page in self.bucket.list(self.prefix.clone(), None)? {
+            for object in page.contents {
+                // Only `{prefix}{id}/metainfo.toml` and `{prefix}{id}/tombstone.toml` keys
+                // describe articles
+                let (article_dir, is_tombstone) = match object.key.strip_suffix("/metainfo.toml") {
+                    Some(article_dir) => (article_dir, false),
+                    None => match object.key.strip_suffix("/tombstone.toml") {
+                        Some(article_dir) => (article_dir, true),
+                        None => continue,
+                    },
+                };
+                let key = Path::new(&object.key);
+                let dir_name = article_dir.rsplit('/').next().unwrap_or_default();
+                let article_id: ArticleId = match dir_name.parse() {
+                    Ok(id) => id,
+                    Err(_) => {
+                        let message = format!("'{}' is not an article ID", dir_name);
+                        found.push(ScanEntry::failed(key, Problem::IdMismatch, message));
+                        continue;
+                    }
+                };
+
+                if is_tombstone {
+                    let tombstone = self
+                        .get_string(&object.key, Some(object.size))
+                        .and_then(|toml| Tombstone::from_toml(&toml, PathBuf::from(article_dir)));
+                    found.push(match tombstone {
+                        Ok(tombstone) if tombstone.id == article_id => {
+                            ScanEntry::tombstone(key, tombstone)
+                        }
+                        Ok(tombstone) => ScanEntry::failed(
+                            key,
+                            Problem::IdMismatch,
+                            format!("tombstone ID {} does not match the key", tombstone.id),
+                        ),
+                        Err(e) => ScanEntry::failed(key, Problem::of(&e), e.to_string()),
+                    });
+                    continue;
+                }
+
+                let metainfo = self
+                    .get_string(&object.key, Some(object.size))
+                    .and_then(|toml| Metainfo::from_toml(&toml, PathBuf::from(article_dir)));
+                found.push(match metainfo {
+                    Ok(metainfo) if metainfo.id == article_id => ScanEntry::loaded(key, metainfo),
+                    Ok(metainfo) => ScanEntry::failed(
+                        key,
+                        Problem::IdMismatch,
+                        format!("metainfo ID {} does not match the key", metainfo.id),
+                    ),
+                    Err(e) => {
+                        warn!("Skipping {}: {}", object.key, e);
+                        ScanEntry::failed(key, Problem::of(&e), e.to_string())
+                    }
+                });
+            }
+        }
+        Ok(found)
+    }
+
+    fn load(&self, metainfo: &Metainfo) -> Result<String> {
+        self.get_string(&Self::markdown_key(metainfo), None)
+    }
+
+    fn mtime(&self, _metainfo: &Metainfo) -> Result<Option<SystemTime>> {
+        // Change detection for objects relies on ETags instead
+        Ok(None)
+    }
+
+    fn fingerprint(&self, metainfo: &Metainfo) -> Result<Option<String>> {
+        let key = Self::markdown_key(metainfo);
+        let (head, status) = self.bucket.head_object(&key)?;
+        if !(200..300).contains(&status) {
+            return Err(anyhow!("HEAD {} returned status {}", key, status));
+        }
+        Ok(head.e_tag)
+    }
+}
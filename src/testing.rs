@@ -0,0 +1,154 @@
+//! Fixtures for tests of the article engine and the code built on it, enabled by
+//! the `testing` feature.
+//!
+//! An [`ArticleTree`] is a temporary articles directory that tests fill with
+//! generated or hand-written articles, and then serve through [`Articles`]:
+//!
+//! ```
+//! use henkaiki::testing::ArticleTree;
+//!
+//! let tree = ArticleTree::new()?;
+//! tree.generate(3)?;
+//! let mut draft = ArticleTree::draft(4);
+//! draft.tags = vec!["rust".into()];
+//! tree.add_flat(&draft)?;
+//!
+//! let articles = tree.articles()?;
+//! assert_eq!(articles.list_article_summaries_by_tag("rust")?.len(), 1);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{bail, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+
+use crate::{
+    articles::{ArticleDraft, ArticleId, Articles, Tombstone},
+    config::{self, Config},
+};
+
+/// A temporary articles directory in the layout of the filesystem store, removed
+/// when dropped.
+pub struct ArticleTree {
+    dir: TempDir,
+}
+
+impl ArticleTree {
+    /// Create an empty tree.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            dir: tempfile::Builder::new().prefix("henkaiki-articles-").tempdir()?,
+        })
+    }
+
+    /// The root of the tree, to be used as `articles_dir`.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// A generated draft for the article `id`: titled `Article {id}`, dated in
+    /// January 2024, tagged `even` or `odd`, with a heading and a paragraph.
+    pub fn draft(id: ArticleId) -> ArticleDraft {
+        let parity = if id % 2 == 0 { "even" } else { "odd" };
+        ArticleDraft {
+            id: Some(id),
+            title: format!("Article {}", id),
+            description: format!("The description of article {}", id),
+            date: 20240101 + id.rem_euclid(31) as u32,
+            tags: vec![parity.to_string()],
+            keywords: vec![format!("keyword{}", id)],
+            cover: None,
+            author: None,
+            canonical_url: None,
+            aliases: Vec::new(),
+            license: None,
+            source: None,
+            attribution: None,
+            extra: Default::default(),
+            pinned: false,
+            access: None,
+            token: None,
+            markdown: format!("# Article {}\n\nThe body of article {}.\n", id, id),
+        }
+    }
+
+    /// Add the articles `1..=count` from [`ArticleTree::draft`], returning their IDs.
+    pub fn generate(&self, count: usize) -> Result<Vec<ArticleId>> {
+        let ids = (1..=count as ArticleId).collect::<Vec<_>>();
+        for &id in &ids {
+            self.add(&Self::draft(id))?;
+        }
+        Ok(ids)
+    }
+
+    /// Add `draft` as an article directory `{id}/` holding a `metainfo.toml` and
+    /// `content.md`, returning the directory.
+    pub fn add(&self, draft: &ArticleDraft) -> Result<PathBuf> {
+        let id = Self::id_of(draft)?;
+        let dir = self.path().join(id.to_string());
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("metainfo.toml"), draft.to_toml(id, Some("content.md"))?)?;
+        fs::write(dir.join("content.md"), &draft.markdown)?;
+        Ok(dir)
+    }
+
+    /// Add `draft` as a flat Markdown file `{id}.md` with front matter, returning
+    /// the file.
+    pub fn add_flat(&self, draft: &ArticleDraft) -> Result<PathBuf> {
+        let id = Self::id_of(draft)?;
+        let path = self.path().join(format!("{}.md", id));
+        let front_matter = draft.to_toml(id, None)?;
+        fs::write(&path, format!("+++\n{}+++\n{}", front_matter, draft.markdown))?;
+        Ok(path)
+    }
+
+    /// Add the tombstone of the deleted article `id`, pointing to `successor` if
+    /// given, returning its directory.
+    pub fn add_tombstone(&self, id: ArticleId, successor: Option<ArticleId>) -> Result<PathBuf> {
+        let tombstone = Tombstone {
+            id,
+            successor,
+            dir: self.path().join(id.to_string()),
+        };
+        fs::create_dir_all(&tombstone.dir)?;
+        fs::write(tombstone.dir.join("tombstone.toml"), tombstone.to_toml()?)?;
+        Ok(tombstone.dir)
+    }
+
+    /// Write a file at `relative` to the root, creating its parent directories; for
+    /// content the other methods don't generate, such as malformed metadata.
+    pub fn write(&self, relative: impl AsRef<Path>, content: impl AsRef<[u8]>) -> Result<PathBuf> {
+        let path = self.path().join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    /// The default configuration, serving this tree without the sample article.
+    pub fn config(&self) -> Config {
+        let mut config = Config::defaults();
+        config.mainconfig.articles_dir = self.path().to_string_lossy().into_owned();
+        config.mainconfig.articles_dirs.clear();
+        config.mainconfig.sample_article = false;
+        config
+    }
+
+    /// Serve the tree with [`ArticleTree::config`], its index loaded.
+    pub fn articles(&self) -> Result<Articles> {
+        Articles::builder()
+            .config(config::shared(self.config()))
+            .build()
+    }
+
+    fn id_of(draft: &ArticleDraft) -> Result<ArticleId> {
+        match draft.id {
+            Some(id) if id > 0 => Ok(id),
+            _ => bail!("fixture drafts need a positive ID"),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use serde::Serialize;
+
+/// What was built and how: reported by `GET /api/v1/version` and `--version`.
+///
+/// The commit, build time and features are recorded by `build.rs`; the commit is
+/// `unknown` when the crate was built outside a git checkout.
+#[derive(Clone, Debug, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    /// When the crate was compiled, as an RFC 3339 UTC timestamp.
+    pub build_timestamp: &'static str,
+    /// The enabled cargo features, sorted.
+    pub features: Vec<&'static str>,
+}
+
+/// Information about the running build.
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: env!("HENKAIKI_GIT_COMMIT"),
+        build_timestamp: env!("HENKAIKI_BUILD_TIMESTAMP"),
+        features: env!("HENKAIKI_FEATURES")
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+            .collect(),
+    }
+}
+
+/// The long `--version` output: the version, commit, build time and features.
+pub const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("HENKAIKI_GIT_COMMIT"),
+    ", built ",
+    env!("HENKAIKI_BUILD_TIMESTAMP"),
+    ")\nfeatures: ",
+    env!("HENKAIKI_FEATURES"),
+);
@@ -0,0 +1,396 @@
+use actix_web::{rt, web};
+use anyhow::{Context, Result, bail};
+use log::*;
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::OpenOptions,
+    io::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::broadcast::error::RecvError;
+use url::Url;
+
+use crate::{
+    articles::{ArticleId, Articles},
+    config,
+    events::ContentEvent,
+    outbound,
+};
+
+/// How long a request to another site may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends webmentions (https://www.w3.org/TR/webmention/) from articles to the
+/// pages they link to, retrying failed deliveries and logging every result.
+pub struct Webmentions {
+    article_url: String,
+    max_attempts: u32,
+    retry_delay: Duration,
+    log_path: String,
+    http: ureq::Agent,
+    /// The targets each article mentioned last, so that targets whose links were
+    /// removed by an edit are notified too.
+    mentioned: Mutex<HashMap<ArticleId, BTreeSet<String>>>,
+}
+
+/// How a mention that didn't fail along the way ended.
+enum Outcome {
+    /// The target doesn't advertise an endpoint, so there is nobody to notify.
+    NoEndpoint,
+    /// The endpoint answered with a final status, successful or not.
+    Answered { endpoint: String, status: u16 },
+}
+
+/// A line of the delivery log.
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    /// Seconds since the Unix epoch.
+    time: u64,
+    article: ArticleId,
+    source: &'a str,
+    target: &'a str,
+    endpoint: Option<&'a str>,
+    attempts: u32,
+    /// `accepted`, `rejected` or `failed`.
+    result: &'a str,
+    status: Option<u16>,
+    error: Option<String>,
+}
+
+impl Webmentions {
+    pub fn from_config(config: &config::Webmention) -> Self {
+        // Endpoints are named by the pages linked to, so they can't be trusted
+        let http = outbound::agent(
+            ureq::Agent::config_builder()
+                .timeout_global(Some(REQUEST_TIMEOUT))
+                .http_status_as_error(false)
+                .build(),
+        );
+        Webmentions {
+            article_url: config.article_url.clone(),
+            max_attempts: config.max_attempts.max(1),
+            retry_delay: Duration::from_secs(config.retry_delay_seconds),
+            log_path: config.log_path.clone(),
+            http,
+            mentioned: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn source_url(&self, article_id: ArticleId) -> String {
+        self.article_url.replace("{id}", &article_id.to_string())
+    }
+
+    /// The targets to notify about a new or changed article: the external pages it
+    /// links to now and those it linked to before. Nothing when they haven't changed.
+    fn targets(&self, articles: &Articles, article_id: ArticleId) -> Result<Vec<String>> {
+        let (article, _) = articles.get_article(article_id)?;
+        let source_host = Url::parse(&self.source_url(article_id))
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string));
+        let links: BTreeSet<String> = links(&article.content)
+            .into_iter()
+            .filter(|link| {
+                Url::parse(link).is_ok_and(|url| {
+                    matches!(url.scheme(), "http" | "https")
+                        && url.host_str().is_some()
+                        && url.host_str() != source_host.as_deref()
+                })
+            })
+            .collect();
+
+        let previous = self
+            .mentioned
+            .lock()
+            .unwrap()
+            .insert(article_id, links.clone());
+        match previous {
+            Some(previous) if previous == links => Ok(Vec::new()),
+            Some(previous) => Ok(previous.union(&links).cloned().collect()),
+            None => Ok(links.into_iter().collect()),
+        }
+    }
+
+    /// Discover the target's endpoint and notify it. Errors are worth retrying.
+    fn send(&self, source: &str, target: &str) -> Result<Outcome> {
+        let Some(endpoint) = self.discover(target)? else {
+            return Ok(Outcome::NoEndpoint);
+        };
+        let response = self
+            .http
+            .post(&endpoint)
+            .send_form([("source", source), ("target", target)])
+            .with_context(|| format!("Failed to post to {}", endpoint))?;
+        let status = response.status().as_u16();
+        if status == 429 || status >= 500 {
+            bail!("{} answered {}", endpoint, status);
+        }
+        Ok(Outcome::Answered { endpoint, status })
+    }
+
+    /// The webmention endpoint of `target`, from its `Link` header or else a
+    /// `<link>` or `<a>` element of its HTML.
+    fn discover(&self, target: &str) -> Result<Option<String>> {
+        let mut response = self
+            .http
+            .get(target)
+            .call()
+            .with_context(|| format!("Failed to fetch {}", target))?;
+        let status = response.status().as_u16();
+        if status == 429 || status >= 500 {
+            bail!("{} answered {}", target, status);
+        }
+        if status >= 400 {
+            return Ok(None);
+        }
+
+        // Relative endpoints are resolved against the page after any redirects
+        let base = Url::parse(&ureq::ResponseExt::get_uri(&response).to_string())?;
+        let from_header = response
+            .headers()
+            .get_all("link")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(link_header_endpoint);
+        let endpoint = match from_header {
+            Some(endpoint) => Some(endpoint),
+            None => {
+                let is_html = response
+                    .headers()
+                    .get("content-type")
+                    .and_then(|value| value.to_str().ok())
+                    .is_some_and(|value| value.starts_with("text/html"));
+                if is_html {
+                    html_endpoint(&response.body_mut().read_to_string()?)
+                } else {
+                    None
+                }
+            }
+        };
+        Ok(endpoint.and_then(|endpoint| base.join(&endpoint).ok().map(String::from)))
+    }
+
+    /// Mention `target` from the article, retrying with a doubling delay while
+    /// delivery fails, and log how it ended.
+    async fn deliver(self: Arc<Self>, article_id: ArticleId, target: String) {
+        let source = self.source_url(article_id);
+        let mut delay = self.retry_delay;
+        for attempt in 1..=self.max_attempts {
+            let (webmentions, source_url, target_url) =
+                (Arc::clone(&self), source.clone(), target.clone());
+            let result = web::block(move || webmentions.send(&source_url, &target_url))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            let mut entry = LogEntry {
+                time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs()),
+                article: article_id,
+                source: &source,
+                target: &target,
+                endpoint: None,
+                attempts: attempt,
+                result: "failed",
+                status: None,
+                error: None,
+            };
+            match result {
+                Ok(Outcome::NoEndpoint) => {
+                    debug!("{} has no webmention endpoint", target);
+                    return;
+                }
+                Ok(Outcome::Answered { endpoint, status }) => {
+                    let accepted = (200..300).contains(&status);
+                    if accepted {
+                        info!("Sent webmention from {} to {}", source, target);
+                    } else {
+                        warn!(
+                            "{} rejected the webmention to {}: {}",
+                            endpoint, target, status
+                        );
+                    }
+                    entry.result = if accepted { "accepted" } else { "rejected" };
+                    entry.endpoint = Some(&endpoint);
+                    entry.status = Some(status);
+                    self.log(&entry);
+                    return;
+                }
+                Err(e) if attempt < self.max_attempts => {
+                    debug!(
+                        "Webmention to {} failed (attempt {}), retrying in {:?}: {:#}",
+                        target, attempt, delay, e
+                    );
+                    rt::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => {
+                    warn!(
+                        "Giving up on the webmention to {} after {} attempts: {:#}",
+                        target, attempt, e
+                    );
+                    entry.error = Some(format!("{:#}", e));
+                    self.log(&entry);
+                }
+            }
+        }
+    }
+
+    /// Append an entry to the delivery log.
+    fn log(&self, entry: &LogEntry) {
+        let result = serde_json::to_string(entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut line| {
+                line.push('\n');
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.log_path)?
+                    .write_all(line.as_bytes())?;
+                Ok(())
+            });
+        if let Err(e) = result {
+            error!("Failed to write to {}: {:#}", self.log_path, e);
+        }
+    }
+}
+
+/// The absolute `href`s of the links in rendered HTML.
+fn links(html: &str) -> Vec<String> {
+    html.split("href=\"")
+        .skip(1)
+        .filter_map(|rest| rest.split('"').next())
+        .map(unescape)
+        .collect()
+}
+
+/// The endpoint of a `Link` header with `rel="webmention"`, if it has one.
+fn link_header_endpoint(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let (target, params) = link.trim().strip_prefix('<')?.split_once('>')?;
+        params
+            .split(';')
+            .filter_map(|param| param.trim().split_once('='))
+            .any(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("rel")
+                    && has_webmention_rel(value.trim_matches('"'))
+            })
+            .then(|| target.to_string())
+    })
+}
+
+/// The `href` of the first `<link>` or `<a>` element with `rel="webmention"`.
+fn html_endpoint(html: &str) -> Option<String> {
+    html.split('<').skip(1).find_map(|tag| {
+        let tag = &tag[..tag.find('>')?];
+        let (name, attributes) = tag.split_once(|c: char| c.is_ascii_whitespace())?;
+        if !name.eq_ignore_ascii_case("link") && !name.eq_ignore_ascii_case("a") {
+            return None;
+        }
+        let attributes = parse_attributes(attributes);
+        let attribute = |wanted: &str| {
+            attributes
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+                .map(|(_, value)| value.as_str())
+        };
+        // An empty `href` means the page is its own endpoint
+        attribute("rel")
+            .filter(|rel| has_webmention_rel(rel))
+            .and_then(|_| attribute("href"))
+            .map(unescape)
+    })
+}
+
+fn has_webmention_rel(rel: &str) -> bool {
+    rel.split_ascii_whitespace()
+        .any(|rel| rel.eq_ignore_ascii_case("webmention"))
+}
+
+/// Parse the attributes of an HTML tag, quoted or not.
+fn parse_attributes(mut rest: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        if name_end == 0 {
+            return attributes;
+        }
+        let name = rest[..name_end].trim_end_matches('/').to_string();
+        rest = rest[name_end..].trim_start();
+        let Some(after_equals) = rest.strip_prefix('=') else {
+            attributes.push((name, String::new()));
+            continue;
+        };
+        let after_equals = after_equals.trim_start();
+        let (value, remainder) = match after_equals.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let inner = &after_equals[1..];
+                let end = inner.find(quote).unwrap_or(inner.len());
+                (&inner[..end], inner.get(end + 1..).unwrap_or(""))
+            }
+            _ => {
+                let end = after_equals
+                    .find(|c: char| c.is_ascii_whitespace())
+                    .unwrap_or(after_equals.len());
+                (&after_equals[..end], &after_equals[end..])
+            }
+        };
+        attributes.push((name, value.to_string()));
+        rest = remainder;
+    }
+}
+
+/// Undo the entity escaping of an HTML attribute value.
+fn unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Spawn a background task sending webmentions for every new or changed article.
+pub fn spawn_sender(webmentions: Arc<Webmentions>, articles: Articles) {
+    let mut events = articles.subscribe();
+    rt::spawn(async move {
+        loop {
+            let article_id = match events.recv().await {
+                Ok(ContentEvent::ArticleAdded { id } | ContentEvent::ArticleUpdated { id }) => id,
+                Ok(ContentEvent::ArticleRemoved { id }) => {
+                    webmentions.mentioned.lock().unwrap().remove(&id);
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Webmention sender missed {} content events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let (sender, source) = (Arc::clone(&webmentions), articles.clone());
+            let targets = match web::block(move || sender.targets(&source, article_id)).await {
+                Ok(Ok(targets)) => targets,
+                Ok(Err(e)) => {
+                    error!(
+                        "Failed to find the links of article {}: {:?}",
+                        article_id, e
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    error!("Link extraction task failed: {:?}", e);
+                    continue;
+                }
+            };
+            for target in targets {
+                rt::spawn(Arc::clone(&webmentions).deliver(article_id, target));
+            }
+        }
+    });
+}
@@ -0,0 +1,175 @@
+//! The API key extractors guarding the v1 routes: a missing or unknown key, a key
+//! without the scope a route requires, and the routine operations that stay open
+//! until keys are configured.
+//!
+//! Run with `cargo test --features testing`.
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::{header, StatusCode},
+    test, web, App,
+};
+use henkaiki::{
+    api,
+    articles::Articles,
+    config::{self, ApiKey, ConfigSource, Overrides, Scope},
+    reload::ConfigReloader,
+    testing::ArticleTree,
+};
+use serde_json::Value;
+
+/// Two articles served as the v1 routes would be, with a full key `admin`, a key
+/// `reader` with only the `read` scope and a key `writer` with only the `author`
+/// scope, or no keys at all.
+struct Server {
+    _tree: ArticleTree,
+    articles: web::Data<Articles>,
+    reloader: web::Data<ConfigReloader>,
+}
+
+fn serve(keys: bool) -> Server {
+    let tree = ArticleTree::new().expect("create article tree");
+    tree.generate(2).expect("generate articles");
+    let mut config = tree.config();
+    if keys {
+        config.auth.api_keys = vec![
+            ApiKey::Key("admin".into()),
+            ApiKey::Scoped {
+                key: "reader".into(),
+                scopes: vec![Scope::Read],
+            },
+            ApiKey::Scoped {
+                key: "writer".into(),
+                scopes: vec![Scope::Author],
+            },
+        ];
+    }
+    let shared = config::shared(config);
+    let articles = Articles::builder()
+        .config(shared.clone())
+        .build()
+        .expect("load articles");
+    let source = ConfigSource {
+        path: tree.path().join("henkaiki.toml"),
+        overrides: Overrides::default(),
+    };
+    let reloader = ConfigReloader::new(source, shared, vec![articles.clone()]);
+    Server {
+        _tree: tree,
+        articles: web::Data::new(articles),
+        reloader: web::Data::new(reloader),
+    }
+}
+
+/// Send `req` to the v1 routes of `server`, with `key` as bearer token.
+async fn call(
+    server: &Server,
+    req: test::TestRequest,
+    key: Option<&str>,
+) -> ServiceResponse<impl MessageBody> {
+    let app = test::init_service(
+        App::new()
+            .app_data(server.articles.clone())
+            .app_data(server.reloader.clone())
+            .configure(api::v1::config),
+    )
+    .await;
+    let req = match key {
+        Some(key) => req.insert_header((header::AUTHORIZATION, format!("Bearer {}", key))),
+        None => req,
+    };
+    app.call(req.to_request()).await.expect("call the service")
+}
+
+/// The status of the response, and the `error_code` of its body if it failed.
+async fn outcome(response: ServiceResponse<impl MessageBody>) -> (StatusCode, Option<String>) {
+    let status = response.status();
+    let body: Value = test::read_body_json(response).await;
+    let code = body["error_code"].as_str().map(str::to_string);
+    (status, code)
+}
+
+fn export() -> test::TestRequest {
+    test::TestRequest::get().uri("/api/v1/export")
+}
+
+fn refresh() -> test::TestRequest {
+    test::TestRequest::post().uri("/api/v1/articles/index/refresh")
+}
+
+/// The routes revealing the server's setup, which take an `admin` key.
+fn admin_reads() -> Vec<test::TestRequest> {
+    let mut requests = vec![test::TestRequest::get().uri("/api/v1/admin/config")];
+    if cfg!(feature = "backup") {
+        requests.push(test::TestRequest::get().uri("/api/v1/admin/backup"));
+    }
+    requests
+}
+
+#[actix_web::test]
+async fn missing_or_unknown_key_is_unauthorized() {
+    let server = serve(true);
+    for key in [None, Some("nonsense")] {
+        let (status, code) = outcome(call(&server, export(), key).await).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "key {:?}", key);
+        assert_eq!(code.as_deref(), Some("unauthorized"));
+    }
+}
+
+#[actix_web::test]
+async fn key_without_scope_is_forbidden() {
+    let server = serve(true);
+    let (status, code) = outcome(call(&server, export(), Some("writer")).await).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(code.as_deref(), Some("forbidden"));
+
+    let (status, code) = outcome(call(&server, refresh(), Some("reader")).await).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(code.as_deref(), Some("forbidden"));
+}
+
+#[actix_web::test]
+async fn key_with_scope_is_accepted() {
+    let server = serve(true);
+    for key in ["reader", "admin"] {
+        let response = call(&server, export(), Some(key)).await;
+        assert_eq!(response.status(), StatusCode::OK, "key {}", key);
+    }
+    let response = call(&server, refresh(), Some("admin")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn operations_are_open_only_without_keys() {
+    let server = serve(false);
+    let response = call(&server, refresh(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let server = serve(true);
+    let (status, code) = outcome(call(&server, refresh(), None).await).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(code.as_deref(), Some("unauthorized"));
+}
+
+#[actix_web::test]
+async fn keyed_routes_stay_closed_without_keys() {
+    let server = serve(false);
+    let (status, code) = outcome(call(&server, export(), None).await).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(code.as_deref(), Some("unauthorized"));
+}
+
+#[actix_web::test]
+async fn configuration_and_backup_need_an_admin_key() {
+    let server = serve(true);
+    for req in admin_reads() {
+        let (status, code) = outcome(call(&server, req, Some("reader")).await).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(code.as_deref(), Some("forbidden"));
+    }
+    for req in admin_reads() {
+        let response = call(&server, req, Some("admin")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
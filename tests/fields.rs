@@ -0,0 +1,50 @@
+//! Field selections with `?fields=` on the article routes, which splice the shared
+//! encoding of the content into the response.
+//!
+//! Run with `cargo test --features testing`.
+
+use actix_web::{dev::Service, test, web, App};
+use henkaiki::{api, articles::Articles, testing::ArticleTree};
+use serde_json::{json, Value};
+
+/// Article 1 with an `extra` entry that looks like empty content.
+fn tree() -> (ArticleTree, web::Data<Articles>) {
+    let tree = ArticleTree::new().expect("create article tree");
+    let mut draft = ArticleTree::draft(1);
+    draft.extra.insert("content".into(), json!(""));
+    tree.add(&draft).expect("add article");
+    let articles = tree.articles().expect("load articles");
+    (tree, web::Data::new(articles))
+}
+
+/// The `data` of the response to a GET of `uri` from the v1 and v2 routes.
+async fn get_data(articles: &web::Data<Articles>, uri: &str) -> Value {
+    let app = test::init_service(
+        App::new()
+            .app_data(articles.clone())
+            .configure(api::v1::config)
+            .configure(|cfg| api::v2::config(cfg, "")),
+    )
+    .await;
+    let req = test::TestRequest::get().uri(uri).to_request();
+    let response = app.call(req).await.expect("call the service");
+    let body: Value = test::read_body_json(response).await;
+    body["data"].clone()
+}
+
+#[actix_web::test]
+async fn content_is_only_sent_when_selected() {
+    let (_tree, articles) = tree();
+    for version in ["v1", "v2"] {
+        let uri = format!("/api/{}/articles/1?fields=id,extra", version);
+        let article = get_data(&articles, &uri).await;
+        assert!(article.get("content").is_none(), "GET {}: {}", uri, article);
+        assert_eq!(article["extra"], json!({ "content": "" }), "GET {}", uri);
+
+        let uri = format!("/api/{}/articles/1?fields=content,extra", version);
+        let article = get_data(&articles, &uri).await;
+        let content = article["content"].as_str().expect("the content");
+        assert!(content.contains("The body of article 1."), "GET {}", uri);
+        assert_eq!(article["extra"], json!({ "content": "" }), "GET {}", uri);
+    }
+}
@@ -0,0 +1,261 @@
+//! Private articles: left out of every listing, search, feed and query, yet served
+//! to readers presenting their token or an API key with the `read` scope.
+//!
+//! Run with `cargo test --features testing`.
+
+use actix_web::{
+    dev::{Service, ServiceResponse},
+    http::{header, StatusCode},
+    test, web, App,
+};
+use henkaiki::{
+    api,
+    articles::{Alias, ArticleId, Articles},
+    config::{self, ApiKey, Scope},
+    status::StartTime,
+    testing::ArticleTree,
+};
+use serde_json::Value;
+
+/// The ID of the private article in [`tree`].
+const PRIVATE: ArticleId = 4;
+
+/// The token of the private article.
+const TOKEN: &str = "preview-token";
+
+/// An alias of the private article.
+const ALIAS: &str = "secret-draft";
+
+/// A deleted article succeeded by the private article.
+const DELETED: ArticleId = 6;
+
+/// The articles `1..=3`, the private article 4 sharing the tag `even` with article
+/// 2, the tombstone of article 6 pointing to it, and a key `reader` with only the
+/// `read` scope.
+fn tree() -> (ArticleTree, web::Data<Articles>) {
+    let tree = ArticleTree::new().expect("create article tree");
+    tree.generate(3).expect("generate articles");
+    let mut draft = ArticleTree::draft(PRIVATE);
+    draft.token = Some(TOKEN.into());
+    draft.aliases = vec![Alias::Slug(ALIAS.into())];
+    tree.add(&draft).expect("add private article");
+    tree.add_tombstone(DELETED, Some(PRIVATE))
+        .expect("add tombstone");
+    let articles = serve(&tree);
+    assert!(articles.is_private(PRIVATE));
+    (tree, articles)
+}
+
+fn serve(tree: &ArticleTree) -> web::Data<Articles> {
+    let mut config = tree.config();
+    config.auth.api_keys = vec![ApiKey::Scoped {
+        key: "reader".into(),
+        scopes: vec![Scope::Read],
+    }];
+    let articles = Articles::builder()
+        .config(config::shared(config))
+        .build()
+        .expect("load articles");
+    web::Data::new(articles)
+}
+
+/// Send `req` to the shortlink, v1, v2 and GraphQL routes serving `articles`.
+async fn call(articles: &web::Data<Articles>, req: test::TestRequest) -> ServiceResponse {
+    let app = App::new()
+        .app_data(articles.clone())
+        .app_data(web::Data::new(StartTime::now()))
+        .configure(api::shortlink::config)
+        .configure(api::v1::config)
+        .configure(|cfg| api::v2::config(cfg, ""));
+    #[cfg(feature = "graphql")]
+    let app = app
+        .app_data(web::Data::new(api::graphql::schema()))
+        .configure(api::graphql::config);
+    let app = test::init_service(app).await;
+    app.call(req.to_request()).await.expect("call the service")
+}
+
+/// The `data` of a successful response to a GET of `uri`.
+async fn get_data(articles: &web::Data<Articles>, uri: &str) -> Value {
+    let response = call(articles, test::TestRequest::get().uri(uri)).await;
+    assert_eq!(response.status(), StatusCode::OK, "GET {}", uri);
+    let body: Value = test::read_body_json(response).await;
+    body["data"].clone()
+}
+
+/// The IDs of the articles in a list of summaries.
+fn ids(summaries: &Value) -> Vec<ArticleId> {
+    let summaries = summaries.as_array().expect("a list of articles");
+    summaries
+        .iter()
+        .map(|summary| summary["id"].as_i64().expect("an article ID") as ArticleId)
+        .collect()
+}
+
+#[actix_web::test]
+async fn private_articles_stay_out_of_listings() {
+    let (_tree, articles) = tree();
+    let listed = ids(&get_data(&articles, "/api/v1/articles").await);
+    assert_eq!(listed.len(), 3);
+    assert!(!listed.contains(&PRIVATE));
+
+    let page = get_data(&articles, "/api/v2/articles").await;
+    assert_eq!(page["total"], 3);
+    assert!(!ids(&page["items"]).contains(&PRIVATE));
+
+    let tagged = ids(&get_data(&articles, "/api/v1/articles/tags/even").await);
+    assert_eq!(tagged, vec![2]);
+
+    let page = get_data(&articles, "/api/v2/articles?tag=even").await;
+    assert_eq!(ids(&page["items"]), vec![2]);
+}
+
+#[actix_web::test]
+async fn private_articles_stay_out_of_keywords_and_search() {
+    let (_tree, articles) = tree();
+    let uri = format!("/api/v1/articles/keywords/keyword{}", PRIVATE);
+    assert!(ids(&get_data(&articles, &uri).await).is_empty());
+
+    let found = ids(&get_data(&articles, "/api/v1/articles/search?q=article").await);
+    assert_eq!(found.len(), 3);
+    assert!(!found.contains(&PRIVATE));
+
+    let uri = format!("/api/v1/articles/search?q=keyword{}", PRIVATE);
+    assert!(ids(&get_data(&articles, &uri).await).is_empty());
+}
+
+#[actix_web::test]
+async fn private_articles_stay_out_of_the_status() {
+    let (_tree, articles) = tree();
+    let status = get_data(&articles, "/api/v1/status").await;
+    assert_eq!(status["articles"], 3);
+}
+
+#[actix_web::test]
+async fn private_articles_stay_out_of_the_changes_feed() {
+    let tree = ArticleTree::new().expect("create article tree");
+    tree.generate(3).expect("generate articles");
+    let articles = serve(&tree);
+    let generation = articles.changes_since(0).generation;
+
+    let mut draft = ArticleTree::draft(PRIVATE);
+    draft.token = Some(TOKEN.into());
+    tree.add(&draft).expect("add private article");
+    let public = ArticleTree::draft(5);
+    tree.add(&public).expect("add public article");
+    articles.refresh_index().expect("refresh index");
+
+    let uri = format!("/api/v1/articles/changes?since={}", generation);
+    let changes = get_data(&articles, &uri).await;
+    assert_eq!(changes["added"], serde_json::json!([5]));
+    assert_eq!(changes["updated"], serde_json::json!([]));
+}
+
+#[cfg(feature = "graphql")]
+#[actix_web::test]
+async fn private_articles_stay_out_of_graphql() {
+    let (_tree, articles) = tree();
+    for (id, found) in [(1, true), (PRIVATE, false)] {
+        let query = format!("{{ article(id: {}) {{ id title }} }}", id);
+        let req = test::TestRequest::post()
+            .uri("/api/graphql")
+            .set_json(serde_json::json!({ "query": query }));
+        let body: Value = test::read_body_json(call(&articles, req).await).await;
+        assert_eq!(
+            body["data"]["article"].is_object(),
+            found,
+            "article {}: {}",
+            id,
+            body
+        );
+    }
+}
+
+#[actix_web::test]
+async fn private_articles_are_served_with_their_token() {
+    let (_tree, articles) = tree();
+    for version in ["v1", "v2"] {
+        let uri = format!("/api/{}/articles/{}", version, PRIVATE);
+        let response = call(&articles, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "GET {}", uri);
+
+        let wrong = format!("{}?token=wrong", uri);
+        let response = call(&articles, test::TestRequest::get().uri(&wrong)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "GET {}", wrong);
+
+        let article = get_data(&articles, &format!("{}?token={}", uri, TOKEN)).await;
+        assert_eq!(article["id"], PRIVATE);
+    }
+    let uri = format!("/api/v1/articles/{}/summary?token={}", PRIVATE, TOKEN);
+    assert_eq!(get_data(&articles, &uri).await["id"], PRIVATE);
+}
+
+#[actix_web::test]
+async fn private_articles_are_served_with_a_read_key() {
+    let (_tree, articles) = tree();
+    for (key, expected) in [
+        ("reader", StatusCode::OK),
+        ("nonsense", StatusCode::NOT_FOUND),
+    ] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/articles/{}", PRIVATE))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", key)));
+        let response = call(&articles, req).await;
+        assert_eq!(response.status(), expected, "key {}", key);
+    }
+}
+
+/// The status and body of the response to a GET of `uri`.
+async fn get(articles: &web::Data<Articles>, uri: &str) -> (StatusCode, Value) {
+    let response = call(articles, test::TestRequest::get().uri(uri)).await;
+    let status = response.status();
+    (status, test::read_body_json(response).await)
+}
+
+#[actix_web::test]
+async fn aliases_of_private_articles_are_unknown_without_access() {
+    let (_tree, articles) = tree();
+    for prefix in ["/api/v1/articles", "/api/v2/articles", "/s"] {
+        let unknown = get(&articles, &format!("{}/no-such-alias", prefix)).await;
+        let (status, body) = get(&articles, &format!("{}/{}", prefix, ALIAS)).await;
+        assert_eq!(status, unknown.0, "GET {}/{}", prefix, ALIAS);
+        assert_eq!(body["error_code"], unknown.1["error_code"]);
+        assert!(!body.to_string().contains(&PRIVATE.to_string()), "{}", body);
+    }
+
+    let uri = format!("/api/v1/articles/{}?token={}", ALIAS, TOKEN);
+    let response = call(&articles, test::TestRequest::get().uri(&uri)).await;
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert_eq!(
+        location,
+        format!("/api/v1/articles/{}?token={}", PRIVATE, TOKEN)
+    );
+}
+
+#[actix_web::test]
+async fn private_successors_are_left_out_without_access() {
+    let (_tree, articles) = tree();
+    for prefix in ["/api/v1/articles", "/api/v2/articles", "/s"] {
+        let uri = format!("{}/{}", prefix, DELETED);
+        let response = call(&articles, test::TestRequest::get().uri(&uri)).await;
+        assert_eq!(response.status(), StatusCode::GONE, "GET {}", uri);
+        assert!(!response.headers().contains_key(header::LINK));
+        let body: Value = test::read_body_json(response).await;
+        assert!(!body.to_string().contains(&PRIVATE.to_string()), "{}", body);
+    }
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/articles/{}", DELETED))
+        .insert_header((header::AUTHORIZATION, "Bearer reader"));
+    let response = call(&articles, req).await;
+    assert_eq!(response.status(), StatusCode::GONE);
+    assert!(response.headers().contains_key(header::LINK));
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["data"]["successor"], PRIVATE);
+}
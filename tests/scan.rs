@@ -0,0 +1,78 @@
+//! The scan report of the filesystem store: the warnings of articles whose files
+//! had invalid UTF-8 replaced under `lossy_utf8`.
+//!
+//! Run with `cargo test --features testing`.
+
+use henkaiki::{
+    articles::{ArticleId, Articles},
+    config,
+    testing::ArticleTree,
+};
+
+/// A Markdown body with a byte that is never valid UTF-8.
+const INVALID: &[u8] = b"# Broken\n\nLatin-1: caf\xe9\n";
+
+/// The message of the loaded entry for `id` in the last scan report.
+fn warning(articles: &Articles, id: ArticleId) -> Option<String> {
+    let report = articles.last_scan_report();
+    let entry = report
+        .entries
+        .iter()
+        .find(|entry| entry.id == Some(id))
+        .expect("a report entry");
+    assert_eq!(entry.outcome, "loaded", "article {}", id);
+    entry.message.clone()
+}
+
+#[test]
+fn replaced_utf8_is_flagged_in_the_scan_report() {
+    let tree = ArticleTree::new().expect("create article tree");
+    tree.generate(2).expect("generate articles");
+    tree.write("1/content.md", INVALID).expect("write Markdown");
+    let flat = ArticleTree::draft(3);
+    let path = tree.add_flat(&flat).expect("add flat article");
+    let mut bytes = std::fs::read(&path).expect("read flat article");
+    bytes.extend_from_slice(INVALID);
+    std::fs::write(&path, bytes).expect("write flat article");
+
+    let mut config = tree.config();
+    config.mainconfig.lossy_utf8 = true;
+    let articles = Articles::builder()
+        .config(config::shared(config))
+        .build()
+        .expect("load articles");
+
+    let message = warning(&articles, 1).expect("a warning for article 1");
+    assert!(
+        message.contains("invalid UTF-8 replaced in content.md"),
+        "{}",
+        message
+    );
+    assert!(!message.contains("metainfo.toml"), "{}", message);
+    assert_eq!(warning(&articles, 2), None);
+    let message = warning(&articles, 3).expect("a warning for article 3");
+    assert!(
+        message.contains("invalid UTF-8 replaced in 3.md"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn oversized_markdown_is_not_read_for_the_warning() {
+    let tree = ArticleTree::new().expect("create article tree");
+    tree.generate(1).expect("generate articles");
+    let mut markdown = INVALID.to_vec();
+    markdown.resize(64 * 1024, b'a');
+    tree.write("1/content.md", markdown)
+        .expect("write Markdown");
+
+    let mut config = tree.config();
+    config.mainconfig.lossy_utf8 = true;
+    config.mainconfig.max_article_bytes = 4096;
+    let articles = Articles::builder()
+        .config(config::shared(config))
+        .build()
+        .expect("load articles");
+    assert_eq!(warning(&articles, 1), None);
+}